@@ -98,7 +98,7 @@ where
 {
     solve_circular(
         initial_cash_guess,
-        |cash| {
+        |_cash| {
             // Calculate interest expense based on average debt
             // (debt can change based on cash availability)
             let interest_expense = beginning_debt * interest_rate / dec!(100);
@@ -186,7 +186,6 @@ mod tests {
     #[test]
     fn test_newton_raphson_faster_convergence() {
         let mut iterations_simple = 0;
-        let mut iterations_newton = 0;
 
         // Count iterations for simple method
         let mut current = dec!(10);
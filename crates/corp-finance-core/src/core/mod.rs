@@ -2,4 +2,4 @@ pub mod circular_solver;
 pub mod time_value;
 
 pub use circular_solver::{solve_circular, solve_circular_newton, solve_cash_interest_circular};
-pub use time_value::{calculate_npv, calculate_irr, calculate_xirr, calculate_moic, moic_to_irr_approx};
+pub use time_value::{calculate_npv, calculate_irr, calculate_xirr, calculate_moic, moic_to_irr, moic_to_irr_approx};
@@ -1,5 +1,7 @@
-use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, MathematicalOps};
 use rust_decimal_macros::dec;
+use std::cell::Cell;
 use chrono::NaiveDate;
 use crate::error::{FinanceError, Result};
 use super::circular_solver::solve_circular_newton;
@@ -78,6 +80,9 @@ pub fn calculate_irr(cash_flows: &[Decimal], initial_guess: Decimal) -> Result<D
 /// Calculate XIRR (IRR with irregular time periods)
 /// dates[0] should be the initial investment date
 /// cash_flows[0] should be the initial investment (typically negative)
+///
+/// Newton-Raphson runs from `initial_guess`; if it does not converge the root is
+/// bracketed between -99% and an expanding upper rate and found by bisection.
 pub fn calculate_xirr(
     cash_flows: &[Decimal],
     dates: &[NaiveDate],
@@ -95,39 +100,13 @@ pub fn calculate_xirr(
         ));
     }
 
-    let base_date = dates[0];
-
-    // XNPV function
+    // Newton cannot handle unrepresentable XNPVs; note them and fall back to bisection
+    let overflowed = Cell::new(false);
     let xnpv_fn = |rate: Decimal| -> Decimal {
-        let rate_decimal = rate / dec!(100);
-        cash_flows
-            .iter()
-            .zip(dates.iter())
-            .map(|(&cf, &date)| {
-                let days = (date - base_date).num_days();
-                let years = Decimal::from(days) / dec!(365.25);
-
-                // Discount factor: 1 / (1 + r)^years
-                let mut discount_factor = Decimal::ONE;
-                let one_plus_r = Decimal::ONE + rate_decimal;
-
-                // Approximate (1+r)^years using iteration
-                // For small years, this is accurate enough
-                if years > Decimal::ZERO {
-                    let years_int = years.floor().to_string().parse::<i64>().unwrap_or(0);
-                    for _ in 0..years_int {
-                        discount_factor /= one_plus_r;
-                    }
-                    // Handle fractional year (simplified)
-                    let frac = years - Decimal::from(years_int);
-                    if frac > Decimal::ZERO {
-                        discount_factor /= Decimal::ONE + rate_decimal * frac;
-                    }
-                }
-
-                cf * discount_factor
-            })
-            .sum()
+        xnpv(cash_flows, dates, rate).unwrap_or_else(|| {
+            overflowed.set(true);
+            Decimal::ZERO
+        })
     };
 
     // Derivative (numerical approximation)
@@ -136,15 +115,100 @@ pub fn calculate_xirr(
         (xnpv_fn(rate + delta) - xnpv_fn(rate - delta)) / (dec!(2) * delta)
     };
 
-    let xirr = solve_circular_newton(
+    let newton = solve_circular_newton(
         initial_guess,
         xnpv_fn,
         xnpv_derivative,
         dec!(0.0001),
         100,
-    )?;
+    );
+
+    match newton {
+        Ok(xirr) if xirr > dec!(-100) && !overflowed.get() => Ok(xirr),
+        _ => bisect_xirr(cash_flows, dates),
+    }
+}
+
+/// XNPV at `rate` (a percentage), discounting each flow by (1 + r)^(days / 365.25);
+/// `None` when a discount factor or discounted flow is beyond Decimal's range
+fn xnpv(cash_flows: &[Decimal], dates: &[NaiveDate], rate: Decimal) -> Option<Decimal> {
+    let base_date = dates[0];
+    let one_plus_r = Decimal::ONE + rate / dec!(100);
+
+    cash_flows
+        .iter()
+        .zip(dates.iter())
+        .try_fold(Decimal::ZERO, |total, (&cf, &date)| {
+            let days = (date - base_date).num_days();
+            let years = Decimal::from(days) / dec!(365.25);
+
+            // Discount factor: 1 / (1 + r)^years
+            let mut discount_factor = Decimal::ONE;
+            if years > Decimal::ZERO {
+                let years_int = years.floor().to_i64().unwrap_or(0);
+                for _ in 0..years_int {
+                    discount_factor = discount_factor.checked_div(one_plus_r)?;
+                }
+                // Fractional year compounds too; below -100% there is no real power
+                let frac = years - Decimal::from(years_int);
+                if frac > Decimal::ZERO {
+                    let growth = if one_plus_r > Decimal::ZERO {
+                        one_plus_r.checked_powd(frac)
+                    } else {
+                        None
+                    };
+                    discount_factor = discount_factor
+                        .checked_div(growth.unwrap_or(Decimal::ONE + rate / dec!(100) * frac))?;
+                }
+            }
 
-    Ok(xirr)
+            total.checked_add(cf.checked_mul(discount_factor)?)
+        })
+}
+
+/// Bisection fallback for XIRR: brackets a sign change of XNPV between the lowest
+/// rate from -99% whose discount factors are representable (halving towards 0%)
+/// and an upper rate doubled from 100% up to 1,000,000%
+fn bisect_xirr(cash_flows: &[Decimal], dates: &[NaiveDate]) -> Result<Decimal> {
+    let no_root = || {
+        FinanceError::CalculationError(
+            "XIRR did not converge and no rate brackets a root of the cash flows".to_string(),
+        )
+    };
+
+    // Long-dated flows overflow near -100%, so raise the floor until XNPV fits
+    let mut low = dec!(-99);
+    let low_value = loop {
+        match xnpv(cash_flows, dates, low) {
+            Some(value) => break value,
+            None if low < dec!(-0.01) => low /= dec!(2),
+            None => return Err(no_root()),
+        }
+    };
+
+    let mut high = dec!(100);
+    loop {
+        match xnpv(cash_flows, dates, high) {
+            Some(value) if (value > Decimal::ZERO) != (low_value > Decimal::ZERO) => break,
+            Some(_) if high < dec!(1000000) => high *= dec!(2),
+            _ => return Err(no_root()),
+        }
+    }
+
+    for _ in 0..200 {
+        let mid = (low + high) / dec!(2);
+        let mid_value = xnpv(cash_flows, dates, mid).ok_or_else(no_root)?;
+        if mid_value.abs() < dec!(0.0000001) || high - low < dec!(0.0000001) {
+            return Ok(mid);
+        }
+        if (mid_value > Decimal::ZERO) == (low_value > Decimal::ZERO) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Err(no_root())
 }
 
 /// Calculate Multiple on Invested Capital (MOIC)
@@ -163,9 +227,42 @@ pub fn calculate_moic(invested: Decimal, returned: Decimal) -> Result<Decimal> {
     Ok(returned / invested)
 }
 
-/// Convert MOIC and holding period to approximate IRR
-/// Uses rule of 72 approximation for quick estimates
+/// Convert MOIC and holding period to IRR exactly
+/// IRR = (MOIC^(1/years) - 1) × 100, returned as a percentage
+///
+/// Whole-year holds take the nth root by Newton's method in Decimal precision;
+/// fractional holds fall back to exp(ln(MOIC) / years).
+pub fn moic_to_irr(moic: Decimal, years: Decimal) -> Result<Decimal> {
+    validate_moic_inputs(moic, years)?;
+
+    let root = if years.fract().is_zero() {
+        let n = years.to_i64().ok_or_else(|| {
+            FinanceError::OutOfRange("Years too large for an exact root".to_string())
+        })?;
+        nth_root(moic, n)?
+    } else {
+        (moic.ln() / years).checked_exp().ok_or_else(|| {
+            FinanceError::CalculationError("IRR root overflowed".to_string())
+        })?
+    };
+
+    Ok((root - Decimal::ONE) * dec!(100))
+}
+
+/// Convert MOIC and holding period to a mental-math IRR estimate
+/// Rule of thumb: IRR ≈ (MOIC - 1) / years × 100
+///
+/// This ignores compounding and overstates returns on longer holds (2.5x over
+/// 5 years reads 30% against an exact ~20.1%); use `moic_to_irr` for the exact value.
 pub fn moic_to_irr_approx(moic: Decimal, years: Decimal) -> Result<Decimal> {
+    validate_moic_inputs(moic, years)?;
+
+    let irr_approx = ((moic - Decimal::ONE) / years) * dec!(100);
+
+    Ok(irr_approx)
+}
+
+fn validate_moic_inputs(moic: Decimal, years: Decimal) -> Result<()> {
     if years <= Decimal::ZERO {
         return Err(FinanceError::InvalidInput(
             "Years must be positive".to_string(),
@@ -178,12 +275,34 @@ pub fn moic_to_irr_approx(moic: Decimal, years: Decimal) -> Result<Decimal> {
         ));
     }
 
-    // Approximation: IRR ≈ (MOIC - 1) / years * 100
-    // More accurate: IRR = (MOIC^(1/years) - 1) * 100
-    // We'll use the simple approximation
-    let irr_approx = ((moic - Decimal::ONE) / years) * dec!(100);
+    Ok(())
+}
 
-    Ok(irr_approx)
+/// nth root of a positive value by Newton's method: x' = ((n - 1)x + v / x^(n-1)) / n
+///
+/// Starts from exp(ln(v) / n), which is already close to the root, so x^(n-1)
+/// stays near v^((n-1)/n) and cannot overflow for large multiples. If a step
+/// would still overflow the estimate is returned unrefined.
+fn nth_root(value: Decimal, n: i64) -> Result<Decimal> {
+    if n == 1 {
+        return Ok(value);
+    }
+    let n_dec = Decimal::from(n);
+    let mut x = (value.ln() / n_dec).checked_exp().ok_or_else(|| {
+        FinanceError::CalculationError("IRR root overflowed".to_string())
+    })?;
+    for _ in 0..200 {
+        let power = match x.checked_powi(n - 1) {
+            Some(power) if !power.is_zero() => power,
+            _ => return Ok(x),
+        };
+        let next = ((n_dec - Decimal::ONE) * x + value / power) / n_dec;
+        if (next - x).abs() <= dec!(0.0000000000000000000001) {
+            return Ok(next);
+        }
+        x = next;
+    }
+    Ok(x)
 }
 
 #[cfg(test)]
@@ -238,6 +357,34 @@ mod tests {
         assert!((irr_approx - dec!(30)).abs() < dec!(1));
     }
 
+    #[test]
+    fn test_moic_to_irr_exact() {
+        // 2.5x over 5 years: 2.5^(1/5) - 1 = 20.1124%
+        let irr = moic_to_irr(dec!(2.5), dec!(5)).unwrap();
+        assert!((irr - dec!(20.1124)).abs() < dec!(0.0001));
+
+        // Compounding back recovers the multiple to Decimal precision
+        let compounded = (Decimal::ONE + irr / dec!(100)).powi(5);
+        assert!((compounded - dec!(2.5)).abs() < dec!(0.000000000000001));
+
+        // Fractional hold: 2x over 3.5 years
+        let irr = moic_to_irr(dec!(2), dec!(3.5)).unwrap();
+        assert!((irr - dec!(21.9013)).abs() < dec!(0.001));
+
+        assert!(moic_to_irr(dec!(0), dec!(5)).is_err());
+    }
+
+    #[test]
+    fn test_moic_to_irr_large_multiple() {
+        // 1,000,000x over 10 years: 10^(6/10) - 1 = 298.1072%
+        let irr = moic_to_irr(dec!(1000000), dec!(10)).unwrap();
+        assert!((irr - dec!(298.1072)).abs() < dec!(0.0001));
+
+        // 1,000,000x over 50 years: 10^(6/50) - 1 = 31.8257%
+        let irr = moic_to_irr(dec!(1000000), dec!(50)).unwrap();
+        assert!((irr - dec!(31.8257)).abs() < dec!(0.0001));
+    }
+
     #[test]
     fn test_xirr() {
         use chrono::NaiveDate;
@@ -260,4 +407,20 @@ mod tests {
         assert!(xirr_val > dec!(0));
         assert!(xirr_val < dec!(50));
     }
+
+    #[test]
+    fn test_xirr_long_dated_fallback() {
+        use chrono::NaiveDate;
+
+        // A wild guess sends Newton astray; bisection must not overflow at -99% over 20 years
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+        ];
+        let xirr = calculate_xirr(&[dec!(-100), dec!(500)], &dates, dec!(100000)).unwrap();
+
+        // 5x over exactly 20 years: 5^(1/20) - 1
+        let expected = moic_to_irr(dec!(5), dec!(20)).unwrap();
+        assert!((xirr - expected).abs() < dec!(0.001));
+    }
 }
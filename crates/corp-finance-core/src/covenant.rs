@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use crate::error::Result;
-use crate::types::{CovenantInput, CovenantOutput, CovenantResult, CovenantTest};
+use crate::types::{CovenantInput, CovenantOutput, CovenantResult};
 
 /// Check compliance with debt covenants
 /// Supports maximum, minimum, and range-based covenants
@@ -65,6 +65,7 @@ pub fn check_covenant_compliance(input: CovenantInput) -> Result<CovenantOutput>
 mod tests {
     use super::*;
     use rust_decimal_macros::dec;
+    use crate::types::CovenantTest;
 
     #[test]
     fn test_covenant_compliance() {
@@ -1,290 +1,274 @@
 use rust_decimal::Decimal;
-use crate::error::Result;
-use super::types::{AccountingFlowInput, AccountingFlowOutput, AccountingImpact};
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::journal::{balance_change, find_transaction, post_transaction, statement_impacts};
+use super::types::{
+    Account, AccountingFlowInput, AccountingFlowOutput, AccountingScenarioInput, AccountingScenarioOutput,
+    AccountingStep, BalanceSheet, BalanceSheetLine, JournalLine,
+};
 
 /// Analyze impact of a transaction on all three financial statements
 /// "Walk me through" questions - classic interview format
+///
+/// The transaction is posted as a balanced double-entry journal entry (from the
+/// standard transaction library, or the caller's own lines for "custom") and the
+/// income statement, balance sheet and cash flow impacts are derived from it.
 pub fn analyze_accounting_flow(input: AccountingFlowInput) -> Result<AccountingFlowOutput> {
-    let mut income_statement_impact = Vec::new();
-    let mut balance_sheet_impact = Vec::new();
-    let mut cash_flow_impact = Vec::new();
-    let explanation;
-
-    match input.transaction_type.as_str() {
-        "depreciation" => {
-            explanation = format!(
-                "Depreciation of ${} is a non-cash expense that reduces net income but doesn't affect cash.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Depreciation Expense".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "PP&E (Accumulated Depreciation)".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Retained Earnings".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Add: Depreciation".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Cash from Operations".to_string(),
-                impact: Decimal::ZERO,
-                sign: "neutral".to_string(),
-            });
-        }
+    let posted = post_transaction(&input)?;
+    let journal_entries = posted.lines;
+    let impacts = statement_impacts(&journal_entries);
+
+    let description = find_transaction(&input.transaction_type)
+        .map(|rule| rule.description)
+        .unwrap_or("custom journal entry");
+    let mut explanation = format!("{} of ${}: {}.", input.transaction, input.amount, description);
+    if !posted.tax.current_tax.is_zero() || !posted.tax.deferred_tax.is_zero() {
+        explanation.push_str(&format!(
+            " Pre-tax income {}; at a {}% tax rate current tax {} and deferred tax {}.",
+            describe_change(posted.tax.pre_tax_income),
+            input.tax_rate.unwrap_or(Decimal::ZERO),
+            describe_tax(posted.tax.current_tax, "paid", "saved"),
+            describe_tax(posted.tax.deferred_tax, "expense", "benefit")
+        ));
+    }
+    explanation.push_str(&format!(
+        " Net income {}; cash {}.",
+        describe_change(impacts.net_income),
+        describe_change(impacts.cash_change)
+    ));
 
-        "amortization" => {
-            explanation = format!(
-                "Amortization of ${} reduces net income but is added back in cash flow as a non-cash expense.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Amortization Expense".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Intangible Assets".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Retained Earnings".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Add: Amortization".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-        }
+    Ok(AccountingFlowOutput {
+        transaction: input.transaction,
+        amount: input.amount,
+        journal_entries,
+        income_statement_impact: impacts.income_statement,
+        balance_sheet_impact: impacts.balance_sheet,
+        cash_flow_impact: impacts.cash_flow,
+        pre_tax_income_impact: posted.tax.pre_tax_income,
+        current_tax: posted.tax.current_tax,
+        deferred_tax: posted.tax.deferred_tax,
+        net_income_impact: impacts.net_income,
+        cash_impact: impacts.cash_change,
+        explanation,
+    })
+}
 
-        "capex" => {
-            explanation = format!(
-                "CapEx of ${} increases PP&E and reduces cash. It's an investing activity with no immediate P&L impact.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "No immediate impact".to_string(),
-                impact: Decimal::ZERO,
-                sign: "neutral".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "PP&E".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Cash".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "CapEx (Investing Activities)".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-        }
+const ASSET_ACCOUNTS: [Account; 8] = [
+    Account::Cash,
+    Account::AccountsReceivable,
+    Account::Inventory,
+    Account::PrepaidExpenses,
+    Account::Ppe,
+    Account::Intangibles,
+    Account::RightOfUseAsset,
+    Account::DeferredTaxAsset,
+];
+
+const LIABILITY_ACCOUNTS: [Account; 7] = [
+    Account::AccountsPayable,
+    Account::AccruedLiabilities,
+    Account::DeferredRevenue,
+    Account::TaxPayable,
+    Account::Debt,
+    Account::LeaseLiability,
+    Account::DeferredTaxLiability,
+];
+
+/// Balance sheet positions tracked while transactions are applied
+#[derive(Clone)]
+struct Position {
+    assets: Vec<Decimal>,
+    liabilities: Vec<Decimal>,
+    equity_investments: Decimal,
+    equity: Decimal,
+    noncontrolling_interest: Decimal,
+}
 
-        "debt_issuance" => {
-            explanation = format!(
-                "Issuing ${} of debt increases cash and debt on balance sheet. No P&L impact, but future interest will affect earnings.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "No immediate impact".to_string(),
-                impact: Decimal::ZERO,
-                sign: "neutral".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Cash".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Debt".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Debt Issuance (Financing Activities)".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
+impl Position {
+    fn from_balance_sheet(bs: &BalanceSheet) -> Self {
+        let asset = |account| match account {
+            Account::Cash => bs.cash,
+            Account::AccountsReceivable => bs.accounts_receivable,
+            Account::Inventory => bs.inventory,
+            Account::Ppe => bs.ppe_net,
+            Account::Intangibles => bs.intangibles,
+            Account::DeferredTaxAsset => bs.deferred_tax_asset,
+            _ => Decimal::ZERO,
+        };
+        let liability = |account| match account {
+            Account::AccountsPayable => bs.accounts_payable,
+            Account::Debt => bs.debt,
+            Account::DeferredTaxLiability => bs.deferred_tax_liability,
+            _ => Decimal::ZERO,
+        };
+        Position {
+            assets: ASSET_ACCOUNTS.iter().map(|&account| asset(account)).collect(),
+            liabilities: LIABILITY_ACCOUNTS.iter().map(|&account| liability(account)).collect(),
+            equity_investments: bs.equity_investments,
+            equity: bs.equity,
+            noncontrolling_interest: bs.noncontrolling_interest,
         }
+    }
 
-        "debt_repayment" => {
-            explanation = format!(
-                "Repaying ${} of debt reduces both cash and debt. No P&L impact, but reduces future interest expense.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "No immediate impact".to_string(),
-                impact: Decimal::ZERO,
-                sign: "neutral".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Cash".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Debt".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Debt Repayment (Financing Activities)".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
+    fn post(&mut self, lines: &[JournalLine], net_income: Decimal) {
+        for (balance, &account) in self.assets.iter_mut().zip(ASSET_ACCOUNTS.iter()) {
+            *balance += balance_change(lines, account);
         }
-
-        "inventory_purchase" => {
-            explanation = format!(
-                "Purchasing ${} of inventory increases inventory and reduces cash. No P&L impact until sold.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "No immediate impact".to_string(),
-                impact: Decimal::ZERO,
-                sign: "neutral".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Inventory".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Cash".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Change in NWC - Inventory".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
+        for (balance, &account) in self.liabilities.iter_mut().zip(LIABILITY_ACCOUNTS.iter()) {
+            *balance += balance_change(lines, account);
         }
+        self.equity += balance_change(lines, Account::PaidInCapital)
+            + balance_change(lines, Account::RetainedEarnings)
+            + net_income;
+    }
 
-        "revenue_recognition" => {
-            explanation = format!(
-                "Recognizing ${} of revenue increases A/R and revenue. Cash collected later.",
-                input.amount
-            );
-
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Revenue".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            income_statement_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Accounts Receivable".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            balance_sheet_impact.push(AccountingImpact {
-                line_item: "Retained Earnings".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Net Income".to_string(),
-                impact: input.amount,
-                sign: "positive".to_string(),
-            });
-            cash_flow_impact.push(AccountingImpact {
-                line_item: "Change in NWC - A/R Increase".to_string(),
-                impact: input.amount,
-                sign: "negative".to_string(),
-            });
-        }
+    fn total_assets(&self) -> Decimal {
+        self.assets.iter().sum::<Decimal>() + self.equity_investments
+    }
 
-        _ => {
-            explanation = format!(
-                "Transaction type '{}' not recognized. Supported types: depreciation, amortization, capex, debt_issuance, debt_repayment, inventory_purchase, revenue_recognition",
-                input.transaction_type
-            );
-        }
+    fn total_liabilities(&self) -> Decimal {
+        self.liabilities.iter().sum()
     }
 
-    Ok(AccountingFlowOutput {
-        transaction: input.transaction,
-        amount: input.amount,
-        income_statement_impact,
-        balance_sheet_impact,
-        cash_flow_impact,
-        explanation,
+    fn total_liabilities_equity(&self) -> Decimal {
+        self.total_liabilities() + self.equity + self.noncontrolling_interest
+    }
+
+    fn lines(&self) -> Vec<(&'static str, Decimal)> {
+        let mut lines: Vec<(&'static str, Decimal)> = ASSET_ACCOUNTS
+            .iter()
+            .map(|account| account.name())
+            .zip(self.assets.iter().copied())
+            .collect();
+        lines.push(("Equity Investments", self.equity_investments));
+        lines.push(("Total Assets", self.total_assets()));
+        lines.extend(
+            LIABILITY_ACCOUNTS
+                .iter()
+                .map(|account| account.name())
+                .zip(self.liabilities.iter().copied()),
+        );
+        lines.push(("Total Liabilities", self.total_liabilities()));
+        lines.push(("Shareholders' Equity", self.equity));
+        lines.push(("Noncontrolling Interest", self.noncontrolling_interest));
+        lines.push(("Total Liabilities & Equity", self.total_liabilities_equity()));
+        lines
+    }
+}
+
+fn compare(before: &Position, after: &Position) -> Vec<BalanceSheetLine> {
+    before
+        .lines()
+        .into_iter()
+        .zip(after.lines())
+        .map(|((line_item, before), (_, after))| BalanceSheetLine {
+            line_item: line_item.to_string(),
+            before,
+            after,
+            change: after - before,
+        })
+        .collect()
+}
+
+/// Apply a sequence of transactions to a starting balance sheet
+///
+/// Each transaction is posted in turn and the balance sheet shown before and
+/// after it, with a check that assets still equal liabilities and equity. Net
+/// income flows into shareholders' equity. Accounts the starting balance sheet
+/// does not carry (e.g. prepaid expenses, lease liabilities) start at zero.
+pub fn apply_accounting_flows(input: AccountingScenarioInput) -> Result<AccountingScenarioOutput> {
+    let tolerance = dec!(0.01);
+    let start = Position::from_balance_sheet(&input.starting_balance_sheet);
+    if (start.total_assets() - start.total_liabilities_equity()).abs() > tolerance {
+        return Err(FinanceError::InvalidInput(format!(
+            "Starting balance sheet does not balance: assets {} vs liabilities and equity {}",
+            start.total_assets(),
+            start.total_liabilities_equity()
+        )));
+    }
+
+    let mut position = start.clone();
+    let mut steps = Vec::new();
+    let mut all_lines = Vec::new();
+    for transaction in &input.transactions {
+        let journal_entries = post_transaction(transaction)?.lines;
+        let impacts = statement_impacts(&journal_entries);
+
+        let before = position.clone();
+        position.post(&journal_entries, impacts.net_income);
+
+        let total_assets = position.total_assets();
+        let total_liabilities_equity = position.total_liabilities_equity();
+        steps.push(AccountingStep {
+            transaction: transaction.transaction.clone(),
+            balance_sheet: compare(&before, &position),
+            net_income_impact: impacts.net_income,
+            cash_impact: impacts.cash_change,
+            total_assets,
+            total_liabilities_equity,
+            balanced: (total_assets - total_liabilities_equity).abs() <= tolerance,
+            journal_entries: journal_entries.clone(),
+        });
+        all_lines.extend(journal_entries);
+    }
+
+    let cumulative = statement_impacts(&all_lines);
+    Ok(AccountingScenarioOutput {
+        balanced: steps.iter().all(|step| step.balanced),
+        balance_sheet: compare(&start, &position),
+        income_statement: cumulative.income_statement,
+        cash_flow: cumulative.cash_flow,
+        net_income: cumulative.net_income,
+        net_change_in_cash: cumulative.cash_change,
+        steps,
     })
 }
 
+fn describe_tax(amount: Decimal, positive: &str, negative: &str) -> String {
+    if amount < Decimal::ZERO {
+        format!("${} {}", -amount, negative)
+    } else if amount > Decimal::ZERO {
+        format!("${} {}", amount, positive)
+    } else {
+        "nil".to_string()
+    }
+}
+
+fn describe_change(amount: Decimal) -> String {
+    if amount > Decimal::ZERO {
+        format!("increases by ${}", amount)
+    } else if amount < Decimal::ZERO {
+        format!("decreases by ${}", -amount)
+    } else {
+        "is unchanged".to_string()
+    }
+}
+
+/// Pre-tax flow with no optional detail, shared by the accounting flow and journal tests
+#[cfg(test)]
+pub(crate) fn flow(transaction_type: &str, amount: Decimal) -> AccountingFlowInput {
+    AccountingFlowInput {
+        transaction: transaction_type.to_string(),
+        amount,
+        transaction_type: transaction_type.to_string(),
+        carrying_value: None,
+        interest_portion: None,
+        journal_entries: None,
+        tax_rate: None,
+        taxable_amount: None,
+        permanent_difference: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rust_decimal_macros::dec;
 
     #[test]
     fn test_depreciation_flow() {
         let input = AccountingFlowInput {
             transaction: "Annual depreciation".to_string(),
-            amount: dec!(100),
-            transaction_type: "depreciation".to_string(),
+            ..flow("depreciation", dec!(100))
         };
 
         let result = analyze_accounting_flow(input).unwrap();
@@ -304,8 +288,7 @@ mod tests {
     fn test_capex_flow() {
         let input = AccountingFlowInput {
             transaction: "Purchase equipment".to_string(),
-            amount: dec!(500),
-            transaction_type: "capex".to_string(),
+            ..flow("capex", dec!(500))
         };
 
         let result = analyze_accounting_flow(input).unwrap();
@@ -321,8 +304,7 @@ mod tests {
     fn test_debt_issuance() {
         let input = AccountingFlowInput {
             transaction: "Issue bonds".to_string(),
-            amount: dec!(1000),
-            transaction_type: "debt_issuance".to_string(),
+            ..flow("debt_issuance", dec!(1000))
         };
 
         let result = analyze_accounting_flow(input).unwrap();
@@ -334,4 +316,121 @@ mod tests {
         assert_eq!(result.balance_sheet_impact.len(), 2);
         assert_eq!(result.cash_flow_impact.len(), 1);
     }
+
+    #[test]
+    fn test_deferred_revenue_cycle() {
+        let collect = AccountingFlowInput {
+            transaction: "Annual subscription billed upfront".to_string(),
+            ..flow("deferred_revenue", dec!(120))
+        };
+        let result = analyze_accounting_flow(collect).unwrap();
+        assert_eq!(result.net_income_impact, Decimal::ZERO);
+        assert_eq!(result.cash_impact, dec!(120));
+
+        let recognise = AccountingFlowInput {
+            transaction: "Month of service delivered".to_string(),
+            ..flow("deferred_revenue_recognition", dec!(10))
+        };
+        let result = analyze_accounting_flow(recognise).unwrap();
+        assert_eq!(result.net_income_impact, dec!(10));
+        assert_eq!(result.cash_impact, Decimal::ZERO);
+        assert_eq!(result.journal_entries.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_transaction_type_errors() {
+        let input = AccountingFlowInput {
+            transaction: "Mystery".to_string(),
+            ..flow("mystery", dec!(10))
+        };
+        assert!(analyze_accounting_flow(input).is_err());
+    }
+
+    fn starting_balance_sheet() -> BalanceSheet {
+        BalanceSheet {
+            cash: dec!(100),
+            accounts_receivable: dec!(50),
+            inventory: dec!(40),
+            ppe_net: dec!(300),
+            intangibles: dec!(60),
+            equity_investments: dec!(0),
+            deferred_tax_asset: dec!(0),
+            total_assets: dec!(550),
+            accounts_payable: dec!(30),
+            debt: dec!(200),
+            deferred_tax_liability: dec!(0),
+            equity: dec!(320),
+            noncontrolling_interest: dec!(0),
+            total_liabilities_equity: dec!(550),
+        }
+    }
+
+    #[test]
+    fn test_apply_sequence_to_balance_sheet() {
+        let input = AccountingScenarioInput {
+            starting_balance_sheet: starting_balance_sheet(),
+            transactions: vec![
+                flow("debt_issuance", dec!(100)),
+                flow("capex", dec!(80)),
+                flow("depreciation", dec!(20)),
+                flow("prepaid_expense", dec!(12)),
+            ],
+        };
+
+        let result = apply_accounting_flows(input).unwrap();
+
+        assert!(result.balanced);
+        assert_eq!(result.steps.len(), 4);
+
+        let line = |lines: &[BalanceSheetLine], name: &str| {
+            lines.iter().find(|line| line.line_item == name).unwrap().clone()
+        };
+
+        // Capex step: cash 200 → 120, PP&E 300 → 380
+        let capex_cash = line(&result.steps[1].balance_sheet, "Cash");
+        assert_eq!(capex_cash.before, dec!(200));
+        assert_eq!(capex_cash.after, dec!(120));
+
+        // Ending position
+        assert_eq!(line(&result.balance_sheet, "Cash").after, dec!(108));
+        assert_eq!(line(&result.balance_sheet, "PP&E").after, dec!(360));
+        assert_eq!(line(&result.balance_sheet, "Prepaid Expenses").after, dec!(12));
+        assert_eq!(line(&result.balance_sheet, "Shareholders' Equity").change, dec!(-20));
+        assert_eq!(line(&result.balance_sheet, "Total Assets").after, dec!(630));
+        assert_eq!(result.net_income, dec!(-20));
+        assert_eq!(result.net_change_in_cash, dec!(8));
+    }
+
+    #[test]
+    fn test_unbalanced_starting_balance_sheet_rejected() {
+        let mut bs = starting_balance_sheet();
+        bs.cash = dec!(90);
+        let input = AccountingScenarioInput {
+            starting_balance_sheet: bs,
+            transactions: vec![],
+        };
+        assert!(apply_accounting_flows(input).is_err());
+    }
+
+    #[test]
+    fn test_depreciation_after_tax() {
+        let input = AccountingFlowInput {
+            transaction: "Annual depreciation".to_string(),
+            tax_rate: Some(dec!(40)),
+            ..flow("depreciation", dec!(100))
+        };
+
+        let result = analyze_accounting_flow(input).unwrap();
+
+        // Classic answer: NI down 60, cash up 40 from the tax shield, PP&E down 100
+        assert_eq!(result.pre_tax_income_impact, dec!(-100));
+        assert_eq!(result.current_tax, dec!(-40));
+        assert_eq!(result.net_income_impact, dec!(-60));
+        assert_eq!(result.cash_impact, dec!(40));
+        let cfo = result.cash_flow_impact.iter()
+            .find(|i| i.line_item == "Cash from Operations")
+            .unwrap();
+        assert_eq!(cfo.impact, dec!(40));
+        assert!(result.explanation.contains("$40 saved"));
+    }
 }
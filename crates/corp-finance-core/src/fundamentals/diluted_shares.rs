@@ -1,7 +1,14 @@
+use std::cell::RefCell;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use crate::error::Result;
-use super::types::{DilutedSharesInput, DilutedSharesOutput, DilutionItem};
+use crate::core::solve_circular;
+use crate::error::{FinanceError, Result};
+use chrono::NaiveDate;
+use crate::valuation::{price_option, OptionPricingInput, OptionType};
+use super::types::{
+    ConvertibleSettlement, DilutedSharesInput, DilutedSharesOutput, DilutionItem, DilutionIteration,
+    MakeWholeTable, OptionGrant, OptionGrantValue, OptionValuationAssumptions, OptionValueDilution,
+};
 
 /// Calculate fully diluted shares using treasury stock method
 ///
@@ -12,8 +19,303 @@ use super::types::{DilutedSharesInput, DilutedSharesOutput, DilutionItem};
 ///
 /// RSUs: Add full count (already granted, no strike price)
 ///
-/// Convertibles: Add shares from conversion if economical
+/// Convertibles: Add shares from conversion if economical, either all shares
+/// (if-converted) or only the value above principal (net share settlement).
+/// Make-whole shares are added when a make-whole event date is given, and a
+/// call spread offsets shares delivered between its lower strike and cap.
+///
+/// Warrants: Treasury stock method on the shares delivered per warrant, with the
+/// exercise price paid per warrant (not per share delivered)
+///
+/// PSUs: Target units × achievement × probability of vesting
+///
+/// In `exercisable_only` mode unvested options are left out, unless the grant
+/// accelerates and the deal is flagged as a change of control.
+///
+/// With `option_valuation` assumptions, a fair-value dilution is reported alongside
+/// the TSM result: unvested options are valued with Black-Scholes-Merton or a
+/// binomial tree while vested options stay on TSM.
+///
+/// When `equity_purchase_price` is given, the per-share price is not known up
+/// front: it is the total equity value divided by diluted shares, which in turn
+/// depend on the price. The two are solved together by fixed-point iteration.
 pub fn calculate_diluted_shares(input: DilutedSharesInput) -> Result<DilutedSharesOutput> {
+    validate_price(&input)?;
+    validate_instruments(&input)?;
+
+    let mut output = match input.equity_purchase_price {
+        Some(equity_value) => solve_price_from_equity_value(&input, equity_value)?,
+        None => dilute_at_price(&input, input.stock_price),
+    };
+
+    if let Some(assumptions) = &input.option_valuation {
+        let share_price = output.implied_share_price.unwrap_or(input.stock_price);
+        output.option_value_dilution = Some(value_options(&input, assumptions, share_price, &output)?);
+    }
+
+    Ok(output)
+}
+
+/// Fair-value alternative to TSM for unvested options
+///
+/// Vested options (and unvested ones accelerating on a change of control) stay on
+/// the treasury stock method. The unvested remainder is valued with an option
+/// pricing model and converted to shares at the share price, replacing whatever
+/// TSM dilution those options contributed (none in `exercisable_only` mode).
+fn value_options(
+    input: &DilutedSharesInput,
+    assumptions: &OptionValuationAssumptions,
+    share_price: Decimal,
+    tsm: &DilutedSharesOutput,
+) -> Result<OptionValueDilution> {
+    if share_price <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "A positive share price is required to value options".to_string(),
+        ));
+    }
+
+    let exercisable_only = input.exercisable_only.unwrap_or(false);
+    let mut grants = Vec::new();
+    let mut model = String::new();
+    let mut tsm_dilution_replaced = Decimal::ZERO;
+    for option in &input.options {
+        let unvested = option.quantity - option.vested_quantity.unwrap_or(option.quantity);
+        if unvested <= Decimal::ZERO || accelerates(input, option) {
+            continue;
+        }
+        if !exercisable_only {
+            tsm_dilution_replaced += treasury_stock_dilution(unvested, option.strike_price, share_price);
+        }
+
+        let time_to_expiry = option.time_to_expiry.unwrap_or(assumptions.time_to_expiry);
+        let priced = price_option(OptionPricingInput {
+            option_type: OptionType::Call,
+            spot_price: share_price,
+            strike_price: option.strike_price,
+            time_to_expiry,
+            risk_free_rate: assumptions.risk_free_rate,
+            volatility: assumptions.volatility,
+            dividend_yield: assumptions.dividend_yield,
+            exercise_style: assumptions.exercise_style,
+            binomial_steps: assumptions.binomial_steps,
+        })?;
+        model = priced.model;
+
+        grants.push(OptionGrantValue {
+            quantity: unvested,
+            strike_price: option.strike_price,
+            time_to_expiry,
+            value_per_option: priced.option_value,
+            total_value: priced.option_value * unvested,
+        });
+    }
+
+    let total_option_value: Decimal = grants.iter().map(|grant| grant.total_value).sum();
+    let equivalent_shares = total_option_value / share_price;
+
+    Ok(OptionValueDilution {
+        model,
+        grants,
+        total_option_value,
+        equivalent_shares,
+        tsm_dilution_replaced,
+        fully_diluted_shares: tsm.fully_diluted_shares - tsm_dilution_replaced + equivalent_shares,
+    })
+}
+
+/// Unvested options vest at closing when the grant accelerates and the deal is a change of control
+fn accelerates(input: &DilutedSharesInput, option: &OptionGrant) -> bool {
+    input.change_of_control.unwrap_or(false)
+        && option.accelerates_on_change_of_control.unwrap_or(false)
+        && option.vested_quantity.is_some_and(|vested| vested < option.quantity)
+}
+
+/// Net new shares from exercising in-the-money options and buying back with the proceeds
+fn treasury_stock_dilution(quantity: Decimal, strike_price: Decimal, stock_price: Decimal) -> Decimal {
+    if quantity > Decimal::ZERO && stock_price > strike_price {
+        quantity - quantity * strike_price / stock_price
+    } else {
+        Decimal::ZERO
+    }
+}
+
+/// Solve price = equity value / diluted shares(price), recording each iteration
+fn solve_price_from_equity_value(input: &DilutedSharesInput, equity_value: Decimal) -> Result<DilutedSharesOutput> {
+    if equity_value <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "equity_purchase_price must be positive".to_string(),
+        ));
+    }
+    if input.basic_shares <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "basic_shares must be positive to derive a share price".to_string(),
+        ));
+    }
+
+    let path = RefCell::new(Vec::new());
+    let share_price = solve_circular(
+        equity_value / input.basic_shares,
+        |price| {
+            let fully_diluted_shares = dilute_at_price(input, price).fully_diluted_shares;
+            let mut path = path.borrow_mut();
+            let iteration = path.len() as u32 + 1;
+            path.push(DilutionIteration {
+                iteration,
+                share_price: price,
+                fully_diluted_shares,
+            });
+            equity_value / fully_diluted_shares
+        },
+        dec!(0.000001),
+        100,
+    )?;
+
+    // Record the converged state so the path ends at the implied price
+    let mut output = dilute_at_price(input, share_price);
+    let mut path = path.into_inner();
+    path.push(DilutionIteration {
+        iteration: path.len() as u32 + 1,
+        share_price,
+        fully_diluted_shares: output.fully_diluted_shares,
+    });
+    output.implied_share_price = Some(share_price);
+    output.convergence_path = Some(path);
+    Ok(output)
+}
+
+/// Exactly one of a positive `stock_price` or an `equity_purchase_price` sets the price
+fn validate_price(input: &DilutedSharesInput) -> Result<()> {
+    match (input.stock_price > Decimal::ZERO, input.equity_purchase_price.is_some()) {
+        (true, true) => Err(FinanceError::InvalidInput(
+            "Give either stock_price or equity_purchase_price, not both".to_string(),
+        )),
+        (false, false) => Err(FinanceError::MissingField(
+            "A positive stock_price or an equity_purchase_price is required".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_instruments(input: &DilutedSharesInput) -> Result<()> {
+    for option in &input.options {
+        if let Some(vested) = option.vested_quantity {
+            if vested < Decimal::ZERO || vested > option.quantity {
+                return Err(FinanceError::OutOfRange(format!(
+                    "Vested quantity {} must be between 0 and the grant quantity {}",
+                    vested, option.quantity
+                )));
+            }
+        }
+    }
+    for convertible in &input.convertibles {
+        if convertible.conversion_price <= Decimal::ZERO {
+            return Err(FinanceError::InvalidInput(
+                "Convertible conversion_price must be positive".to_string(),
+            ));
+        }
+        if let Some(table) = &convertible.make_whole {
+            validate_make_whole(table)?;
+            if convertible.make_whole_date.is_none() {
+                return Err(FinanceError::MissingField(
+                    "make_whole_date is required with a make-whole table".to_string(),
+                ));
+            }
+        }
+        if let Some(spread) = &convertible.call_spread {
+            let lower_strike = spread.lower_strike.unwrap_or(convertible.conversion_price);
+            if spread.cap_price <= lower_strike {
+                return Err(FinanceError::InvalidInput(format!(
+                    "Call spread cap {} must be above the lower strike {}",
+                    spread.cap_price, lower_strike
+                )));
+            }
+        }
+    }
+    for warrant in input.warrants.iter().flatten() {
+        if warrant.shares_per_warrant.is_some_and(|ratio| ratio <= Decimal::ZERO) {
+            return Err(FinanceError::InvalidInput(
+                "shares_per_warrant must be positive".to_string(),
+            ));
+        }
+    }
+    for psu in input.psus.iter().flatten() {
+        if psu.achievement_percent < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(
+                "PSU achievement_percent cannot be negative".to_string(),
+            ));
+        }
+        if let Some(probability) = psu.probability_percent {
+            if probability < Decimal::ZERO || probability > dec!(100) {
+                return Err(FinanceError::OutOfRange(
+                    "PSU probability_percent must be between 0 and 100".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_make_whole(table: &MakeWholeTable) -> Result<()> {
+    if table.stock_prices.is_empty() || table.dates.is_empty() {
+        return Err(FinanceError::InvalidInput(
+            "Make-whole table needs at least one stock price and one date".to_string(),
+        ));
+    }
+    if table.stock_prices.windows(2).any(|pair| pair[1] <= pair[0])
+        || table.dates.windows(2).any(|pair| pair[1] <= pair[0])
+    {
+        return Err(FinanceError::InvalidInput(
+            "Make-whole stock prices and dates must be strictly ascending".to_string(),
+        ));
+    }
+    if table.additional_shares.len() != table.dates.len()
+        || table.additional_shares.iter().any(|row| row.len() != table.stock_prices.len())
+    {
+        return Err(FinanceError::InvalidInput(format!(
+            "Make-whole table must have {} rows (dates) of {} values (stock prices)",
+            table.dates.len(),
+            table.stock_prices.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Additional shares per 1,000 principal, interpolated linearly in price and date
+///
+/// No additional shares are due outside the table's price range; dates outside
+/// the table use the first or last row.
+fn make_whole_shares(table: &MakeWholeTable, stock_price: Decimal, date: NaiveDate) -> Decimal {
+    let prices = &table.stock_prices;
+    if stock_price < prices[0] || stock_price > prices[prices.len() - 1] {
+        return Decimal::ZERO;
+    }
+
+    let at_price = |row: &[Decimal]| {
+        if prices.len() == 1 {
+            return row[0];
+        }
+        let i = prices.windows(2).position(|pair| stock_price <= pair[1]).unwrap_or(0);
+        let weight = (stock_price - prices[i]) / (prices[i + 1] - prices[i]);
+        row[i] + (row[i + 1] - row[i]) * weight
+    };
+
+    let dates = &table.dates;
+    let rows = &table.additional_shares;
+    if date <= dates[0] {
+        return at_price(&rows[0]);
+    }
+    if date >= dates[dates.len() - 1] {
+        return at_price(&rows[rows.len() - 1]);
+    }
+    let j = dates.windows(2).position(|pair| date < pair[1]).unwrap_or(0);
+    let elapsed = Decimal::from((date - dates[j]).num_days());
+    let span = Decimal::from((dates[j + 1] - dates[j]).num_days());
+    let earlier = at_price(&rows[j]);
+    let later = at_price(&rows[j + 1]);
+    earlier + (later - earlier) * elapsed / span
+}
+
+fn dilute_at_price(input: &DilutedSharesInput, stock_price: Decimal) -> DilutedSharesOutput {
     let basic_shares = input.basic_shares;
     let mut breakdown = Vec::new();
 
@@ -25,20 +327,57 @@ pub fn calculate_diluted_shares(input: DilutedSharesInput) -> Result<DilutedShar
     });
 
     // Calculate options dilution using treasury stock method
+    let exercisable_only = input.exercisable_only.unwrap_or(false);
     let mut options_dilution = Decimal::ZERO;
+    let mut unvested_options_excluded = Decimal::ZERO;
     for option in &input.options {
-        if input.stock_price > option.strike_price {
+        let vested = option.vested_quantity.unwrap_or(option.quantity);
+        let unvested = option.quantity - vested;
+        let accelerated = accelerates(input, option);
+
+        let quantity = if exercisable_only && !accelerated {
+            unvested_options_excluded += unvested;
+            vested
+        } else {
+            option.quantity
+        };
+
+        if quantity > Decimal::ZERO && stock_price > option.strike_price {
             // In-the-money options
-            let proceeds = option.quantity * option.strike_price;
-            let shares_repurchased = proceeds / input.stock_price;
-            let net_dilution = option.quantity - shares_repurchased;
+            let net_dilution = treasury_stock_dilution(quantity, option.strike_price, stock_price);
 
             options_dilution += net_dilution;
 
             breakdown.push(DilutionItem {
                 source: format!(
                     "Options @ ${} ({} shares)",
-                    option.strike_price, option.quantity
+                    option.strike_price, quantity
+                ),
+                shares: net_dilution,
+                method: if accelerated {
+                    "Treasury Stock Method (unvested accelerated)".to_string()
+                } else {
+                    "Treasury Stock Method".to_string()
+                },
+            });
+        }
+    }
+
+    // Warrants - treasury stock method on the shares each warrant delivers
+    let mut warrants_dilution = Decimal::ZERO;
+    for warrant in input.warrants.iter().flatten() {
+        let shares_issued = warrant.quantity * warrant.shares_per_warrant.unwrap_or(Decimal::ONE);
+        // exercise_price is paid per warrant, however many shares each one delivers
+        let proceeds = warrant.quantity * warrant.exercise_price;
+
+        if shares_issued > Decimal::ZERO && stock_price * shares_issued > proceeds {
+            let net_dilution = shares_issued - proceeds / stock_price;
+            warrants_dilution += net_dilution;
+
+            breakdown.push(DilutionItem {
+                source: format!(
+                    "Warrants @ ${} per warrant ({} shares)",
+                    warrant.exercise_price, shares_issued
                 ),
                 shares: net_dilution,
                 method: "Treasury Stock Method".to_string(),
@@ -46,6 +385,25 @@ pub fn calculate_diluted_shares(input: DilutedSharesInput) -> Result<DilutedShar
         }
     }
 
+    // PSUs - expected units earned
+    let mut psu_dilution = Decimal::ZERO;
+    for psu in input.psus.iter().flatten() {
+        let probability = psu.probability_percent.unwrap_or(dec!(100));
+        let shares = psu.target_units * psu.achievement_percent / dec!(100) * probability / dec!(100);
+        if shares > Decimal::ZERO {
+            psu_dilution += shares;
+
+            breakdown.push(DilutionItem {
+                source: format!("PSUs ({} target units)", psu.target_units),
+                shares,
+                method: format!(
+                    "{}% achievement × {}% probability",
+                    psu.achievement_percent, probability
+                ),
+            });
+        }
+    }
+
     // RSUs - fully dilutive (no strike price)
     let rsu_dilution = input.rsus;
     if rsu_dilution > Decimal::ZERO {
@@ -58,27 +416,94 @@ pub fn calculate_diluted_shares(input: DilutedSharesInput) -> Result<DilutedShar
 
     // Convertibles - if conversion is economical
     let mut convertibles_dilution = Decimal::ZERO;
+    let mut call_spread_offset = Decimal::ZERO;
     for convertible in &input.convertibles {
-        let shares_on_conversion = convertible.principal / convertible.conversion_price;
+        let source = format!(
+            "Convertible ${} @ ${}",
+            convertible.principal, convertible.conversion_price
+        );
+        let base_shares = convertible.principal / convertible.conversion_price;
+        let make_whole = match (&convertible.make_whole, convertible.make_whole_date) {
+            (Some(table), Some(date)) => {
+                make_whole_shares(table, stock_price, date) * convertible.principal / dec!(1000)
+            }
+            _ => Decimal::ZERO,
+        };
+        let shares_on_conversion = base_shares + make_whole;
+        let conversion_value = shares_on_conversion * stock_price;
 
         // Check if conversion is economical
-        if input.stock_price > convertible.conversion_price {
-            convertibles_dilution += shares_on_conversion;
+        if stock_price <= Decimal::ZERO || conversion_value <= convertible.principal {
+            continue;
+        }
 
+        let settlement = convertible.settlement.unwrap_or(ConvertibleSettlement::IfConverted);
+        let shares_issued = match settlement {
+            ConvertibleSettlement::IfConverted => {
+                breakdown.push(DilutionItem {
+                    source: source.clone(),
+                    shares: base_shares,
+                    method: "If-converted method".to_string(),
+                });
+                shares_on_conversion
+            }
+            ConvertibleSettlement::NetShare => {
+                let shares = (conversion_value - convertible.principal) / stock_price;
+                breakdown.push(DilutionItem {
+                    source: source.clone(),
+                    shares: shares - make_whole * shares / shares_on_conversion,
+                    method: format!(
+                        "Net share settlement (${} principal in cash, excess value in shares)",
+                        convertible.principal
+                    ),
+                });
+                shares
+            }
+        };
+
+        if make_whole > Decimal::ZERO {
+            let make_whole_issued = match settlement {
+                ConvertibleSettlement::IfConverted => make_whole,
+                ConvertibleSettlement::NetShare => make_whole * shares_issued / shares_on_conversion,
+            };
             breakdown.push(DilutionItem {
-                source: format!(
-                    "Convertible ${} @ ${}",
-                    convertible.principal, convertible.conversion_price
+                source: format!("{} make-whole", source),
+                shares: make_whole_issued,
+                method: format!(
+                    "Make-whole table: {} additional shares per 1,000 principal",
+                    make_whole * dec!(1000) / convertible.principal
                 ),
-                shares: shares_on_conversion,
-                method: "If-converted method".to_string(),
             });
         }
+        convertibles_dilution += shares_issued;
+
+        if let Some(spread) = &convertible.call_spread {
+            let lower_strike = spread.lower_strike.unwrap_or(convertible.conversion_price);
+            if stock_price > lower_strike {
+                // Counterparty delivers the value between lower strike and cap in shares
+                let offset = (base_shares * (stock_price.min(spread.cap_price) - lower_strike) / stock_price)
+                    .min(shares_issued);
+                call_spread_offset += offset;
+                breakdown.push(DilutionItem {
+                    source: format!("{} call spread", source),
+                    shares: -offset,
+                    method: format!(
+                        "Capped call ${} to ${} offsets shares delivered",
+                        lower_strike, spread.cap_price
+                    ),
+                });
+            }
+        }
     }
 
     // Calculate totals
-    let fully_diluted_shares =
-        basic_shares + options_dilution + rsu_dilution + convertibles_dilution;
+    let fully_diluted_shares = basic_shares
+        + options_dilution
+        + rsu_dilution
+        + convertibles_dilution
+        - call_spread_offset
+        + warrants_dilution
+        + psu_dilution;
 
     let dilution_percentage = if basic_shares > Decimal::ZERO {
         ((fully_diluted_shares - basic_shares) / basic_shares) * dec!(100)
@@ -86,22 +511,29 @@ pub fn calculate_diluted_shares(input: DilutedSharesInput) -> Result<DilutedShar
         Decimal::ZERO
     };
 
-    Ok(DilutedSharesOutput {
+    DilutedSharesOutput {
         basic_shares,
         options_dilution,
         rsu_dilution,
         convertibles_dilution,
+        call_spread_offset,
+        warrants_dilution,
+        psu_dilution,
+        unvested_options_excluded,
         fully_diluted_shares,
         dilution_percentage,
         breakdown,
-    })
+        implied_share_price: None,
+        convergence_path: None,
+        option_value_dilution: None,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use rust_decimal_macros::dec;
-    use super::super::types::{OptionGrant, Convertible};
+    use super::super::types::{Convertible, Warrant, PerformanceShareUnit, CallSpread};
 
     #[test]
     fn test_treasury_stock_method() {
@@ -112,10 +544,19 @@ mod tests {
                 OptionGrant {
                     quantity: dec!(10),
                     strike_price: dec!(30),
+                    vested_quantity: None,
+                    accelerates_on_change_of_control: None,
+                    time_to_expiry: None,
                 },
             ],
             rsus: dec!(0),
             convertibles: vec![],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
         };
 
         let result = calculate_diluted_shares(input).unwrap();
@@ -137,10 +578,19 @@ mod tests {
                 OptionGrant {
                     quantity: dec!(10),
                     strike_price: dec!(30),
+                    vested_quantity: None,
+                    accelerates_on_change_of_control: None,
+                    time_to_expiry: None,
                 },
             ],
             rsus: dec!(0),
             convertibles: vec![],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
         };
 
         let result = calculate_diluted_shares(input).unwrap();
@@ -158,6 +608,12 @@ mod tests {
             options: vec![],
             rsus: dec!(5),
             convertibles: vec![],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
         };
 
         let result = calculate_diluted_shares(input).unwrap();
@@ -178,8 +634,18 @@ mod tests {
                 Convertible {
                     principal: dec!(1000),
                     conversion_price: dec!(40),
+                    settlement: None,
+                    make_whole: None,
+                    make_whole_date: None,
+                    call_spread: None,
                 },
             ],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
         };
 
         let result = calculate_diluted_shares(input).unwrap();
@@ -198,10 +664,16 @@ mod tests {
                 OptionGrant {
                     quantity: dec!(50),
                     strike_price: dec!(60),
+                    vested_quantity: None,
+                    accelerates_on_change_of_control: None,
+                    time_to_expiry: None,
                 },
                 OptionGrant {
                     quantity: dec!(30),
                     strike_price: dec!(80),
+                    vested_quantity: None,
+                    accelerates_on_change_of_control: None,
+                    time_to_expiry: None,
                 },
             ],
             rsus: dec!(20),
@@ -209,8 +681,18 @@ mod tests {
                 Convertible {
                     principal: dec!(5000),
                     conversion_price: dec!(90),
+                    settlement: None,
+                    make_whole: None,
+                    make_whole_date: None,
+                    call_spread: None,
                 },
             ],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
         };
 
         let result = calculate_diluted_shares(input).unwrap();
@@ -232,4 +714,329 @@ mod tests {
         assert!(result.fully_diluted_shares > dec!(1101));
         assert!(result.fully_diluted_shares < dec!(1102));
     }
+
+    #[test]
+    fn test_price_solved_from_equity_purchase_price() {
+        let input = DilutedSharesInput {
+            basic_shares: dec!(100),
+            stock_price: dec!(0),
+            options: vec![
+                OptionGrant {
+                    quantity: dec!(10),
+                    strike_price: dec!(30),
+                    vested_quantity: None,
+                    accelerates_on_change_of_control: None,
+                    time_to_expiry: None,
+                },
+            ],
+            rsus: dec!(5),
+            convertibles: vec![],
+            equity_purchase_price: Some(dec!(5700)),
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
+        };
+
+        let result = calculate_diluted_shares(input).unwrap();
+
+        // With the option in the money: P × (100 + 5 + 10) − 10 × 30 = 5700 → P = 52.1739...
+        let price = result.implied_share_price.unwrap();
+        assert!((price - dec!(6000) / dec!(115)).abs() < dec!(0.0001));
+        assert!((price * result.fully_diluted_shares - dec!(5700)).abs() < dec!(0.001));
+
+        // Path starts from equity value / basic shares and moves towards the solution
+        let path = result.convergence_path.unwrap();
+        assert_eq!(path[0].share_price, dec!(57));
+        assert!(path.len() > 1);
+
+        // ...and ends at the converged price and share count
+        let last = path.last().unwrap();
+        assert_eq!(last.share_price, price);
+        assert_eq!(last.fully_diluted_shares, result.fully_diluted_shares);
+    }
+
+    #[test]
+    fn test_non_positive_equity_purchase_price_rejected() {
+        let input = DilutedSharesInput {
+            basic_shares: dec!(100),
+            stock_price: dec!(0),
+            options: vec![],
+            rsus: dec!(0),
+            convertibles: vec![],
+            equity_purchase_price: Some(dec!(0)),
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
+        };
+
+        assert!(calculate_diluted_shares(input).is_err());
+    }
+
+    #[test]
+    fn test_price_must_come_from_exactly_one_input() {
+        let input = DilutedSharesInput {
+            basic_shares: dec!(100),
+            stock_price: dec!(0),
+            options: vec![],
+            rsus: dec!(5),
+            convertibles: vec![],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
+        };
+
+        let missing = calculate_diluted_shares(input.clone()).unwrap_err();
+        assert!(matches!(missing, FinanceError::MissingField(_)));
+
+        let both = DilutedSharesInput {
+            stock_price: dec!(50),
+            equity_purchase_price: Some(dec!(5000)),
+            ..input
+        };
+        assert!(matches!(calculate_diluted_shares(both), Err(FinanceError::InvalidInput(_))));
+    }
+
+    fn proxy_input() -> DilutedSharesInput {
+        DilutedSharesInput {
+            basic_shares: dec!(1000),
+            stock_price: dec!(50),
+            options: vec![
+                OptionGrant {
+                    quantity: dec!(40),
+                    strike_price: dec!(25),
+                    vested_quantity: Some(dec!(30)),
+                    accelerates_on_change_of_control: Some(true),
+                    time_to_expiry: None,
+                },
+                OptionGrant {
+                    quantity: dec!(20),
+                    strike_price: dec!(40),
+                    vested_quantity: Some(dec!(10)),
+                    accelerates_on_change_of_control: Some(false),
+                    time_to_expiry: None,
+                },
+            ],
+            rsus: dec!(0),
+            convertibles: vec![],
+            equity_purchase_price: None,
+            warrants: Some(vec![Warrant {
+                quantity: dec!(10),
+                exercise_price: dec!(60),
+                shares_per_warrant: Some(dec!(2)),
+            }]),
+            psus: Some(vec![PerformanceShareUnit {
+                target_units: dec!(20),
+                achievement_percent: dec!(150),
+                probability_percent: Some(dec!(50)),
+            }]),
+            exercisable_only: Some(true),
+            change_of_control: None,
+            option_valuation: None,
+        }
+    }
+
+    #[test]
+    fn test_exercisable_only_excludes_unvested() {
+        let result = calculate_diluted_shares(proxy_input()).unwrap();
+
+        // Vested only: 30 − 30 × 25 / 50 = 15, plus 10 − 10 × 40 / 50 = 2
+        assert_eq!(result.options_dilution, dec!(17));
+        assert_eq!(result.unvested_options_excluded, dec!(20));
+
+        // Warrants: 20 shares for 600 proceeds → 20 − 12 = 8
+        assert_eq!(result.warrants_dilution, dec!(8));
+
+        // PSUs: 20 × 150% × 50% = 15
+        assert_eq!(result.psu_dilution, dec!(15));
+        assert_eq!(result.fully_diluted_shares, dec!(1040));
+    }
+
+    #[test]
+    fn test_warrant_exercise_price_is_per_warrant() {
+        let warrant_input = |stock_price: Decimal| DilutedSharesInput {
+            stock_price,
+            options: vec![],
+            psus: None,
+            ..proxy_input()
+        };
+
+        // 60 per warrant for 2 shares is 30 per share: out of the money at 29
+        let below = calculate_diluted_shares(warrant_input(dec!(29))).unwrap();
+        assert_eq!(below.warrants_dilution, Decimal::ZERO);
+
+        // At 40: 20 shares issued, 600 of proceeds buys back 15
+        let above = calculate_diluted_shares(warrant_input(dec!(40))).unwrap();
+        assert_eq!(above.warrants_dilution, dec!(5));
+    }
+
+    #[test]
+    fn test_change_of_control_accelerates_flagged_grants() {
+        let input = DilutedSharesInput {
+            change_of_control: Some(true),
+            ..proxy_input()
+        };
+        let result = calculate_diluted_shares(input).unwrap();
+
+        // First grant fully accelerated: 40 − 20 = 20; second grant still vested only: 2
+        assert_eq!(result.options_dilution, dec!(22));
+        assert_eq!(result.unvested_options_excluded, dec!(10));
+        assert!(result.breakdown.iter().any(|item| item.method.contains("accelerated")));
+    }
+
+    #[test]
+    fn test_invalid_vested_quantity_rejected() {
+        let mut input = proxy_input();
+        input.options[0].vested_quantity = Some(dec!(50));
+        assert!(calculate_diluted_shares(input).is_err());
+    }
+
+    fn convertible_input(stock_price: Decimal, convertible: Convertible) -> DilutedSharesInput {
+        DilutedSharesInput {
+            basic_shares: dec!(1000),
+            stock_price,
+            options: vec![],
+            rsus: dec!(0),
+            convertibles: vec![convertible],
+            equity_purchase_price: None,
+            warrants: None,
+            psus: None,
+            exercisable_only: None,
+            change_of_control: None,
+            option_valuation: None,
+        }
+    }
+
+    fn convertible(settlement: ConvertibleSettlement) -> Convertible {
+        Convertible {
+            principal: dec!(1000),
+            conversion_price: dec!(40),
+            settlement: Some(settlement),
+            make_whole: None,
+            make_whole_date: None,
+            call_spread: None,
+        }
+    }
+
+    #[test]
+    fn test_net_share_settlement() {
+        let input = convertible_input(dec!(50), convertible(ConvertibleSettlement::NetShare));
+        let result = calculate_diluted_shares(input).unwrap();
+
+        // 25 shares worth 1,250; 1,000 paid in cash, 250 / 50 = 5 shares
+        assert_eq!(result.convertibles_dilution, dec!(5));
+        assert!(result.breakdown[1].method.contains("Net share settlement"));
+    }
+
+    #[test]
+    fn test_make_whole_interpolation() {
+        let mut bond = convertible(ConvertibleSettlement::IfConverted);
+        bond.make_whole = Some(MakeWholeTable {
+            stock_prices: vec![dec!(40), dec!(60)],
+            dates: vec![
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            ],
+            additional_shares: vec![vec![dec!(5), dec!(3)], vec![dec!(3), dec!(1)]],
+        });
+        bond.make_whole_date = Some(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+
+        // At 50 on the first date: halfway between 5 and 3 = 4 per 1,000
+        let result = calculate_diluted_shares(convertible_input(dec!(50), bond.clone())).unwrap();
+        assert_eq!(result.convertibles_dilution, dec!(29));
+
+        // Above the table's highest price no make-whole shares are due
+        let result = calculate_diluted_shares(convertible_input(dec!(70), bond.clone())).unwrap();
+        assert_eq!(result.convertibles_dilution, dec!(25));
+
+        // Missing event date is rejected
+        bond.make_whole_date = None;
+        assert!(calculate_diluted_shares(convertible_input(dec!(50), bond)).is_err());
+    }
+
+    #[test]
+    fn test_call_spread_offsets_until_cap() {
+        let mut bond = convertible(ConvertibleSettlement::NetShare);
+        bond.call_spread = Some(CallSpread {
+            lower_strike: None,
+            cap_price: dec!(60),
+        });
+
+        // Below the cap the capped call fully offsets net shares
+        let result = calculate_diluted_shares(convertible_input(dec!(50), bond.clone())).unwrap();
+        assert_eq!(result.call_spread_offset, dec!(5));
+        assert_eq!(result.fully_diluted_shares, dec!(1000));
+
+        // Above the cap: 25 × (80 − 40) / 80 = 12.5 issued, 25 × 20 / 80 = 6.25 offset
+        let result = calculate_diluted_shares(convertible_input(dec!(80), bond)).unwrap();
+        assert_eq!(result.convertibles_dilution, dec!(12.5));
+        assert_eq!(result.call_spread_offset, dec!(6.25));
+        assert_eq!(result.fully_diluted_shares, dec!(1006.25));
+    }
+
+    #[test]
+    fn test_option_value_dilution_alongside_tsm() {
+        let assumptions = OptionValuationAssumptions {
+            risk_free_rate: dec!(5),
+            volatility: dec!(20),
+            dividend_yield: None,
+            time_to_expiry: dec!(1),
+            exercise_style: None,
+            binomial_steps: None,
+        };
+
+        // Exercisable only: TSM covers the 40 vested options, fair value the 20 unvested
+        let input = DilutedSharesInput {
+            option_valuation: Some(assumptions.clone()),
+            ..proxy_input()
+        };
+        let result = calculate_diluted_shares(input).unwrap();
+        assert_eq!(result.options_dilution, dec!(17));
+
+        let fair_value = result.option_value_dilution.unwrap();
+        assert_eq!(fair_value.model, "Black-Scholes-Merton");
+        assert_eq!(fair_value.grants.len(), 2);
+        assert_eq!(fair_value.grants[0].quantity, dec!(10));
+        assert_eq!(fair_value.grants[1].quantity, dec!(10));
+        assert!(fair_value.grants[0].value_per_option > dec!(25));
+        assert!(fair_value.grants[1].value_per_option > dec!(10));
+        assert_eq!(fair_value.equivalent_shares, fair_value.total_option_value / dec!(50));
+        assert_eq!(fair_value.tsm_dilution_replaced, Decimal::ZERO);
+        assert_eq!(
+            fair_value.fully_diluted_shares,
+            result.fully_diluted_shares + fair_value.equivalent_shares
+        );
+
+        // All options on TSM: the unvested options' TSM dilution (5 + 2) is swapped for fair value
+        let input = DilutedSharesInput {
+            exercisable_only: None,
+            option_valuation: Some(assumptions.clone()),
+            ..proxy_input()
+        };
+        let result = calculate_diluted_shares(input).unwrap();
+        assert_eq!(result.options_dilution, dec!(24));
+
+        let fair_value = result.option_value_dilution.unwrap();
+        assert_eq!(fair_value.tsm_dilution_replaced, dec!(7));
+        assert_eq!(
+            fair_value.fully_diluted_shares,
+            result.fully_diluted_shares - dec!(7) + fair_value.equivalent_shares
+        );
+
+        // Change of control: the first grant accelerates and stays on TSM
+        let input = DilutedSharesInput {
+            change_of_control: Some(true),
+            option_valuation: Some(assumptions),
+            ..proxy_input()
+        };
+        let fair_value = calculate_diluted_shares(input).unwrap().option_value_dilution.unwrap();
+        assert_eq!(fair_value.grants.len(), 1);
+        assert_eq!(fair_value.grants[0].strike_price, dec!(40));
+    }
 }
@@ -1,6 +1,11 @@
 use rust_decimal::Decimal;
-use crate::error::Result;
-use super::types::{EquityEnterpriseInput, EquityEnterpriseOutput, BridgeItem};
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::types::{
+    EquityEnterpriseInput, EquityEnterpriseOutput, BridgeItem, BridgeItemDirection, BridgeDirection,
+    BridgeAdjustment, BridgeAdjustmentResult, BridgeAdjustmentType, AdjustmentTreatment,
+    ValuationBasis, LeaseStandard, ConvertibleTreatment, RoundTripCheck,
+};
 
 /// Calculate Enterprise Value from Equity Value or vice versa
 ///
@@ -9,132 +14,294 @@ use super::types::{EquityEnterpriseInput, EquityEnterpriseOutput, BridgeItem};
 ///
 /// Enterprise Value → Equity Value:
 ///   Equity Value = EV - Debt + Cash - Minority Interest - Preferred Stock + Associates
+///
+/// Optional typed `adjustments` extend the bridge. Debt-like items are added to
+/// equity value to reach EV; cash-like items are deducted:
+///   - operating_lease: debt-like under IFRS 16; excluded under ASC 842 (lease cost stays in EBITDA)
+///   - pension_deficit: debt-like, net of tax
+///   - convertible_debt: debt-like, or excluded when treated as equity (count the shares instead)
+///   - contingent_consideration, other_debt_like: debt-like
+///   - trapped_cash: debt-like reversal of cash that is not freely available (`cash` is
+///     assumed to include it); with a tax rate only the repatriation tax cost is deducted
+///   - non_operating_assets, other_cash_like: cash-like
+///   - tax_assets: cash-like; with a tax rate the value is treated as losses and tax-effected
+///
+/// With `verify_roundtrip`, the result is run back through the bridge in the reverse
+/// direction and must reproduce the input value (within 0.01), otherwise an error is returned.
 pub fn equity_enterprise_bridge(input: EquityEnterpriseInput) -> Result<EquityEnterpriseOutput> {
+    let mut output = run_bridge(&input)?;
+
+    if input.verify_roundtrip.unwrap_or(false) {
+        let reverse_direction = input.direction.reversed();
+        let reverse_value = match input.direction {
+            BridgeDirection::EquityToEv => output.enterprise_value,
+            BridgeDirection::EvToEquity => output.equity_value,
+        };
+        let reverse = run_bridge(&EquityEnterpriseInput {
+            direction: reverse_direction,
+            value: reverse_value,
+            verify_roundtrip: None,
+            ..input.clone()
+        })?;
+        let reverse_result = match reverse_direction {
+            BridgeDirection::EquityToEv => reverse.enterprise_value,
+            BridgeDirection::EvToEquity => reverse.equity_value,
+        };
+        let difference = reverse_result - input.value;
+
+        if difference.abs() >= dec!(0.01) {
+            return Err(FinanceError::CalculationError(format!(
+                "Bridge round-trip mismatch: started at {}, reversed to {}",
+                input.value, reverse_result
+            )));
+        }
+
+        output.roundtrip = Some(RoundTripCheck {
+            reverse_direction,
+            reverse_result,
+            difference,
+        });
+    }
+
+    Ok(output)
+}
+
+fn run_bridge(input: &EquityEnterpriseInput) -> Result<EquityEnterpriseOutput> {
     let net_debt = input.debt - input.cash;
     let mut bridge_items = Vec::new();
 
-    let (equity_value, enterprise_value) = match input.direction.as_str() {
-        "equity_to_ev" => {
+    let adjustments = input
+        .adjustments
+        .iter()
+        .flatten()
+        .map(resolve_adjustment)
+        .collect::<Result<Vec<_>>>()?;
+    let total_debt_like_items: Decimal = adjustments
+        .iter()
+        .filter(|a| a.treatment == AdjustmentTreatment::DebtLike)
+        .map(|a| a.bridge_amount)
+        .sum();
+    let total_cash_like_items: Decimal = adjustments
+        .iter()
+        .filter(|a| a.treatment == AdjustmentTreatment::CashLike)
+        .map(|a| a.bridge_amount)
+        .sum();
+    let net_adjustments = total_debt_like_items - total_cash_like_items;
+
+    let (equity_value, enterprise_value) = match input.direction {
+        BridgeDirection::EquityToEv => {
             let equity_value = input.value;
 
             // Build bridge from equity to EV
             bridge_items.push(BridgeItem {
                 item: "Equity Value".to_string(),
                 amount: equity_value,
-                direction: "start".to_string(),
+                direction: BridgeItemDirection::Start,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Add: Debt".to_string(),
                 amount: input.debt,
-                direction: "add".to_string(),
+                direction: BridgeItemDirection::Add,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Less: Cash".to_string(),
                 amount: input.cash,
-                direction: "subtract".to_string(),
+                direction: BridgeItemDirection::Subtract,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Add: Minority Interest".to_string(),
                 amount: input.minority_interest,
-                direction: "add".to_string(),
+                direction: BridgeItemDirection::Add,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Add: Preferred Stock".to_string(),
                 amount: input.preferred_stock,
-                direction: "add".to_string(),
+                direction: BridgeItemDirection::Add,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Less: Associates/Investments".to_string(),
                 amount: input.associates,
-                direction: "subtract".to_string(),
+                direction: BridgeItemDirection::Subtract,
             });
 
+            for adjustment in adjustments.iter().filter(|a| a.treatment != AdjustmentTreatment::Excluded) {
+                let add = adjustment.treatment == AdjustmentTreatment::DebtLike;
+                bridge_items.push(BridgeItem {
+                    item: format!("{}: {}", if add { "Add" } else { "Less" }, adjustment.name),
+                    amount: adjustment.bridge_amount,
+                    direction: if add { BridgeItemDirection::Add } else { BridgeItemDirection::Subtract },
+                });
+            }
+
             let enterprise_value = equity_value
                 + input.debt
                 - input.cash
                 + input.minority_interest
                 + input.preferred_stock
-                - input.associates;
+                - input.associates
+                + net_adjustments;
 
             bridge_items.push(BridgeItem {
                 item: "Enterprise Value".to_string(),
                 amount: enterprise_value,
-                direction: "end".to_string(),
+                direction: BridgeItemDirection::End,
             });
 
             (equity_value, enterprise_value)
         }
-        "ev_to_equity" => {
+        BridgeDirection::EvToEquity => {
             let enterprise_value = input.value;
 
             bridge_items.push(BridgeItem {
                 item: "Enterprise Value".to_string(),
                 amount: enterprise_value,
-                direction: "start".to_string(),
+                direction: BridgeItemDirection::Start,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Less: Debt".to_string(),
                 amount: input.debt,
-                direction: "subtract".to_string(),
+                direction: BridgeItemDirection::Subtract,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Add: Cash".to_string(),
                 amount: input.cash,
-                direction: "add".to_string(),
+                direction: BridgeItemDirection::Add,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Less: Minority Interest".to_string(),
                 amount: input.minority_interest,
-                direction: "subtract".to_string(),
+                direction: BridgeItemDirection::Subtract,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Less: Preferred Stock".to_string(),
                 amount: input.preferred_stock,
-                direction: "subtract".to_string(),
+                direction: BridgeItemDirection::Subtract,
             });
 
             bridge_items.push(BridgeItem {
                 item: "Add: Associates/Investments".to_string(),
                 amount: input.associates,
-                direction: "add".to_string(),
+                direction: BridgeItemDirection::Add,
             });
 
+            for adjustment in adjustments.iter().filter(|a| a.treatment != AdjustmentTreatment::Excluded) {
+                let subtract = adjustment.treatment == AdjustmentTreatment::DebtLike;
+                bridge_items.push(BridgeItem {
+                    item: format!("{}: {}", if subtract { "Less" } else { "Add" }, adjustment.name),
+                    amount: adjustment.bridge_amount,
+                    direction: if subtract { BridgeItemDirection::Subtract } else { BridgeItemDirection::Add },
+                });
+            }
+
             let equity_value = enterprise_value
                 - input.debt
                 + input.cash
                 - input.minority_interest
                 - input.preferred_stock
-                + input.associates;
+                + input.associates
+                - net_adjustments;
 
             bridge_items.push(BridgeItem {
                 item: "Equity Value".to_string(),
                 amount: equity_value,
-                direction: "end".to_string(),
+                direction: BridgeItemDirection::End,
             });
 
             (equity_value, enterprise_value)
         }
-        _ => {
-            return Err(crate::error::FinanceError::InvalidInput(
-                "direction must be 'equity_to_ev' or 'ev_to_equity'".to_string()
-            ));
-        }
     };
 
     Ok(EquityEnterpriseOutput {
         equity_value,
         enterprise_value,
         net_debt,
+        total_debt_like_items,
+        total_cash_like_items,
+        adjustments,
         bridge_items,
+        roundtrip: None,
+    })
+}
+
+/// Apply valuation basis, tax effects and the sign convention for one adjustment
+fn resolve_adjustment(adjustment: &BridgeAdjustment) -> Result<BridgeAdjustmentResult> {
+    let valuation_basis = adjustment.valuation_basis.unwrap_or(ValuationBasis::Book);
+    let gross_value = match valuation_basis {
+        ValuationBasis::Book => adjustment.book_value,
+        ValuationBasis::Market => adjustment.market_value.ok_or_else(|| {
+            FinanceError::MissingField(format!("market_value for '{}'", adjustment.name))
+        })?,
+    };
+
+    use AdjustmentTreatment::{CashLike, DebtLike, Excluded};
+    let (bridge_amount, treatment, note) = match adjustment.adjustment_type {
+        BridgeAdjustmentType::OperatingLease => match adjustment.lease_standard.unwrap_or(LeaseStandard::Ifrs16) {
+            LeaseStandard::Ifrs16 => (gross_value, DebtLike, "IFRS 16 lease liability treated as debt".to_string()),
+            LeaseStandard::Asc842 => (
+                Decimal::ZERO,
+                Excluded,
+                "ASC 842 operating lease excluded; lease cost remains in EBITDA".to_string(),
+            ),
+        },
+        BridgeAdjustmentType::PensionDeficit => {
+            let rate = adjustment.tax_rate.unwrap_or(Decimal::ZERO);
+            (
+                gross_value * (Decimal::ONE - rate / dec!(100)),
+                DebtLike,
+                format!("Unfunded pension deficit net of {}% tax", rate),
+            )
+        }
+        BridgeAdjustmentType::ConvertibleDebt => {
+            match adjustment.convertible_treatment.unwrap_or(ConvertibleTreatment::Debt) {
+                ConvertibleTreatment::Debt => (gross_value, DebtLike, "Convertible treated as debt".to_string()),
+                ConvertibleTreatment::Equity => (
+                    Decimal::ZERO,
+                    Excluded,
+                    "Convertible treated as equity; include conversion shares in diluted count".to_string(),
+                ),
+            }
+        }
+        BridgeAdjustmentType::ContingentConsideration => {
+            (gross_value, DebtLike, "Contingent consideration (earn-out)".to_string())
+        }
+        BridgeAdjustmentType::OtherDebtLike => (gross_value, DebtLike, "Other debt-like item".to_string()),
+        BridgeAdjustmentType::TrappedCash => match adjustment.tax_rate {
+            Some(rate) => (
+                gross_value * rate / dec!(100),
+                DebtLike,
+                format!("Repatriation tax of {}% on trapped cash", rate),
+            ),
+            None => (gross_value, DebtLike, "Trapped cash excluded from cash".to_string()),
+        },
+        BridgeAdjustmentType::NonOperatingAssets => (gross_value, CashLike, "Non-operating assets".to_string()),
+        BridgeAdjustmentType::OtherCashLike => (gross_value, CashLike, "Other cash-like item".to_string()),
+        BridgeAdjustmentType::TaxAssets => match adjustment.tax_rate {
+            Some(rate) => (
+                gross_value * rate / dec!(100),
+                CashLike,
+                format!("Tax losses valued at {}% tax rate", rate),
+            ),
+            None => (gross_value, CashLike, "Tax assets".to_string()),
+        },
+    };
+
+    Ok(BridgeAdjustmentResult {
+        name: adjustment.name.clone(),
+        adjustment_type: adjustment.adjustment_type,
+        valuation_basis,
+        gross_value,
+        bridge_amount,
+        treatment,
+        note,
     })
 }
 
@@ -146,13 +313,15 @@ mod tests {
     #[test]
     fn test_equity_to_ev() {
         let input = EquityEnterpriseInput {
-            direction: "equity_to_ev".to_string(),
+            direction: BridgeDirection::EquityToEv,
             value: dec!(1000),
             cash: dec!(100),
             debt: dec!(300),
             minority_interest: dec!(50),
             associates: dec!(25),
             preferred_stock: dec!(75),
+            adjustments: None,
+            verify_roundtrip: None,
         };
 
         let result = equity_enterprise_bridge(input).unwrap();
@@ -166,13 +335,15 @@ mod tests {
     #[test]
     fn test_ev_to_equity() {
         let input = EquityEnterpriseInput {
-            direction: "ev_to_equity".to_string(),
+            direction: BridgeDirection::EvToEquity,
             value: dec!(1300),
             cash: dec!(100),
             debt: dec!(300),
             minority_interest: dec!(50),
             associates: dec!(25),
             preferred_stock: dec!(75),
+            adjustments: None,
+            verify_roundtrip: None,
         };
 
         let result = equity_enterprise_bridge(input).unwrap();
@@ -187,29 +358,218 @@ mod tests {
     fn test_roundtrip() {
         // Test that equity -> EV -> equity gives same result
         let equity_to_ev = EquityEnterpriseInput {
-            direction: "equity_to_ev".to_string(),
+            direction: BridgeDirection::EquityToEv,
             value: dec!(5000),
             cash: dec!(500),
             debt: dec!(2000),
             minority_interest: dec!(100),
             associates: dec!(200),
             preferred_stock: dec!(300),
+            adjustments: None,
+            verify_roundtrip: None,
         };
 
         let ev_result = equity_enterprise_bridge(equity_to_ev).unwrap();
 
         let ev_to_equity = EquityEnterpriseInput {
-            direction: "ev_to_equity".to_string(),
+            direction: BridgeDirection::EvToEquity,
             value: ev_result.enterprise_value,
             cash: dec!(500),
             debt: dec!(2000),
             minority_interest: dec!(100),
             associates: dec!(200),
             preferred_stock: dec!(300),
+            adjustments: None,
+            verify_roundtrip: None,
         };
 
         let equity_result = equity_enterprise_bridge(ev_to_equity).unwrap();
 
         assert_eq!(equity_result.equity_value, dec!(5000));
     }
+
+    fn adjustment(name: &str, adjustment_type: BridgeAdjustmentType, book_value: Decimal) -> BridgeAdjustment {
+        BridgeAdjustment {
+            name: name.to_string(),
+            adjustment_type,
+            book_value,
+            market_value: None,
+            valuation_basis: None,
+            tax_rate: None,
+            lease_standard: None,
+            convertible_treatment: None,
+        }
+    }
+
+    #[test]
+    fn test_typed_adjustments() {
+        let input = EquityEnterpriseInput {
+            direction: BridgeDirection::EquityToEv,
+            value: dec!(1000),
+            cash: dec!(100),
+            debt: dec!(300),
+            minority_interest: dec!(0),
+            associates: dec!(0),
+            preferred_stock: dec!(0),
+            adjustments: Some(vec![
+                adjustment("Lease liabilities", BridgeAdjustmentType::OperatingLease, dec!(80)),
+                BridgeAdjustment {
+                    tax_rate: Some(dec!(25)),
+                    ..adjustment("Pension deficit", BridgeAdjustmentType::PensionDeficit, dec!(40))
+                },
+                BridgeAdjustment {
+                    market_value: Some(dec!(120)),
+                    valuation_basis: Some(ValuationBasis::Market),
+                    ..adjustment("Convertible notes", BridgeAdjustmentType::ConvertibleDebt, dec!(100))
+                },
+                adjustment("Earn-out", BridgeAdjustmentType::ContingentConsideration, dec!(15)),
+                adjustment("Land held for sale", BridgeAdjustmentType::NonOperatingAssets, dec!(50)),
+                BridgeAdjustment {
+                    tax_rate: Some(dec!(10)),
+                    ..adjustment("Offshore cash", BridgeAdjustmentType::TrappedCash, dec!(60))
+                },
+                BridgeAdjustment {
+                    tax_rate: Some(dec!(20)),
+                    ..adjustment("NOLs", BridgeAdjustmentType::TaxAssets, dec!(100))
+                },
+            ]),
+            verify_roundtrip: None,
+        };
+
+        let result = equity_enterprise_bridge(input).unwrap();
+
+        // Debt-like: 80 + 30 + 120 + 15 + 6 = 251; cash-like: 50 + 20 = 70
+        assert_eq!(result.total_debt_like_items, dec!(251));
+        assert_eq!(result.total_cash_like_items, dec!(70));
+
+        // EV = 1000 + 300 - 100 + 251 - 70 = 1381
+        assert_eq!(result.enterprise_value, dec!(1381));
+
+        // Bridge items: start + 5 standard + 7 adjustments + end
+        assert_eq!(result.bridge_items.len(), 14);
+        assert_eq!(result.bridge_items[7].item, "Add: Pension deficit");
+        assert_eq!(result.bridge_items[7].amount, dec!(30));
+    }
+
+    #[test]
+    fn test_excluded_adjustments_and_roundtrip() {
+        let adjustments = vec![
+            BridgeAdjustment {
+                lease_standard: Some(LeaseStandard::Asc842),
+                ..adjustment("Operating leases", BridgeAdjustmentType::OperatingLease, dec!(200))
+            },
+            BridgeAdjustment {
+                convertible_treatment: Some(ConvertibleTreatment::Equity),
+                ..adjustment("Convertible", BridgeAdjustmentType::ConvertibleDebt, dec!(150))
+            },
+            adjustment("Investments", BridgeAdjustmentType::NonOperatingAssets, dec!(40)),
+        ];
+
+        let to_ev = equity_enterprise_bridge(EquityEnterpriseInput {
+            direction: BridgeDirection::EquityToEv,
+            value: dec!(1000),
+            cash: dec!(0),
+            debt: dec!(0),
+            minority_interest: dec!(0),
+            associates: dec!(0),
+            preferred_stock: dec!(0),
+            adjustments: Some(adjustments.clone()),
+            verify_roundtrip: None,
+        })
+        .unwrap();
+
+        assert_eq!(to_ev.enterprise_value, dec!(960));
+        assert_eq!(to_ev.adjustments[0].treatment, AdjustmentTreatment::Excluded);
+        assert_eq!(to_ev.adjustments[1].treatment, AdjustmentTreatment::Excluded);
+
+        let to_equity = equity_enterprise_bridge(EquityEnterpriseInput {
+            direction: BridgeDirection::EvToEquity,
+            value: to_ev.enterprise_value,
+            cash: dec!(0),
+            debt: dec!(0),
+            minority_interest: dec!(0),
+            associates: dec!(0),
+            preferred_stock: dec!(0),
+            adjustments: Some(adjustments),
+            verify_roundtrip: None,
+        })
+        .unwrap();
+
+        assert_eq!(to_equity.equity_value, dec!(1000));
+    }
+
+    #[test]
+    fn test_market_basis_requires_market_value() {
+        let input = EquityEnterpriseInput {
+            direction: BridgeDirection::EquityToEv,
+            value: dec!(1000),
+            cash: dec!(0),
+            debt: dec!(0),
+            minority_interest: dec!(0),
+            associates: dec!(0),
+            preferred_stock: dec!(0),
+            adjustments: Some(vec![BridgeAdjustment {
+                valuation_basis: Some(ValuationBasis::Market),
+                ..adjustment("Convertible", BridgeAdjustmentType::ConvertibleDebt, dec!(100))
+            }]),
+            verify_roundtrip: None,
+        };
+
+        assert!(equity_enterprise_bridge(input).is_err());
+    }
+
+    #[test]
+    fn test_existing_json_strings_still_accepted() {
+        let json = r#"{
+            "direction": "equity_to_ev",
+            "value": 1000,
+            "cash": 100,
+            "debt": 300,
+            "minority_interest": 0,
+            "associates": 0,
+            "preferred_stock": 0,
+            "adjustments": [
+                {"name": "Leases", "adjustment_type": "operating_lease", "book_value": 50, "lease_standard": "ifrs16"}
+            ]
+        }"#;
+
+        let input: EquityEnterpriseInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.direction, BridgeDirection::EquityToEv);
+
+        let result = equity_enterprise_bridge(input).unwrap();
+        assert_eq!(result.enterprise_value, dec!(1250));
+
+        let output = serde_json::to_value(&result).unwrap();
+        assert_eq!(output["bridge_items"][0]["direction"], "start");
+        assert_eq!(output["bridge_items"][1]["direction"], "add");
+        assert_eq!(output["adjustments"][0]["treatment"], "debt_like");
+
+        let typo = json.replace("equity_to_ev", "equity_to_evv");
+        assert!(serde_json::from_str::<EquityEnterpriseInput>(&typo).is_err());
+    }
+
+    #[test]
+    fn test_verify_roundtrip() {
+        let input = EquityEnterpriseInput {
+            direction: BridgeDirection::EvToEquity,
+            value: dec!(2500),
+            cash: dec!(200),
+            debt: dec!(900),
+            minority_interest: dec!(30),
+            associates: dec!(45),
+            preferred_stock: dec!(10),
+            adjustments: Some(vec![BridgeAdjustment {
+                tax_rate: Some(dec!(21)),
+                ..adjustment("Pension", BridgeAdjustmentType::PensionDeficit, dec!(100))
+            }]),
+            verify_roundtrip: Some(true),
+        };
+
+        let result = equity_enterprise_bridge(input).unwrap();
+        let check = result.roundtrip.unwrap();
+
+        assert_eq!(check.reverse_direction, BridgeDirection::EquityToEv);
+        assert_eq!(check.reverse_result, dec!(2500));
+        assert_eq!(check.difference, Decimal::ZERO);
+    }
 }
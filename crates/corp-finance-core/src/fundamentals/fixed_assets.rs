@@ -0,0 +1,260 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::types::{DepreciationMethod, FixedAssetInput, FixedAssetYear};
+
+/// Build the PP&E and intangibles waterfall for the projection period
+///
+/// Each year's capex is a vintage depreciated from the year it is spent over
+/// `useful_life` years, either straight-line or declining balance (switching to
+/// straight-line once that gives the larger charge). Opening PP&E and opening
+/// intangibles run off straight-line over their own remaining lives; purchased
+/// intangibles amortise straight-line from the year of purchase. A remaining life
+/// is required for non-zero opening PP&E; opening intangibles without one are not
+/// amortised.
+pub fn build_fixed_asset_schedule(
+    config: &FixedAssetInput,
+    capex: &[Decimal],
+    starting_ppe: Decimal,
+    starting_intangibles: Decimal,
+    num_years: usize,
+) -> Result<Vec<FixedAssetYear>> {
+    if config.useful_life == 0 {
+        return Err(FinanceError::InvalidInput(
+            "fixed_assets.useful_life must be at least 1 year".to_string(),
+        ));
+    }
+    if capex.len() < num_years {
+        return Err(FinanceError::InvalidInput(format!(
+            "capex must have a value for each of the {} projection years",
+            num_years
+        )));
+    }
+
+    // Depreciation by year from each capex vintage
+    let mut new_capex_depreciation = vec![Decimal::ZERO; num_years];
+    for (vintage, &spend) in capex.iter().enumerate().take(num_years) {
+        let charges = match config.method {
+            DepreciationMethod::StraightLine => straight_line(spend, config.useful_life, num_years - vintage),
+            DepreciationMethod::DecliningBalance => declining_balance(
+                spend,
+                config.useful_life,
+                config.declining_balance_rate.unwrap_or(dec!(200)),
+                num_years - vintage,
+            )?,
+        };
+        for (age, charge) in charges.into_iter().enumerate() {
+            new_capex_depreciation[vintage + age] += charge;
+        }
+    }
+
+    // Opening PP&E always wears out; opening intangibles without a life are treated as indefinite-lived
+    if starting_ppe != Decimal::ZERO && config.existing_ppe_remaining_life.is_none() {
+        return Err(FinanceError::MissingField(
+            "fixed_assets.existing_ppe_remaining_life (required when starting PP&E is not zero)".to_string(),
+        ));
+    }
+    let existing_ppe_depreciation = run_off(
+        starting_ppe,
+        config.existing_ppe_remaining_life,
+        "existing_ppe_remaining_life",
+        num_years,
+    )?;
+    let mut amortization = run_off(
+        starting_intangibles,
+        config.intangibles_remaining_life,
+        "intangibles_remaining_life",
+        num_years,
+    )?;
+
+    let mut intangible_additions = vec![Decimal::ZERO; num_years];
+    for addition in config.intangible_additions.iter().flatten() {
+        if addition.year == 0 || addition.year as usize > num_years {
+            return Err(FinanceError::OutOfRange(format!(
+                "intangible addition year {} is outside the 1-{} projection period",
+                addition.year, num_years
+            )));
+        }
+        if addition.useful_life == 0 {
+            return Err(FinanceError::InvalidInput(
+                "intangible addition useful_life must be at least 1 year".to_string(),
+            ));
+        }
+        let start = addition.year as usize - 1;
+        intangible_additions[start] += addition.amount;
+        for (age, charge) in straight_line(addition.amount, addition.useful_life, num_years - start).into_iter().enumerate() {
+            amortization[start + age] += charge;
+        }
+    }
+
+    let mut schedule = Vec::new();
+    let mut beginning_ppe = starting_ppe;
+    let mut beginning_intangibles = starting_intangibles;
+    for year in 0..num_years {
+        let depreciation = existing_ppe_depreciation[year] + new_capex_depreciation[year];
+        let ending_ppe = beginning_ppe + capex[year] - depreciation;
+        let ending_intangibles = beginning_intangibles + intangible_additions[year] - amortization[year];
+
+        schedule.push(FixedAssetYear {
+            year: year as u32 + 1,
+            beginning_ppe,
+            capex: capex[year],
+            existing_ppe_depreciation: existing_ppe_depreciation[year],
+            new_capex_depreciation: new_capex_depreciation[year],
+            depreciation,
+            ending_ppe,
+            beginning_intangibles,
+            intangible_additions: intangible_additions[year],
+            amortization: amortization[year],
+            ending_intangibles,
+        });
+
+        beginning_ppe = ending_ppe;
+        beginning_intangibles = ending_intangibles;
+    }
+
+    Ok(schedule)
+}
+
+/// Charges for the first `years` years of the life only, so a long life from the
+/// input never allocates beyond the projection
+fn straight_line(cost: Decimal, life: u32, years: usize) -> Vec<Decimal> {
+    vec![cost / Decimal::from(life); years.min(life as usize)]
+}
+
+fn declining_balance(cost: Decimal, life: u32, rate_percent: Decimal, years: usize) -> Result<Vec<Decimal>> {
+    if rate_percent <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "fixed_assets.declining_balance_rate must be positive".to_string(),
+        ));
+    }
+
+    // e.g. 200% over 5 years = 40% of net book value per year
+    let annual_rate = rate_percent / dec!(100) / Decimal::from(life);
+    let mut charges = Vec::new();
+    let mut net_book_value = cost;
+    for age in 0..life.min(years as u32) {
+        let remaining_years = Decimal::from(life - age);
+        let charge = (net_book_value * annual_rate).max(net_book_value / remaining_years);
+        let charge = charge.min(net_book_value);
+        charges.push(charge);
+        net_book_value -= charge;
+    }
+    Ok(charges)
+}
+
+/// Straight-line run-off of an opening balance; no charge when the life is not given (indefinite-lived)
+fn run_off(balance: Decimal, remaining_life: Option<u32>, name: &str, num_years: usize) -> Result<Vec<Decimal>> {
+    let mut charges = vec![Decimal::ZERO; num_years];
+    match remaining_life {
+        Some(0) => {
+            return Err(FinanceError::InvalidInput(format!(
+                "fixed_assets.{} must be at least 1 year",
+                name
+            )));
+        }
+        Some(life) => {
+            for (year, charge) in straight_line(balance, life, num_years).into_iter().enumerate() {
+                charges[year] = charge;
+            }
+        }
+        None => {}
+    }
+    Ok(charges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::types::IntangibleAddition;
+
+    fn config(method: DepreciationMethod) -> FixedAssetInput {
+        FixedAssetInput {
+            method,
+            useful_life: 5,
+            declining_balance_rate: None,
+            existing_ppe_remaining_life: Some(4),
+            intangibles_remaining_life: Some(10),
+            intangible_additions: None,
+        }
+    }
+
+    #[test]
+    fn test_straight_line_vintages() {
+        let capex = vec![dec!(100), dec!(200), dec!(0)];
+        let schedule = build_fixed_asset_schedule(&config(DepreciationMethod::StraightLine), &capex, dec!(400), dec!(50), 3).unwrap();
+
+        // Existing 400 over 4 years = 100/yr; vintage 1 = 20/yr, vintage 2 = 40/yr
+        assert_eq!(schedule[0].depreciation, dec!(120));
+        assert_eq!(schedule[1].depreciation, dec!(160));
+        assert_eq!(schedule[2].new_capex_depreciation, dec!(60));
+        assert_eq!(schedule[0].ending_ppe, dec!(380));
+        assert_eq!(schedule[1].beginning_ppe, dec!(380));
+
+        // Opening intangibles 50 over 10 years
+        assert_eq!(schedule[0].amortization, dec!(5));
+        assert_eq!(schedule[2].ending_intangibles, dec!(35));
+    }
+
+    #[test]
+    fn test_declining_balance_fully_depreciates() {
+        let charges = declining_balance(dec!(1000), 5, dec!(200), 5).unwrap();
+
+        // 40% of NBV, switching to straight-line: 400, 240, 144, 108, 108
+        assert_eq!(charges[0], dec!(400));
+        assert_eq!(charges[1], dec!(240));
+        assert_eq!(charges[2], dec!(144));
+        assert_eq!(charges[3], dec!(108));
+        assert_eq!(charges.iter().sum::<Decimal>(), dec!(1000));
+    }
+
+    #[test]
+    fn test_unknown_method_fails_to_deserialize() {
+        let json = r#"{"method": "double_declining", "useful_life": 5}"#;
+        assert!(serde_json::from_str::<FixedAssetInput>(json).is_err());
+
+        let json = r#"{"method": "declining_balance", "useful_life": 5}"#;
+        let cfg: FixedAssetInput = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.method, DepreciationMethod::DecliningBalance);
+    }
+
+    #[test]
+    fn test_long_useful_life_is_truncated_to_the_projection() {
+        let mut cfg = config(DepreciationMethod::StraightLine);
+        cfg.useful_life = u32::MAX;
+        let schedule = build_fixed_asset_schedule(&cfg, &[dec!(100), dec!(0)], dec!(0), dec!(0), 2).unwrap();
+        assert_eq!(schedule.len(), 2);
+        assert!(schedule[1].new_capex_depreciation > Decimal::ZERO);
+
+        cfg.method = DepreciationMethod::DecliningBalance;
+        assert!(build_fixed_asset_schedule(&cfg, &[dec!(100), dec!(0)], dec!(0), dec!(0), 2).is_ok());
+    }
+
+    #[test]
+    fn test_opening_ppe_requires_remaining_life() {
+        let mut cfg = config(DepreciationMethod::StraightLine);
+        cfg.existing_ppe_remaining_life = None;
+
+        assert!(build_fixed_asset_schedule(&cfg, &[dec!(0)], dec!(400), dec!(0), 1).is_err());
+        assert!(build_fixed_asset_schedule(&cfg, &[dec!(0)], dec!(0), dec!(0), 1).is_ok());
+    }
+
+    #[test]
+    fn test_intangible_additions() {
+        let mut cfg = config(DepreciationMethod::StraightLine);
+        cfg.intangibles_remaining_life = None;
+        cfg.intangible_additions = Some(vec![IntangibleAddition {
+            year: 2,
+            amount: dec!(30),
+            useful_life: 3,
+        }]);
+
+        let schedule = build_fixed_asset_schedule(&cfg, &[dec!(0), dec!(0), dec!(0)], dec!(0), dec!(0), 3).unwrap();
+
+        assert_eq!(schedule[0].amortization, Decimal::ZERO);
+        assert_eq!(schedule[1].intangible_additions, dec!(30));
+        assert_eq!(schedule[1].amortization, dec!(10));
+        assert_eq!(schedule[2].ending_intangibles, dec!(10));
+    }
+}
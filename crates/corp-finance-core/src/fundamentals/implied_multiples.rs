@@ -0,0 +1,303 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use crate::error::{FinanceError, Result};
+use super::types::EquityEnterpriseOutput;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpliedMultiplesInput {
+    pub bridge: EquityEnterpriseOutput,
+    pub periods: Vec<PeriodMetrics>,
+    pub multiples: Option<Vec<MultipleSpec>>,  // defaults to the standard set
+}
+
+/// Financial metrics for one period (e.g. "LTM", "FY2025E")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodMetrics {
+    pub period: String,
+    pub revenue: Option<Decimal>,
+    pub ebitda: Option<Decimal>,
+    pub ebit: Option<Decimal>,
+    pub capex: Option<Decimal>,
+    pub unlevered_fcf: Option<Decimal>,
+    pub net_income: Option<Decimal>,
+    pub book_value: Option<Decimal>,  // book value of equity
+    pub levered_fcf: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultipleNumerator {
+    EnterpriseValue,
+    EquityValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultipleMetric {
+    Revenue,
+    Ebitda,
+    Ebit,
+    EbitdaLessCapex,
+    UnleveredFcf,
+    NetIncome,
+    BookValue,
+    LeveredFcf,
+}
+
+impl MultipleMetric {
+    /// Levered metrics are after interest and belong to equity holders only
+    pub fn is_levered(self) -> bool {
+        matches!(
+            self,
+            MultipleMetric::NetIncome | MultipleMetric::BookValue | MultipleMetric::LeveredFcf
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleSpec {
+    pub name: String,
+    pub numerator: MultipleNumerator,
+    pub metric: MultipleMetric,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpliedMultiplesOutput {
+    pub enterprise_value: Decimal,
+    pub equity_value: Decimal,
+    pub periods: Vec<PeriodMultiples>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodMultiples {
+    pub period: String,
+    pub multiples: Vec<ImpliedMultiple>,
+    pub fcf_yield: Option<Decimal>,  // levered FCF / equity value, as %
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpliedMultiple {
+    pub name: String,
+    pub numerator: MultipleNumerator,
+    pub metric: MultipleMetric,
+    pub metric_value: Option<Decimal>,
+    pub multiple: Option<Decimal>,  // None when not meaningful (missing or non-positive metric)
+}
+
+/// Standard trading multiples: EV over unlevered metrics, equity value over levered ones
+pub fn standard_multiples() -> Vec<MultipleSpec> {
+    let spec = |name: &str, numerator, metric| MultipleSpec {
+        name: name.to_string(),
+        numerator,
+        metric,
+    };
+    vec![
+        spec("EV/Revenue", MultipleNumerator::EnterpriseValue, MultipleMetric::Revenue),
+        spec("EV/EBITDA", MultipleNumerator::EnterpriseValue, MultipleMetric::Ebitda),
+        spec("EV/EBIT", MultipleNumerator::EnterpriseValue, MultipleMetric::Ebit),
+        spec("EV/(EBITDA - Capex)", MultipleNumerator::EnterpriseValue, MultipleMetric::EbitdaLessCapex),
+        spec("P/E", MultipleNumerator::EquityValue, MultipleMetric::NetIncome),
+        spec("P/B", MultipleNumerator::EquityValue, MultipleMetric::BookValue),
+        spec("P/FCF", MultipleNumerator::EquityValue, MultipleMetric::LeveredFcf),
+    ]
+}
+
+/// Calculate implied trading multiples from an equity/EV bridge result
+///
+/// Every multiple must pair enterprise value with an unlevered metric (available to
+/// all capital providers) or equity value with a levered metric (after interest);
+/// mismatched requests are rejected. Multiples are reported as None where the metric
+/// is missing or non-positive.
+pub fn calculate_implied_multiples(input: ImpliedMultiplesInput) -> Result<ImpliedMultiplesOutput> {
+    if input.periods.is_empty() {
+        return Err(FinanceError::InvalidInput(
+            "At least one period of metrics is required".to_string(),
+        ));
+    }
+
+    let specs = input.multiples.unwrap_or_else(standard_multiples);
+    for spec in &specs {
+        let equity_numerator = spec.numerator == MultipleNumerator::EquityValue;
+        if equity_numerator != spec.metric.is_levered() {
+            return Err(FinanceError::InvalidInput(format!(
+                "{}: {} must be paired with {} metrics",
+                spec.name,
+                if equity_numerator { "equity value" } else { "enterprise value" },
+                if equity_numerator { "levered" } else { "unlevered" }
+            )));
+        }
+    }
+
+    let enterprise_value = input.bridge.enterprise_value;
+    let equity_value = input.bridge.equity_value;
+
+    let periods = input
+        .periods
+        .iter()
+        .map(|metrics| {
+            let multiples = specs
+                .iter()
+                .map(|spec| {
+                    let numerator = match spec.numerator {
+                        MultipleNumerator::EnterpriseValue => enterprise_value,
+                        MultipleNumerator::EquityValue => equity_value,
+                    };
+                    let metric_value = metric_value(metrics, spec.metric);
+                    let multiple = metric_value
+                        .filter(|value| *value > Decimal::ZERO && numerator > Decimal::ZERO)
+                        .map(|value| numerator / value);
+
+                    ImpliedMultiple {
+                        name: spec.name.clone(),
+                        numerator: spec.numerator,
+                        metric: spec.metric,
+                        metric_value,
+                        multiple,
+                    }
+                })
+                .collect();
+
+            let fcf_yield = match metrics.levered_fcf {
+                Some(fcf) if equity_value > Decimal::ZERO => Some(fcf / equity_value * dec!(100)),
+                _ => None,
+            };
+
+            PeriodMultiples {
+                period: metrics.period.clone(),
+                multiples,
+                fcf_yield,
+            }
+        })
+        .collect();
+
+    Ok(ImpliedMultiplesOutput {
+        enterprise_value,
+        equity_value,
+        periods,
+    })
+}
+
+fn metric_value(metrics: &PeriodMetrics, metric: MultipleMetric) -> Option<Decimal> {
+    match metric {
+        MultipleMetric::Revenue => metrics.revenue,
+        MultipleMetric::Ebitda => metrics.ebitda,
+        MultipleMetric::Ebit => metrics.ebit,
+        MultipleMetric::EbitdaLessCapex => match (metrics.ebitda, metrics.capex) {
+            (Some(ebitda), Some(capex)) => Some(ebitda - capex.abs()),
+            _ => None,
+        },
+        MultipleMetric::UnleveredFcf => metrics.unlevered_fcf,
+        MultipleMetric::NetIncome => metrics.net_income,
+        MultipleMetric::BookValue => metrics.book_value,
+        MultipleMetric::LeveredFcf => metrics.levered_fcf,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::equity_enterprise_bridge::equity_enterprise_bridge;
+    use super::super::types::{BridgeDirection, EquityEnterpriseInput};
+
+    fn bridge() -> EquityEnterpriseOutput {
+        equity_enterprise_bridge(EquityEnterpriseInput {
+            direction: BridgeDirection::EquityToEv,
+            value: dec!(800),
+            cash: dec!(100),
+            debt: dec!(300),
+            minority_interest: dec!(0),
+            associates: dec!(0),
+            preferred_stock: dec!(0),
+            adjustments: None,
+            verify_roundtrip: None,
+        })
+        .unwrap()
+    }
+
+    fn period(name: &str, scale: Decimal) -> PeriodMetrics {
+        PeriodMetrics {
+            period: name.to_string(),
+            revenue: Some(dec!(500) * scale),
+            ebitda: Some(dec!(100) * scale),
+            ebit: Some(dec!(80) * scale),
+            capex: Some(dec!(20) * scale),
+            unlevered_fcf: None,
+            net_income: Some(dec!(50) * scale),
+            book_value: Some(dec!(400)),
+            levered_fcf: Some(dec!(40) * scale),
+        }
+    }
+
+    #[test]
+    fn test_standard_multiples_ltm_and_forward() {
+        let input = ImpliedMultiplesInput {
+            bridge: bridge(),
+            periods: vec![period("LTM", dec!(1)), period("NTM", dec!(1.25))],
+            multiples: None,
+        };
+
+        let result = calculate_implied_multiples(input).unwrap();
+        assert_eq!(result.enterprise_value, dec!(1000));
+
+        let ltm = &result.periods[0];
+        let find = |p: &PeriodMultiples, name: &str| {
+            p.multiples.iter().find(|m| m.name == name).unwrap().multiple
+        };
+        assert_eq!(find(ltm, "EV/Revenue"), Some(dec!(2)));
+        assert_eq!(find(ltm, "EV/EBITDA"), Some(dec!(10)));
+        assert_eq!(find(ltm, "EV/EBIT"), Some(dec!(12.5)));
+        assert_eq!(find(ltm, "EV/(EBITDA - Capex)"), Some(dec!(12.5)));
+        assert_eq!(find(ltm, "P/E"), Some(dec!(16)));
+        assert_eq!(find(ltm, "P/B"), Some(dec!(2)));
+        assert_eq!(find(ltm, "P/FCF"), Some(dec!(20)));
+        assert_eq!(ltm.fcf_yield, Some(dec!(5)));
+
+        let ntm = &result.periods[1];
+        assert_eq!(find(ntm, "EV/EBITDA"), Some(dec!(8)));
+    }
+
+    #[test]
+    fn test_negative_metric_not_meaningful() {
+        let mut loss_period = period("LTM", dec!(1));
+        loss_period.net_income = Some(dec!(-10));
+        loss_period.book_value = None;
+
+        let result = calculate_implied_multiples(ImpliedMultiplesInput {
+            bridge: bridge(),
+            periods: vec![loss_period],
+            multiples: None,
+        })
+        .unwrap();
+
+        let pe = result.periods[0].multiples.iter().find(|m| m.name == "P/E").unwrap();
+        assert_eq!(pe.multiple, None);
+        assert_eq!(pe.metric_value, Some(dec!(-10)));
+    }
+
+    #[test]
+    fn test_mismatched_numerator_rejected() {
+        let input = ImpliedMultiplesInput {
+            bridge: bridge(),
+            periods: vec![period("LTM", dec!(1))],
+            multiples: Some(vec![MultipleSpec {
+                name: "EV/Net Income".to_string(),
+                numerator: MultipleNumerator::EnterpriseValue,
+                metric: MultipleMetric::NetIncome,
+            }]),
+        };
+        assert!(calculate_implied_multiples(input).is_err());
+
+        let input = ImpliedMultiplesInput {
+            bridge: bridge(),
+            periods: vec![period("LTM", dec!(1))],
+            multiples: Some(vec![MultipleSpec {
+                name: "P/EBITDA".to_string(),
+                numerator: MultipleNumerator::EquityValue,
+                metric: MultipleMetric::Ebitda,
+            }]),
+        };
+        assert!(calculate_implied_multiples(input).is_err());
+    }
+}
@@ -0,0 +1,784 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::types::{
+    Account, AccountClass, AccountingFlowInput, AccountingImpact, CashFlowLine, CashFlowSection,
+    JournalLine,
+};
+
+impl Account {
+    /// Statement line the account is reported on
+    pub fn name(self) -> &'static str {
+        match self {
+            Account::Cash => "Cash",
+            Account::AccountsReceivable => "Accounts Receivable",
+            Account::Inventory => "Inventory",
+            Account::PrepaidExpenses => "Prepaid Expenses",
+            Account::Ppe => "PP&E",
+            Account::Intangibles => "Intangible Assets",
+            Account::RightOfUseAsset => "Right-of-Use Asset",
+            Account::DeferredTaxAsset => "Deferred Tax Asset",
+            Account::AccountsPayable => "Accounts Payable",
+            Account::AccruedLiabilities => "Accrued Liabilities",
+            Account::DeferredRevenue => "Deferred Revenue",
+            Account::TaxPayable => "Taxes Payable",
+            Account::Debt => "Debt",
+            Account::LeaseLiability => "Lease Liability",
+            Account::DeferredTaxLiability => "Deferred Tax Liability",
+            Account::PaidInCapital => "Paid-in Capital",
+            Account::RetainedEarnings => "Retained Earnings",
+            Account::Revenue => "Revenue",
+            Account::Cogs => "Cost of Goods Sold",
+            Account::OperatingExpenses => "Operating Expenses",
+            Account::StockBasedCompensation => "Stock-Based Compensation",
+            Account::DepreciationExpense => "Depreciation Expense",
+            Account::AmortizationExpense => "Amortization Expense",
+            Account::ImpairmentLoss => "Impairment Loss",
+            Account::InterestExpense => "Interest Expense",
+            Account::GainLossOnSale => "Gain / (Loss) on Asset Sale",
+            Account::TaxExpense => "Income Tax Expense",
+        }
+    }
+
+    pub fn class(self) -> AccountClass {
+        match self {
+            Account::Cash
+            | Account::AccountsReceivable
+            | Account::Inventory
+            | Account::PrepaidExpenses
+            | Account::Ppe
+            | Account::Intangibles
+            | Account::RightOfUseAsset
+            | Account::DeferredTaxAsset => AccountClass::Asset,
+            Account::AccountsPayable
+            | Account::AccruedLiabilities
+            | Account::DeferredRevenue
+            | Account::TaxPayable
+            | Account::Debt
+            | Account::LeaseLiability
+            | Account::DeferredTaxLiability => AccountClass::Liability,
+            Account::PaidInCapital | Account::RetainedEarnings => AccountClass::Equity,
+            Account::Revenue | Account::GainLossOnSale => AccountClass::Income,
+            Account::Cogs
+            | Account::OperatingExpenses
+            | Account::StockBasedCompensation
+            | Account::DepreciationExpense
+            | Account::AmortizationExpense
+            | Account::ImpairmentLoss
+            | Account::InterestExpense
+            | Account::TaxExpense => AccountClass::Expense,
+        }
+    }
+
+    pub fn is_income_statement(self) -> bool {
+        matches!(self.class(), AccountClass::Income | AccountClass::Expense)
+    }
+
+    /// Cash flow line a movement in the account falls on, unless the journal line overrides it
+    pub fn default_cash_flow_line(self) -> Option<CashFlowLine> {
+        let line = match self {
+            Account::Cash => return None,
+            Account::AccountsReceivable => CashFlowLine::ChangeInReceivables,
+            Account::Inventory => CashFlowLine::ChangeInInventory,
+            Account::PrepaidExpenses => CashFlowLine::ChangeInPrepaidExpenses,
+            Account::Ppe => CashFlowLine::CapitalExpenditures,
+            Account::Intangibles => CashFlowLine::IntangiblesPurchased,
+            Account::RightOfUseAsset => CashFlowLine::NonCash,
+            Account::DeferredTaxAsset | Account::DeferredTaxLiability => CashFlowLine::DeferredTax,
+            Account::AccountsPayable => CashFlowLine::ChangeInPayables,
+            Account::AccruedLiabilities => CashFlowLine::ChangeInAccruedLiabilities,
+            Account::DeferredRevenue => CashFlowLine::ChangeInDeferredRevenue,
+            Account::TaxPayable => CashFlowLine::ChangeInTaxPayable,
+            Account::Debt => CashFlowLine::Debt,
+            Account::LeaseLiability => CashFlowLine::LeasePrincipal,
+            Account::PaidInCapital => CashFlowLine::EquityIssuance,
+            Account::RetainedEarnings => CashFlowLine::Dividends,
+            _ => CashFlowLine::NetIncome,
+        };
+        Some(line)
+    }
+}
+
+impl CashFlowLine {
+    /// None for non-cash transactions, which stay off the cash flow statement
+    pub fn section(self) -> Option<CashFlowSection> {
+        match self {
+            CashFlowLine::CapitalExpenditures
+            | CashFlowLine::AssetSaleProceeds
+            | CashFlowLine::IntangiblesPurchased => Some(CashFlowSection::Investing),
+            CashFlowLine::Debt
+            | CashFlowLine::LeasePrincipal
+            | CashFlowLine::EquityIssuance
+            | CashFlowLine::Dividends => Some(CashFlowSection::Financing),
+            CashFlowLine::NonCash => None,
+            _ => Some(CashFlowSection::Operating),
+        }
+    }
+
+    pub fn label(self, amount: Decimal) -> &'static str {
+        match self {
+            CashFlowLine::NetIncome => "Net Income",
+            CashFlowLine::Depreciation => "Add: Depreciation",
+            CashFlowLine::Amortization => "Add: Amortization",
+            CashFlowLine::Impairment => "Add: Impairment",
+            CashFlowLine::StockBasedCompensation => "Add: Stock-Based Compensation",
+            CashFlowLine::InventoryWriteDown => "Add: Inventory Write-Down",
+            CashFlowLine::DeferredTax => "Deferred Taxes",
+            CashFlowLine::ChangeInReceivables => "Change in NWC - Accounts Receivable",
+            CashFlowLine::ChangeInInventory => "Change in NWC - Inventory",
+            CashFlowLine::ChangeInPrepaidExpenses => "Change in NWC - Prepaid Expenses",
+            CashFlowLine::ChangeInPayables => "Change in NWC - Accounts Payable",
+            CashFlowLine::ChangeInAccruedLiabilities => "Change in NWC - Accrued Liabilities",
+            CashFlowLine::ChangeInDeferredRevenue => "Change in NWC - Deferred Revenue",
+            CashFlowLine::ChangeInTaxPayable => "Change in Taxes Payable",
+            CashFlowLine::CapitalExpenditures => "CapEx (Investing Activities)",
+            CashFlowLine::AssetSaleProceeds => "Proceeds from Asset Sale (Investing Activities)",
+            CashFlowLine::IntangiblesPurchased => "Purchase of Intangibles (Investing Activities)",
+            CashFlowLine::Debt if amount < Decimal::ZERO => "Debt Repayment (Financing Activities)",
+            CashFlowLine::Debt => "Debt Issuance (Financing Activities)",
+            CashFlowLine::LeasePrincipal => "Lease Principal Repayment (Financing Activities)",
+            CashFlowLine::EquityIssuance => "Equity Issuance (Financing Activities)",
+            CashFlowLine::Dividends => "Dividends Paid (Financing Activities)",
+            CashFlowLine::NonCash => "Non-cash Transaction",
+        }
+    }
+}
+
+impl JournalLine {
+    pub fn debit(account: Account, amount: Decimal) -> Self {
+        JournalLine {
+            account,
+            debit: amount,
+            credit: Decimal::ZERO,
+            cash_flow_line: None,
+        }
+    }
+
+    pub fn credit(account: Account, amount: Decimal) -> Self {
+        JournalLine {
+            account,
+            debit: Decimal::ZERO,
+            credit: amount,
+            cash_flow_line: None,
+        }
+    }
+
+    pub fn with_cash_flow(mut self, line: CashFlowLine) -> Self {
+        self.cash_flow_line = Some(line);
+        self
+    }
+
+    /// Credit less debit: the line's effect on net income, or on cash via the indirect method
+    pub fn net_credit(&self) -> Decimal {
+        self.credit - self.debit
+    }
+}
+
+/// A standard transaction and the journal entry it posts
+pub struct TransactionRule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub tax_deduction_deferred: bool,  // expense deductible only later (temporary difference)
+    build: fn(&AccountingFlowInput) -> Result<Vec<JournalLine>>,
+}
+
+static STANDARD_TRANSACTIONS: &[TransactionRule] = &[
+    TransactionRule {
+        name: "depreciation",
+        description: "a non-cash expense that reduces PP&E and net income and is added back in cash from operations",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::DepreciationExpense, input.amount),
+            JournalLine::credit(Account::Ppe, input.amount).with_cash_flow(CashFlowLine::Depreciation),
+        ]),
+    },
+    TransactionRule {
+        name: "amortization",
+        description: "a non-cash expense that reduces intangible assets and net income and is added back in cash from operations",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::AmortizationExpense, input.amount),
+            JournalLine::credit(Account::Intangibles, input.amount).with_cash_flow(CashFlowLine::Amortization),
+        ]),
+    },
+    TransactionRule {
+        name: "capex",
+        description: "an investing outflow that increases PP&E with no immediate P&L impact",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Ppe, input.amount),
+            JournalLine::credit(Account::Cash, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "debt_issuance",
+        description: "a financing inflow that increases cash and debt; future interest will affect earnings",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Cash, input.amount),
+            JournalLine::credit(Account::Debt, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "debt_repayment",
+        description: "a financing outflow that reduces cash and debt and lowers future interest expense",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Debt, input.amount),
+            JournalLine::credit(Account::Cash, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "inventory_purchase",
+        description: "increases inventory and reduces cash with no P&L impact until the goods are sold",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Inventory, input.amount),
+            JournalLine::credit(Account::Cash, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "revenue_recognition",
+        description: "revenue earned on credit increases accounts receivable; cash is collected later",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::AccountsReceivable, input.amount),
+            JournalLine::credit(Account::Revenue, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "accrued_expense",
+        description: "an expense incurred but not yet paid reduces net income and increases accrued liabilities",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::OperatingExpenses, input.amount),
+            JournalLine::credit(Account::AccruedLiabilities, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "prepaid_expense",
+        description: "cash paid in advance becomes a prepaid asset and is expensed when the service is used",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::PrepaidExpenses, input.amount),
+            JournalLine::credit(Account::Cash, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "prepaid_expense_recognition",
+        description: "the prepaid asset is used up and expensed with no cash impact",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::OperatingExpenses, input.amount),
+            JournalLine::credit(Account::PrepaidExpenses, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "impairment",
+        description: "a non-cash write-down of intangible assets or goodwill that reduces net income and is added back in cash from operations",
+        tax_deduction_deferred: true,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::ImpairmentLoss, input.amount),
+            JournalLine::credit(Account::Intangibles, input.amount).with_cash_flow(CashFlowLine::Impairment),
+        ]),
+    },
+    TransactionRule {
+        name: "stock_based_compensation",
+        description: "a non-cash expense settled in shares: net income falls, paid-in capital rises and the expense is added back",
+        tax_deduction_deferred: true,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::StockBasedCompensation, input.amount),
+            JournalLine::credit(Account::PaidInCapital, input.amount)
+                .with_cash_flow(CashFlowLine::StockBasedCompensation),
+        ]),
+    },
+    TransactionRule {
+        name: "inventory_write_down",
+        description: "a non-cash charge to cost of goods sold that reduces inventory and is added back in cash from operations",
+        tax_deduction_deferred: true,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Cogs, input.amount),
+            JournalLine::credit(Account::Inventory, input.amount).with_cash_flow(CashFlowLine::InventoryWriteDown),
+        ]),
+    },
+    TransactionRule {
+        name: "deferred_revenue",
+        description: "cash received before the service is delivered increases cash and deferred revenue with no P&L impact",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::Cash, input.amount),
+            JournalLine::credit(Account::DeferredRevenue, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "deferred_revenue_recognition",
+        description: "previously collected cash is earned, moving deferred revenue into revenue with no cash impact",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::DeferredRevenue, input.amount),
+            JournalLine::credit(Account::Revenue, input.amount),
+        ]),
+    },
+    TransactionRule {
+        name: "asset_sale",
+        description: "proceeds are an investing inflow; the gain or loss against book value runs through net income and is reversed in cash from operations",
+        tax_deduction_deferred: false,
+        build: |input| {
+            let carrying_value = input.carrying_value.ok_or_else(|| {
+                FinanceError::MissingField("carrying_value is required for asset_sale".to_string())
+            })?;
+            let gain = input.amount - carrying_value;
+            let mut lines = vec![
+                JournalLine::debit(Account::Cash, input.amount),
+                JournalLine::credit(Account::Ppe, carrying_value).with_cash_flow(CashFlowLine::AssetSaleProceeds),
+            ];
+            if gain > Decimal::ZERO {
+                lines.push(JournalLine::credit(Account::GainLossOnSale, gain)
+                    .with_cash_flow(CashFlowLine::AssetSaleProceeds));
+            } else if gain < Decimal::ZERO {
+                lines.push(JournalLine::debit(Account::GainLossOnSale, -gain)
+                    .with_cash_flow(CashFlowLine::AssetSaleProceeds));
+            }
+            Ok(lines)
+        },
+    },
+    TransactionRule {
+        name: "lease_commencement",
+        description: "a lease recognised on the balance sheet adds a right-of-use asset and lease liability with no cash or P&L impact",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::RightOfUseAsset, input.amount).with_cash_flow(CashFlowLine::NonCash),
+            JournalLine::credit(Account::LeaseLiability, input.amount).with_cash_flow(CashFlowLine::NonCash),
+        ]),
+    },
+    TransactionRule {
+        name: "lease_payment",
+        description: "the interest portion is an expense in cash from operations and the principal portion reduces the lease liability in financing",
+        tax_deduction_deferred: false,
+        build: |input| {
+            let interest = input.interest_portion.unwrap_or(Decimal::ZERO);
+            if interest < Decimal::ZERO || interest > input.amount {
+                return Err(FinanceError::OutOfRange(
+                    "interest_portion must be between 0 and the lease payment".to_string(),
+                ));
+            }
+            Ok(vec![
+                JournalLine::debit(Account::InterestExpense, interest),
+                JournalLine::debit(Account::LeaseLiability, input.amount - interest),
+                JournalLine::credit(Account::Cash, input.amount),
+            ])
+        },
+    },
+    TransactionRule {
+        name: "right_of_use_depreciation",
+        description: "a non-cash expense that reduces the right-of-use asset and is added back in cash from operations",
+        tax_deduction_deferred: false,
+        build: |input| Ok(vec![
+            JournalLine::debit(Account::DepreciationExpense, input.amount),
+            JournalLine::credit(Account::RightOfUseAsset, input.amount).with_cash_flow(CashFlowLine::Depreciation),
+        ]),
+    },
+];
+
+/// The library of standard transactions
+pub fn standard_transactions() -> &'static [TransactionRule] {
+    STANDARD_TRANSACTIONS
+}
+
+pub fn find_transaction(name: &str) -> Option<&'static TransactionRule> {
+    STANDARD_TRANSACTIONS.iter().find(|rule| rule.name == name)
+}
+
+/// Tax on a transaction's P&L impact
+#[derive(Debug, Clone, Copy)]
+pub struct TaxEffect {
+    pub pre_tax_income: Decimal,
+    pub taxable_income: Decimal,
+    pub current_tax: Decimal,  // paid (saved) in cash in the period
+    pub deferred_tax: Decimal,  // recorded against deferred tax assets / liabilities
+}
+
+/// A posted transaction: its balanced journal lines and the tax booked on it
+pub struct PostedEntry {
+    pub lines: Vec<JournalLine>,
+    pub tax: TaxEffect,
+}
+
+/// Build the balanced journal entry for a transaction, including tax at `tax_rate`
+///
+/// "custom" posts the caller's own `journal_entries`; any other type is looked up
+/// in the standard library. Current tax on taxable income is paid (or saved) in
+/// cash in the same period. Where taxable income differs from book income (given
+/// by `taxable_amount`, or because the library defers the deduction) the
+/// difference is booked as deferred tax, unless it is a permanent difference.
+pub fn post_transaction(input: &AccountingFlowInput) -> Result<PostedEntry> {
+    let rule = find_transaction(&input.transaction_type);
+    let mut lines = if input.transaction_type == "custom" {
+        input.journal_entries.clone().ok_or_else(|| {
+            FinanceError::MissingField("journal_entries is required for custom transactions".to_string())
+        })?
+    } else {
+        let rule = rule.ok_or_else(|| {
+            let supported: Vec<&str> = STANDARD_TRANSACTIONS.iter().map(|rule| rule.name).collect();
+            FinanceError::InvalidInput(format!(
+                "Transaction type '{}' not recognized. Supported types: {}, custom",
+                input.transaction_type,
+                supported.join(", ")
+            ))
+        })?;
+        if input.amount < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(
+                "Transaction amount cannot be negative".to_string(),
+            ));
+        }
+        (rule.build)(input)?
+    };
+
+    let tax_rate = input.tax_rate.unwrap_or(Decimal::ZERO);
+    if tax_rate < Decimal::ZERO || tax_rate > dec!(100) {
+        return Err(FinanceError::OutOfRange(
+            "tax_rate must be between 0 and 100".to_string(),
+        ));
+    }
+
+    let pre_tax_income: Decimal = lines
+        .iter()
+        .filter(|line| line.account.is_income_statement() && line.account != Account::TaxExpense)
+        .map(JournalLine::net_credit)
+        .sum();
+    let deferred_by_default = rule.is_some_and(|rule| rule.tax_deduction_deferred);
+    let taxable_income = input.taxable_amount.unwrap_or(if deferred_by_default {
+        Decimal::ZERO
+    } else {
+        pre_tax_income
+    });
+
+    let current_tax = taxable_income * tax_rate / dec!(100);
+    let deferred_tax = if input.permanent_difference.unwrap_or(false) {
+        Decimal::ZERO
+    } else {
+        (pre_tax_income - taxable_income) * tax_rate / dec!(100)
+    };
+
+    if current_tax > Decimal::ZERO {
+        lines.push(JournalLine::debit(Account::TaxExpense, current_tax));
+        lines.push(JournalLine::credit(Account::Cash, current_tax));
+    } else if current_tax < Decimal::ZERO {
+        lines.push(JournalLine::debit(Account::Cash, -current_tax));
+        lines.push(JournalLine::credit(Account::TaxExpense, -current_tax));
+    }
+    if deferred_tax > Decimal::ZERO {
+        lines.push(JournalLine::debit(Account::TaxExpense, deferred_tax));
+        lines.push(JournalLine::credit(Account::DeferredTaxLiability, deferred_tax));
+    } else if deferred_tax < Decimal::ZERO {
+        lines.push(JournalLine::debit(Account::DeferredTaxAsset, -deferred_tax));
+        lines.push(JournalLine::credit(Account::TaxExpense, -deferred_tax));
+    }
+
+    lines.retain(|line| !line.debit.is_zero() || !line.credit.is_zero());
+    validate_entry(&lines)?;
+    Ok(PostedEntry {
+        lines,
+        tax: TaxEffect {
+            pre_tax_income,
+            taxable_income,
+            current_tax,
+            deferred_tax,
+        },
+    })
+}
+
+/// Every line must be a non-negative debit or credit and total debits must equal total credits
+pub fn validate_entry(lines: &[JournalLine]) -> Result<()> {
+    if lines.iter().any(|line| line.debit < Decimal::ZERO || line.credit < Decimal::ZERO) {
+        return Err(FinanceError::NegativeValue(
+            "Journal lines cannot have negative debits or credits".to_string(),
+        ));
+    }
+
+    let debits: Decimal = lines.iter().map(|line| line.debit).sum();
+    let credits: Decimal = lines.iter().map(|line| line.credit).sum();
+    if debits != credits {
+        return Err(FinanceError::InvalidInput(format!(
+            "Journal entry does not balance: debits {} vs credits {}",
+            debits, credits
+        )));
+    }
+    Ok(())
+}
+
+/// Three-statement presentation of a journal entry
+pub struct StatementImpacts {
+    pub income_statement: Vec<AccountingImpact>,
+    pub balance_sheet: Vec<AccountingImpact>,
+    pub cash_flow: Vec<AccountingImpact>,
+    pub net_income: Decimal,
+    pub cash_change: Decimal,
+}
+
+/// Change in an account's balance, positive when the account grows
+pub fn balance_change(lines: &[JournalLine], account: Account) -> Decimal {
+    let net_credit: Decimal = lines
+        .iter()
+        .filter(|line| line.account == account)
+        .map(JournalLine::net_credit)
+        .sum();
+    match account.class() {
+        AccountClass::Asset | AccountClass::Expense => -net_credit,
+        _ => net_credit,
+    }
+}
+
+pub fn statement_impacts(lines: &[JournalLine]) -> StatementImpacts {
+    let net_income: Decimal = lines
+        .iter()
+        .filter(|line| line.account.is_income_statement())
+        .map(JournalLine::net_credit)
+        .sum();
+    let cash_change = balance_change(lines, Account::Cash);
+
+    // Income statement: each line's effect on net income
+    let mut income_statement = Vec::new();
+    for account in accounts_in_order(lines, |account| account.is_income_statement()) {
+        let effect: Decimal = lines
+            .iter()
+            .filter(|line| line.account == account)
+            .map(JournalLine::net_credit)
+            .sum();
+        income_statement.push(impact(account.name(), effect));
+    }
+    if income_statement.is_empty() {
+        income_statement.push(impact("No immediate impact", Decimal::ZERO));
+    } else {
+        income_statement.push(impact("Net Income", net_income));
+    }
+
+    // Balance sheet: assets, then liabilities, then equity with net income in retained earnings
+    let mut balance_sheet = Vec::new();
+    for class in [AccountClass::Asset, AccountClass::Liability, AccountClass::Equity] {
+        for account in accounts_in_order(lines, |account| account.class() == class) {
+            let mut change = balance_change(lines, account);
+            if account == Account::RetainedEarnings {
+                change += net_income;
+            }
+            balance_sheet.push(impact(account.name(), change));
+        }
+    }
+    if !net_income.is_zero() && !lines.iter().any(|line| line.account == Account::RetainedEarnings) {
+        balance_sheet.push(impact(Account::RetainedEarnings.name(), net_income));
+    }
+
+    StatementImpacts {
+        income_statement,
+        balance_sheet,
+        cash_flow: cash_flow_impacts(lines, net_income),
+        net_income,
+        cash_change,
+    }
+}
+
+/// Indirect-method cash flow: net income, then the cash effect of every non-cash line
+///
+/// A balance sheet line contributes credit less debit to its cash flow line. An
+/// income statement line mapped to a cash flow line (e.g. a gain on sale) is
+/// reversed out of operations and shown on that line instead.
+fn cash_flow_impacts(lines: &[JournalLine], net_income: Decimal) -> Vec<AccountingImpact> {
+    let mut rows: Vec<(CashFlowSection, String, Decimal)> = Vec::new();
+    let mut add = |section: CashFlowSection, label: String, amount: Decimal| {
+        match rows.iter_mut().find(|row| row.1 == label) {
+            Some(row) => row.2 += amount,
+            None => rows.push((section, label, amount)),
+        }
+    };
+
+    if lines.iter().any(|line| line.account.is_income_statement()) {
+        add(CashFlowSection::Operating, CashFlowLine::NetIncome.label(net_income).to_string(), net_income);
+    }
+
+    for line in lines {
+        let Some(cash_flow_line) = line.cash_flow_line.or(line.account.default_cash_flow_line()) else {
+            continue;
+        };
+        let effect = line.net_credit();
+        if line.account.is_income_statement() {
+            if cash_flow_line == CashFlowLine::NetIncome {
+                continue;
+            }
+            add(CashFlowSection::Operating, format!("Less: {}", line.account.name()), -effect);
+        }
+        if let Some(section) = cash_flow_line.section() {
+            add(section, cash_flow_line.label(effect).to_string(), effect);
+        }
+    }
+
+    rows.retain(|row| !row.2.is_zero() || row.1 == CashFlowLine::NetIncome.label(Decimal::ZERO));
+    rows.sort_by_key(|row| row.0);
+
+    let mut impacts = Vec::new();
+    for (section, subtotal) in [
+        (CashFlowSection::Operating, "Cash from Operations"),
+        (CashFlowSection::Investing, "Cash from Investing"),
+        (CashFlowSection::Financing, "Cash from Financing"),
+    ] {
+        let section_rows: Vec<_> = rows.iter().filter(|row| row.0 == section).collect();
+        for row in &section_rows {
+            impacts.push(impact(&row.1, row.2));
+        }
+        // Subtotal only where it sums several lines
+        if section_rows.len() > 1 {
+            impacts.push(impact(subtotal, section_rows.iter().map(|row| row.2).sum()));
+        }
+    }
+    impacts
+}
+
+fn accounts_in_order(lines: &[JournalLine], include: impl Fn(Account) -> bool) -> Vec<Account> {
+    let mut accounts = Vec::new();
+    for line in lines {
+        if include(line.account) && !accounts.contains(&line.account) {
+            accounts.push(line.account);
+        }
+    }
+    accounts
+}
+
+fn impact(line_item: &str, amount: Decimal) -> AccountingImpact {
+    let sign = if amount > Decimal::ZERO {
+        "positive"
+    } else if amount < Decimal::ZERO {
+        "negative"
+    } else {
+        "neutral"
+    };
+    AccountingImpact {
+        line_item: line_item.to_string(),
+        impact: amount.abs(),
+        sign: sign.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::accounting_flows::flow;
+
+    #[test]
+    fn test_every_standard_transaction_balances() {
+        for rule in standard_transactions() {
+            let mut transaction = flow(rule.name, dec!(100));
+            transaction.carrying_value = Some(dec!(80));
+            transaction.interest_portion = Some(dec!(10));
+
+            transaction.tax_rate = Some(dec!(25));
+            let lines = post_transaction(&transaction).unwrap().lines;
+            let impacts = statement_impacts(&lines);
+
+            // Cash flow statement reconciles to the change in cash
+            let cash_flow_total: Decimal = impacts
+                .cash_flow
+                .iter()
+                .filter(|item| !item.line_item.starts_with("Cash from"))
+                .map(|item| if item.sign == "negative" { -item.impact } else { item.impact })
+                .sum();
+            assert_eq!(cash_flow_total, impacts.cash_change, "{}", rule.name);
+        }
+    }
+
+    #[test]
+    fn test_asset_sale_gain_reclassified_to_investing() {
+        let mut sale = flow("asset_sale", dec!(120));
+        sale.carrying_value = Some(dec!(100));
+
+        let lines = post_transaction(&sale).unwrap().lines;
+        let impacts = statement_impacts(&lines);
+
+        assert_eq!(impacts.net_income, dec!(20));
+        let find = |label: &str| impacts.cash_flow.iter().find(|item| item.line_item == label).unwrap();
+        assert_eq!(find("Less: Gain / (Loss) on Asset Sale").sign, "negative");
+        assert_eq!(find("Proceeds from Asset Sale (Investing Activities)").impact, dec!(120));
+        assert_eq!(find("Cash from Operations").impact, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_lease_payment_split() {
+        let mut payment = flow("lease_payment", dec!(100));
+        payment.interest_portion = Some(dec!(30));
+
+        let impacts = statement_impacts(&post_transaction(&payment).unwrap().lines);
+
+        assert_eq!(impacts.net_income, dec!(-30));
+        let principal = impacts
+            .cash_flow
+            .iter()
+            .find(|item| item.line_item == "Lease Principal Repayment (Financing Activities)")
+            .unwrap();
+        assert_eq!(principal.impact, dec!(70));
+        assert_eq!(impacts.cash_change, dec!(-100));
+    }
+
+    #[test]
+    fn test_tax_at_configurable_rate() {
+        let mut depreciation = flow("depreciation", dec!(100));
+        depreciation.tax_rate = Some(dec!(25));
+        let posted = post_transaction(&depreciation).unwrap();
+        let impacts = statement_impacts(&posted.lines);
+
+        // 100 pre-tax expense, 25 of tax saved in cash
+        assert_eq!(posted.tax.current_tax, dec!(-25));
+        assert_eq!(impacts.net_income, dec!(-75));
+        assert_eq!(impacts.cash_change, dec!(25));
+    }
+
+    #[test]
+    fn test_deferred_tax_on_book_tax_differences() {
+        // Stock comp is deducted only on exercise: benefit booked as a deferred tax asset
+        let mut sbc = flow("stock_based_compensation", dec!(100));
+        sbc.tax_rate = Some(dec!(25));
+        let posted = post_transaction(&sbc).unwrap();
+        let impacts = statement_impacts(&posted.lines);
+        assert_eq!(posted.tax.deferred_tax, dec!(-25));
+        assert_eq!(impacts.net_income, dec!(-75));
+        assert_eq!(impacts.cash_change, Decimal::ZERO);
+        assert_eq!(balance_change(&posted.lines, Account::DeferredTaxAsset), dec!(25));
+
+        // Accelerated tax depreciation: more cash saved now, deferred tax liability for later
+        let mut depreciation = flow("depreciation", dec!(100));
+        depreciation.tax_rate = Some(dec!(25));
+        depreciation.taxable_amount = Some(dec!(-150));
+        let posted = post_transaction(&depreciation).unwrap();
+        let impacts = statement_impacts(&posted.lines);
+        assert_eq!(impacts.net_income, dec!(-75));
+        assert_eq!(impacts.cash_change, dec!(37.5));
+        assert_eq!(balance_change(&posted.lines, Account::DeferredTaxLiability), dec!(12.5));
+
+        // Non-deductible goodwill impairment: no tax effect at all
+        let mut impairment = flow("impairment", dec!(100));
+        impairment.tax_rate = Some(dec!(25));
+        impairment.permanent_difference = Some(true);
+        let impacts = statement_impacts(&post_transaction(&impairment).unwrap().lines);
+        assert_eq!(impacts.net_income, dec!(-100));
+    }
+
+    #[test]
+    fn test_custom_entry_must_balance() {
+        let mut custom = flow("custom", dec!(50));
+        custom.journal_entries = Some(vec![
+            JournalLine::debit(Account::Cash, dec!(50)),
+            JournalLine::credit(Account::PaidInCapital, dec!(40)),
+        ]);
+        assert!(post_transaction(&custom).is_err());
+
+        custom.journal_entries = Some(vec![
+            JournalLine::debit(Account::Cash, dec!(50)),
+            JournalLine::credit(Account::PaidInCapital, dec!(50)),
+        ]);
+        let impacts = statement_impacts(&post_transaction(&custom).unwrap().lines);
+        assert_eq!(impacts.cash_flow[0].line_item, "Equity Issuance (Financing Activities)");
+    }
+
+    #[test]
+    fn test_unknown_transaction_rejected() {
+        assert!(post_transaction(&flow("barter", dec!(10))).is_err());
+    }
+}
@@ -3,11 +3,31 @@ pub mod three_statement_model;
 pub mod equity_enterprise_bridge;
 pub mod diluted_shares;
 pub mod accounting_flows;
+pub mod journal;
 pub mod sources_uses;
+pub mod pro_forma;
+pub mod purchase_accounting;
+pub mod scenarios;
+pub mod tax_depreciation;
+pub mod fixed_assets;
+pub mod implied_multiples;
 
 pub use types::*;
 pub use three_statement_model::build_three_statement_model;
 pub use equity_enterprise_bridge::equity_enterprise_bridge;
 pub use diluted_shares::calculate_diluted_shares;
-pub use accounting_flows::analyze_accounting_flow;
-pub use sources_uses::{SourcesAndUsesInput, SourcesAndUsesOutput, SourceItem, UseItem, build_sources_and_uses};
+pub use accounting_flows::{analyze_accounting_flow, apply_accounting_flows};
+pub use journal::{post_transaction, standard_transactions, statement_impacts, validate_entry};
+pub use sources_uses::{SourcesAndUsesInput, SourcesAndUsesOutput, SourceItem, UseItem, PlugSource, build_sources_and_uses};
+pub use pro_forma::{ProFormaBalanceSheetInput, ProFormaBalanceSheetOutput, ProFormaLine, build_pro_forma_balance_sheet};
+pub use purchase_accounting::{
+    PurchasePriceAllocationInput, PurchasePriceAllocationOutput, IdentifiedIntangible, IntangibleCategory,
+    AllocationLine, PurchaseAccountingYear, allocate_purchase_price,
+};
+pub use tax_depreciation::{build_tax_depreciation, macrs_rates};
+pub use fixed_assets::build_fixed_asset_schedule;
+pub use scenarios::{ScenarioInput, ScenarioCase, ScenarioOverride, ScenarioField, ScenarioAdjustment, ScenarioOutput, ScenarioResult, ScenarioMetrics, run_scenarios};
+pub use implied_multiples::{
+    ImpliedMultiplesInput, ImpliedMultiplesOutput, PeriodMetrics, MultipleSpec, MultipleNumerator,
+    MultipleMetric, calculate_implied_multiples,
+};
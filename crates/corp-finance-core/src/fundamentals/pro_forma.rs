@@ -0,0 +1,383 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use crate::error::{FinanceError, Result};
+use super::sources_uses::{
+    SourcesAndUsesOutput, CASH_TO_BALANCE_SHEET, FINANCING_FEES, PURCHASE_EQUITY_VALUE, REFINANCE_EXISTING_DEBT,
+    TRANSACTION_FEES,
+};
+use super::types::BalanceSheet;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProFormaBalanceSheetInput {
+    pub target_balance_sheet: BalanceSheet,
+    pub sources_and_uses: SourcesAndUsesOutput,  // the purchase price is its "Purchase Equity Value" use
+    pub capitalize_financing_fees: Option<bool>,  // default true: carried as an asset at close
+    pub capitalize_transaction_fees: Option<bool>,  // default false: expensed against equity at close
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProFormaBalanceSheetOutput {
+    pub adjustment_columns: Vec<String>,
+    pub lines: Vec<ProFormaLine>,
+    pub goodwill: Decimal,
+    pub total_assets: Decimal,
+    pub total_liabilities_equity: Decimal,
+    pub balanced: bool,
+    pub pro_forma_balance_sheet: BalanceSheet,  // goodwill and capitalised fees folded into intangibles
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProFormaLine {
+    pub line_item: String,
+    pub target: Decimal,
+    pub adjustments: Vec<Decimal>,  // one per adjustment column
+    pub pro_forma: Decimal,
+}
+
+const CASH: usize = 0;
+const GOODWILL: usize = 5;
+const CAPITALIZED_FINANCING_FEES: usize = 8;
+const DEBT: usize = 10;
+const EQUITY: usize = 12;
+
+const ASSET_ROWS: [&str; 9] = [
+    "Cash",
+    "Accounts Receivable",
+    "Inventory",
+    "PP&E",
+    "Intangible Assets",
+    "Goodwill",
+    "Equity Investments",
+    "Deferred Tax Asset",
+    "Capitalized Financing Fees",
+];
+
+const LIABILITY_ROWS: [&str; 3] = ["Accounts Payable", "Debt", "Deferred Tax Liability"];
+
+const EQUITY_ROWS: [&str; 2] = ["Shareholders' Equity", "Noncontrolling Interest"];
+
+/// Build the pro forma closing balance sheet for an acquisition
+///
+/// Starting from the target's pre-deal balance sheet, the sources & uses are
+/// applied as separate adjustment columns: new financing raised, purchase of the
+/// target's equity (eliminating its book equity and recognising goodwill for the
+/// excess), refinancing of existing debt, financing fees and transaction fees.
+/// Each column balances on its own. Capitalised financing fees are carried as an
+/// asset at close; their amortisation is not modelled here.
+pub fn build_pro_forma_balance_sheet(input: ProFormaBalanceSheetInput) -> Result<ProFormaBalanceSheetOutput> {
+    let target = &input.target_balance_sheet;
+    let sources_and_uses = &input.sources_and_uses;
+    let tolerance = dec!(0.01);
+
+    let target_values = [
+        target.cash,
+        target.accounts_receivable,
+        target.inventory,
+        target.ppe_net,
+        target.intangibles,
+        Decimal::ZERO,
+        target.equity_investments,
+        target.deferred_tax_asset,
+        Decimal::ZERO,
+        target.accounts_payable,
+        target.debt,
+        target.deferred_tax_liability,
+        target.equity,
+        target.noncontrolling_interest,
+    ];
+    let (target_assets, target_claims) = totals(&target_values);
+    if (target_assets - target_claims).abs() > tolerance {
+        return Err(FinanceError::InvalidInput(format!(
+            "Target balance sheet does not balance: assets {} vs liabilities and equity {}",
+            target_assets, target_claims
+        )));
+    }
+
+    let use_amount = |name: &str| {
+        sources_and_uses
+            .uses
+            .iter()
+            .filter(|item| item.name == name)
+            .map(|item| item.amount)
+            .sum::<Decimal>()
+    };
+    let purchase_equity_value = use_amount(PURCHASE_EQUITY_VALUE);
+    let refinanced_debt = use_amount(REFINANCE_EXISTING_DEBT);
+    let transaction_fees = use_amount(TRANSACTION_FEES);
+    let financing_fees = use_amount(FINANCING_FEES);
+    let standard_uses = [
+        PURCHASE_EQUITY_VALUE,
+        REFINANCE_EXISTING_DEBT,
+        TRANSACTION_FEES,
+        FINANCING_FEES,
+        CASH_TO_BALANCE_SHEET,
+    ];
+    let other_uses: Decimal = sources_and_uses
+        .uses
+        .iter()
+        .filter(|item| !standard_uses.contains(&item.name.as_str()))
+        .map(|item| item.amount)
+        .sum();
+
+    if purchase_equity_value <= Decimal::ZERO {
+        return Err(FinanceError::MissingField(format!(
+            "'{}' use in the sources & uses",
+            PURCHASE_EQUITY_VALUE
+        )));
+    }
+    if refinanced_debt > target.debt + tolerance {
+        return Err(FinanceError::OutOfRange(format!(
+            "Refinanced debt {} exceeds the target's existing debt {}",
+            refinanced_debt, target.debt
+        )));
+    }
+
+    let capitalize_financing_fees = input.capitalize_financing_fees.unwrap_or(true);
+    let capitalize_transaction_fees = input.capitalize_transaction_fees.unwrap_or(false);
+    let purchase_goodwill = purchase_equity_value - target.equity;
+
+    let mut columns: Vec<(String, [Decimal; 14])> = Vec::new();
+    let mut column = |name: &str, entries: &[(usize, Decimal)]| {
+        let mut values = [Decimal::ZERO; 14];
+        for &(row, amount) in entries {
+            values[row] += amount;
+        }
+        columns.push((name.to_string(), values));
+    };
+
+    let new_equity = sources_and_uses.total_sources - sources_and_uses.total_debt;
+    column("New Financing", &[
+        (CASH, sources_and_uses.total_sources),
+        (DEBT, sources_and_uses.total_debt),
+        (EQUITY, new_equity),
+    ]);
+
+    // Negative goodwill is a bargain purchase gain credited to equity
+    column("Purchase of Equity", &[
+        (CASH, -purchase_equity_value),
+        (EQUITY, -target.equity),
+        (GOODWILL, purchase_goodwill.max(Decimal::ZERO)),
+        (EQUITY, -purchase_goodwill.min(Decimal::ZERO)),
+    ]);
+
+    column("Refinance Debt", &[(CASH, -refinanced_debt), (DEBT, -refinanced_debt)]);
+
+    column("Financing Fees", &[
+        (CASH, -financing_fees),
+        if capitalize_financing_fees {
+            (CAPITALIZED_FINANCING_FEES, financing_fees)
+        } else {
+            (EQUITY, -financing_fees)
+        },
+    ]);
+
+    column("Transaction Fees", &[
+        (CASH, -transaction_fees),
+        if capitalize_transaction_fees {
+            (GOODWILL, transaction_fees)
+        } else {
+            (EQUITY, -transaction_fees)
+        },
+    ]);
+
+    if !other_uses.is_zero() {
+        column("Other Uses", &[(CASH, -other_uses), (EQUITY, -other_uses)]);
+    }
+
+    let mut pro_forma_values = target_values;
+    for (_, values) in &columns {
+        for (total, value) in pro_forma_values.iter_mut().zip(values.iter()) {
+            *total += value;
+        }
+    }
+
+    let names: Vec<&str> = ASSET_ROWS
+        .iter()
+        .chain(LIABILITY_ROWS.iter())
+        .chain(EQUITY_ROWS.iter())
+        .copied()
+        .collect();
+    let mut lines = Vec::new();
+    for (row, name) in names.iter().enumerate() {
+        lines.push(ProFormaLine {
+            line_item: name.to_string(),
+            target: target_values[row],
+            adjustments: columns.iter().map(|(_, values)| values[row]).collect(),
+            pro_forma: pro_forma_values[row],
+        });
+        if row == ASSET_ROWS.len() - 1 {
+            lines.push(total_line("Total Assets", &target_values, &pro_forma_values, &columns, 0..ASSET_ROWS.len()));
+        }
+    }
+    lines.push(total_line(
+        "Total Liabilities & Equity",
+        &target_values,
+        &pro_forma_values,
+        &columns,
+        ASSET_ROWS.len()..names.len(),
+    ));
+
+    let (total_assets, total_liabilities_equity) = totals(&pro_forma_values);
+    let value = |row: usize| pro_forma_values[row];
+
+    Ok(ProFormaBalanceSheetOutput {
+        adjustment_columns: columns.iter().map(|(name, _)| name.clone()).collect(),
+        lines,
+        goodwill: value(GOODWILL),
+        total_assets,
+        total_liabilities_equity,
+        balanced: (total_assets - total_liabilities_equity).abs() <= tolerance,
+        pro_forma_balance_sheet: BalanceSheet {
+            cash: value(0),
+            accounts_receivable: value(1),
+            inventory: value(2),
+            ppe_net: value(3),
+            intangibles: value(4) + value(GOODWILL) + value(CAPITALIZED_FINANCING_FEES),
+            equity_investments: value(6),
+            deferred_tax_asset: value(7),
+            total_assets,
+            accounts_payable: value(9),
+            debt: value(DEBT),
+            deferred_tax_liability: value(11),
+            equity: value(EQUITY),
+            noncontrolling_interest: value(13),
+            total_liabilities_equity,
+        },
+    })
+}
+
+fn totals(values: &[Decimal; 14]) -> (Decimal, Decimal) {
+    let assets = values[..ASSET_ROWS.len()].iter().sum();
+    let claims = values[ASSET_ROWS.len()..].iter().sum();
+    (assets, claims)
+}
+
+fn total_line(
+    name: &str,
+    target: &[Decimal; 14],
+    pro_forma: &[Decimal; 14],
+    columns: &[(String, [Decimal; 14])],
+    rows: std::ops::Range<usize>,
+) -> ProFormaLine {
+    ProFormaLine {
+        line_item: name.to_string(),
+        target: target[rows.clone()].iter().sum(),
+        adjustments: columns
+            .iter()
+            .map(|(_, values)| values[rows.clone()].iter().sum())
+            .collect(),
+        pro_forma: pro_forma[rows].iter().sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::sources_uses::{build_sources_and_uses, SourcesAndUsesInput};
+
+    fn target() -> BalanceSheet {
+        BalanceSheet {
+            cash: dec!(50),
+            accounts_receivable: dec!(100),
+            inventory: dec!(80),
+            ppe_net: dec!(400),
+            intangibles: dec!(70),
+            equity_investments: dec!(0),
+            deferred_tax_asset: dec!(0),
+            total_assets: dec!(700),
+            accounts_payable: dec!(60),
+            debt: dec!(200),
+            deferred_tax_liability: dec!(40),
+            equity: dec!(400),
+            noncontrolling_interest: dec!(0),
+            total_liabilities_equity: dec!(700),
+        }
+    }
+
+    fn sources_and_uses() -> SourcesAndUsesOutput {
+        build_sources_and_uses(SourcesAndUsesInput {
+            senior_debt: dec!(500),
+            subordinated_debt: dec!(100),
+            equity_contribution: dec!(0),
+            rollover_equity: dec!(0),
+            seller_note: None,
+            other_sources: vec![],
+            purchase_equity_value: dec!(800),
+            refinanced_debt: dec!(200),
+            transaction_fees: dec!(20),
+            financing_fees: dec!(12),
+            other_uses: vec![],
+            minimum_cash: Some(dec!(30)),
+            financing_fee_percent: None,
+            advisory_fee_percent: None,
+            enterprise_value: None,
+            balance_with_plug: Some(true),
+            plug_source: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pro_forma_closing_balance_sheet() {
+        let input = ProFormaBalanceSheetInput {
+            target_balance_sheet: target(),
+            sources_and_uses: sources_and_uses(),
+            capitalize_financing_fees: None,
+            capitalize_transaction_fees: None,
+        };
+
+        let result = build_pro_forma_balance_sheet(input).unwrap();
+        assert!(result.balanced);
+
+        // Goodwill = 800 price − 400 book equity
+        assert_eq!(result.goodwill, dec!(400));
+
+        let line = |name: &str| result.lines.iter().find(|line| line.line_item == name).unwrap();
+
+        // Cash: 50 existing + 30 minimum cash from the S&U
+        assert_eq!(line("Cash").pro_forma, dec!(80));
+        // Debt: 200 refinanced, 600 new
+        assert_eq!(line("Debt").pro_forma, dec!(600));
+        assert_eq!(line("Capitalized Financing Fees").pro_forma, dec!(12));
+        // Equity: 462 sponsor equity less 20 expensed transaction fees
+        assert_eq!(line("Shareholders' Equity").pro_forma, dec!(442));
+
+        // Every adjustment column balances
+        let assets = line("Total Assets");
+        let claims = line("Total Liabilities & Equity");
+        for (asset_change, claim_change) in assets.adjustments.iter().zip(claims.adjustments.iter()) {
+            assert_eq!(asset_change, claim_change);
+        }
+    }
+
+    #[test]
+    fn test_capitalized_transaction_fees_go_to_goodwill() {
+        let input = ProFormaBalanceSheetInput {
+            target_balance_sheet: target(),
+            sources_and_uses: sources_and_uses(),
+            capitalize_financing_fees: Some(false),
+            capitalize_transaction_fees: Some(true),
+        };
+
+        let result = build_pro_forma_balance_sheet(input).unwrap();
+        assert!(result.balanced);
+        assert_eq!(result.goodwill, dec!(420));
+        assert_eq!(result.pro_forma_balance_sheet.equity, dec!(450));
+    }
+
+    #[test]
+    fn test_purchase_price_comes_from_sources_and_uses() {
+        let mut sources_and_uses = sources_and_uses();
+        sources_and_uses.uses.retain(|item| item.name != PURCHASE_EQUITY_VALUE);
+        let input = ProFormaBalanceSheetInput {
+            target_balance_sheet: target(),
+            sources_and_uses,
+            capitalize_financing_fees: None,
+            capitalize_transaction_fees: None,
+        };
+
+        assert!(build_pro_forma_balance_sheet(input).is_err());
+    }
+}
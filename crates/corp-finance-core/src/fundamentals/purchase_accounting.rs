@@ -0,0 +1,388 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use crate::error::{FinanceError, Result};
+use super::types::PurchaseAccountingAdjustments;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchasePriceAllocationInput {
+    pub purchase_price: Decimal,                 // equity purchase price paid for the target
+    pub target_book_equity: Decimal,             // book value of net assets acquired
+    pub existing_goodwill: Option<Decimal>,      // target's goodwill, written off
+    pub existing_intangibles: Option<Decimal>,   // target's book intangibles, replaced by those identified
+    pub ppe_step_up: Option<Decimal>,
+    pub ppe_useful_life: Option<u32>,            // years, straight-line
+    pub inventory_step_up: Option<Decimal>,
+    pub inventory_release_years: Option<u32>,    // years to sell through, default 1
+    pub intangibles: Vec<IdentifiedIntangible>,
+    pub tax_rate: Decimal,                       // as %
+    pub tax_deductible_step_up: Option<bool>,    // asset deal / 338(h)(10) election, default false
+    pub projection_years: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntangibleCategory {
+    CustomerRelationships,
+    Technology,
+    Brand,
+    Other,
+}
+
+/// Intangible asset identified in the allocation, at fair value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentifiedIntangible {
+    pub name: String,
+    pub category: IntangibleCategory,
+    pub fair_value: Decimal,
+    pub useful_life: Option<u32>,  // years, straight-line; None = indefinite-lived, not amortised
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchasePriceAllocationOutput {
+    pub allocation: Vec<AllocationLine>,
+    pub total_step_up: Decimal,               // excess of fair value over tax basis
+    pub deferred_tax_liability: Decimal,
+    pub fair_value_net_assets: Decimal,
+    pub goodwill: Decimal,
+    pub bargain_purchase_gain: Decimal,
+    pub schedule: Vec<PurchaseAccountingYear>,
+    pub three_statement_adjustments: PurchaseAccountingAdjustments,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationLine {
+    pub item: String,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseAccountingYear {
+    pub year: u32,
+    pub ppe_depreciation: Decimal,
+    pub intangible_amortization: Decimal,
+    pub incremental_d_and_a: Decimal,
+    pub inventory_step_up_release: Decimal,
+    pub pre_tax_charge: Decimal,
+    pub tax_amortization: Decimal,  // carried-over tax basis of the target's intangibles
+    pub deferred_tax_unwind: Decimal,
+    pub after_tax_charge: Decimal,
+    pub ending_deferred_tax_liability: Decimal,
+}
+
+/// Allocate an acquisition's purchase price and project the incremental D&A
+///
+/// The target's book equity is adjusted by writing off its existing goodwill and
+/// intangibles, stepping PP&E, inventory and identified intangibles up to fair value,
+/// and recognising a DTL on the step-up when it is not tax deductible. Goodwill is
+/// the excess of the purchase price over the fair value of net assets; a shortfall
+/// is a bargain purchase gain.
+///
+/// In a stock deal the target's existing intangibles keep their tax basis, which is
+/// spread across the identified intangibles by fair value and amortised for tax over
+/// their lives. The DTL unwinds only on the book charges above that deduction, so
+/// once every finite life has run it is left with the indefinite-lived step-up.
+///
+/// `three_statement_adjustments` carries the step-ups and charges into
+/// `build_three_statement_model` on top of the acquirer's pre-deal combined book
+/// balances (which still include the target's existing goodwill and intangibles).
+pub fn allocate_purchase_price(input: PurchasePriceAllocationInput) -> Result<PurchasePriceAllocationOutput> {
+    validate(&input)?;
+
+    let tax_rate = input.tax_rate / dec!(100);
+    let tax_deductible = input.tax_deductible_step_up.unwrap_or(false);
+    let existing_goodwill = input.existing_goodwill.unwrap_or(Decimal::ZERO);
+    let existing_intangibles = input.existing_intangibles.unwrap_or(Decimal::ZERO);
+    let ppe_step_up = input.ppe_step_up.unwrap_or(Decimal::ZERO);
+    let inventory_step_up = input.inventory_step_up.unwrap_or(Decimal::ZERO);
+    let identified_intangibles: Decimal = input.intangibles.iter().map(|i| i.fair_value).sum();
+
+    // Existing intangibles are assumed to have a tax basis equal to book
+    let total_step_up = ppe_step_up + inventory_step_up + identified_intangibles - existing_intangibles;
+    let deferred_tax_liability = if tax_deductible {
+        Decimal::ZERO
+    } else {
+        total_step_up * tax_rate
+    };
+
+    let fair_value_net_assets = input.target_book_equity - existing_goodwill + total_step_up - deferred_tax_liability;
+    let excess = input.purchase_price - fair_value_net_assets;
+    let goodwill = excess.max(Decimal::ZERO);
+    let bargain_purchase_gain = (-excess).max(Decimal::ZERO);
+
+    let mut allocation = vec![
+        line("Book Value of Net Assets", input.target_book_equity),
+        line("Less: Existing Goodwill", -existing_goodwill),
+        line("Less: Existing Intangibles", -existing_intangibles),
+        line("PP&E Step-Up", ppe_step_up),
+        line("Inventory Step-Up", inventory_step_up),
+    ];
+    for intangible in &input.intangibles {
+        allocation.push(line(&intangible.name, intangible.fair_value));
+    }
+    allocation.push(line("Less: Deferred Tax Liability", -deferred_tax_liability));
+    allocation.push(line("Fair Value of Net Assets", fair_value_net_assets));
+    allocation.push(line("Goodwill", goodwill));
+    if !bargain_purchase_gain.is_zero() {
+        allocation.push(line("Less: Bargain Purchase Gain", -bargain_purchase_gain));
+    }
+    allocation.push(line("Purchase Price", input.purchase_price));
+
+    // Incremental charges by year
+    let num_years = input.projection_years as usize;
+    let depreciation = straight_line(ppe_step_up, input.ppe_useful_life.unwrap_or(1), num_years);
+    let inventory_release = straight_line(inventory_step_up, input.inventory_release_years.unwrap_or(1), num_years);
+    // The target's existing tax basis carries over in a stock deal: spread across the
+    // identified intangibles by fair value and amortised for tax over the same lives
+    let mut amortization = vec![Decimal::ZERO; num_years];
+    let mut tax_amortization = vec![Decimal::ZERO; num_years];
+    for intangible in &input.intangibles {
+        if let Some(life) = intangible.useful_life {
+            for (total, charge) in amortization.iter_mut().zip(straight_line(intangible.fair_value, life, num_years)) {
+                *total += charge;
+            }
+            if !tax_deductible && identified_intangibles > Decimal::ZERO {
+                let tax_basis = existing_intangibles * intangible.fair_value / identified_intangibles;
+                for (total, charge) in tax_amortization.iter_mut().zip(straight_line(tax_basis, life, num_years)) {
+                    *total += charge;
+                }
+            }
+        }
+    }
+
+    let mut schedule = Vec::new();
+    let mut dtl = deferred_tax_liability;
+    for year in 0..num_years {
+        let incremental_d_and_a = depreciation[year] + amortization[year];
+        let pre_tax_charge = incremental_d_and_a + inventory_release[year];
+        // Only the book charge above the carried-over tax deduction unwinds the DTL
+        let deferred_tax_unwind = if tax_deductible {
+            Decimal::ZERO
+        } else {
+            (pre_tax_charge - tax_amortization[year]) * tax_rate
+        };
+        dtl -= deferred_tax_unwind;
+
+        schedule.push(PurchaseAccountingYear {
+            year: year as u32 + 1,
+            ppe_depreciation: depreciation[year],
+            intangible_amortization: amortization[year],
+            incremental_d_and_a,
+            inventory_step_up_release: inventory_release[year],
+            pre_tax_charge,
+            tax_amortization: tax_amortization[year],
+            deferred_tax_unwind,
+            after_tax_charge: pre_tax_charge * (Decimal::ONE - tax_rate),
+            ending_deferred_tax_liability: dtl,
+        });
+    }
+
+    let three_statement_adjustments = PurchaseAccountingAdjustments {
+        ppe_step_up,
+        intangibles_step_up: identified_intangibles + goodwill - existing_goodwill - existing_intangibles,
+        inventory_step_up,
+        deferred_tax_liability,
+        depreciation,
+        amortization,
+        inventory_step_up_release: inventory_release,
+        tax_amortization: Some(tax_amortization),
+        tax_deductible,
+    };
+
+    Ok(PurchasePriceAllocationOutput {
+        allocation,
+        total_step_up,
+        deferred_tax_liability,
+        fair_value_net_assets,
+        goodwill,
+        bargain_purchase_gain,
+        schedule,
+        three_statement_adjustments,
+    })
+}
+
+fn validate(input: &PurchasePriceAllocationInput) -> Result<()> {
+    if input.purchase_price <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "purchase_price must be positive".to_string(),
+        ));
+    }
+    if input.projection_years == 0 {
+        return Err(FinanceError::InvalidInput(
+            "projection_years must be at least 1".to_string(),
+        ));
+    }
+    if input.tax_rate < Decimal::ZERO || input.tax_rate >= dec!(100) {
+        return Err(FinanceError::OutOfRange(
+            "tax_rate must be between 0 and 100".to_string(),
+        ));
+    }
+    if input.ppe_step_up.is_some_and(|step_up| !step_up.is_zero()) && input.ppe_useful_life.is_none() {
+        return Err(FinanceError::MissingField(
+            "ppe_useful_life is required with a PP&E step-up".to_string(),
+        ));
+    }
+    if input.ppe_useful_life == Some(0) || input.inventory_release_years == Some(0) {
+        return Err(FinanceError::InvalidInput(
+            "Useful lives and release periods must be at least 1 year".to_string(),
+        ));
+    }
+    for intangible in &input.intangibles {
+        if intangible.fair_value < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(format!(
+                "{} fair value cannot be negative",
+                intangible.name
+            )));
+        }
+        if intangible.useful_life == Some(0) {
+            return Err(FinanceError::InvalidInput(format!(
+                "{} useful_life must be at least 1 year",
+                intangible.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn line(item: &str, amount: Decimal) -> AllocationLine {
+    AllocationLine {
+        item: item.to_string(),
+        amount,
+    }
+}
+
+/// Straight-line charges over `life` years, truncated to the projection period
+fn straight_line(amount: Decimal, life: u32, num_years: usize) -> Vec<Decimal> {
+    (0..num_years)
+        .map(|year| {
+            if year < life as usize {
+                amount / Decimal::from(life)
+            } else {
+                Decimal::ZERO
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn intangible(name: &str, category: IntangibleCategory, fair_value: Decimal, life: Option<u32>) -> IdentifiedIntangible {
+        IdentifiedIntangible {
+            name: name.to_string(),
+            category,
+            fair_value,
+            useful_life: life,
+        }
+    }
+
+    fn input() -> PurchasePriceAllocationInput {
+        PurchasePriceAllocationInput {
+            purchase_price: dec!(1000),
+            target_book_equity: dec!(400),
+            existing_goodwill: Some(dec!(50)),
+            existing_intangibles: Some(dec!(30)),
+            ppe_step_up: Some(dec!(100)),
+            ppe_useful_life: Some(10),
+            inventory_step_up: Some(dec!(20)),
+            inventory_release_years: None,
+            intangibles: vec![
+                intangible("Customer Relationships", IntangibleCategory::CustomerRelationships, dec!(200), Some(10)),
+                intangible("Developed Technology", IntangibleCategory::Technology, dec!(100), Some(5)),
+                intangible("Trade Name", IntangibleCategory::Brand, dec!(80), None),
+            ],
+            tax_rate: dec!(25),
+            tax_deductible_step_up: None,
+            projection_years: 6,
+        }
+    }
+
+    #[test]
+    fn test_stock_deal_allocation() {
+        let result = allocate_purchase_price(input()).unwrap();
+
+        // Step-up over tax basis: 100 + 20 + 380 - 30 = 470; DTL at 25%
+        assert_eq!(result.total_step_up, dec!(470));
+        assert_eq!(result.deferred_tax_liability, dec!(117.5));
+
+        // FV net assets = 400 - 50 + 470 - 117.5
+        assert_eq!(result.fair_value_net_assets, dec!(702.5));
+        assert_eq!(result.goodwill, dec!(297.5));
+
+        // Year 1: 10 depreciation, 20 + 20 amortisation, 20 inventory release
+        let year1 = &result.schedule[0];
+        assert_eq!(year1.incremental_d_and_a, dec!(50));
+        assert_eq!(year1.pre_tax_charge, dec!(70));
+
+        // 30 of existing basis split 200:100:80; 15.79 / 10 + 7.89 / 5 deducted for tax
+        assert!((year1.tax_amortization - dec!(3.1579)).abs() < dec!(0.0001));
+        assert_eq!(year1.deferred_tax_unwind, (dec!(70) - year1.tax_amortization) * dec!(0.25));
+
+        // Year 6: technology fully amortised, the trade name is never amortised
+        let year6 = &result.schedule[5];
+        assert_eq!(year6.intangible_amortization, dec!(20));
+        assert_eq!(year6.inventory_step_up_release, Decimal::ZERO);
+
+        let adjustments = &result.three_statement_adjustments;
+        assert_eq!(adjustments.intangibles_step_up, dec!(597.5));
+        assert!(!adjustments.tax_deductible);
+    }
+
+    #[test]
+    fn test_dtl_unwinds_to_indefinite_lived_residual() {
+        let result = allocate_purchase_price(PurchasePriceAllocationInput {
+            projection_years: 12,
+            ..input()
+        })
+        .unwrap();
+
+        // Trade name step-up over its 30 × 80 / 380 share of the basis stays deferred
+        let residual = (dec!(80) - dec!(30) * dec!(80) / dec!(380)) * dec!(0.25);
+        let ending = result.schedule[11].ending_deferred_tax_liability;
+        assert!((ending - residual).abs() < dec!(0.000001));
+
+        // With every intangible finite-lived the DTL runs off completely
+        let mut finite = input();
+        finite.intangibles.pop();
+        finite.projection_years = 12;
+        let result = allocate_purchase_price(finite).unwrap();
+        assert_eq!(result.deferred_tax_liability, dec!(97.5));
+        assert!(result.schedule[11].ending_deferred_tax_liability.abs() < dec!(0.000001));
+    }
+
+    #[test]
+    fn test_tax_deductible_step_up_has_no_dtl() {
+        let result = allocate_purchase_price(PurchasePriceAllocationInput {
+            tax_deductible_step_up: Some(true),
+            ..input()
+        })
+        .unwrap();
+
+        assert_eq!(result.deferred_tax_liability, Decimal::ZERO);
+        assert_eq!(result.goodwill, dec!(180));
+        assert_eq!(result.schedule[0].deferred_tax_unwind, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_bargain_purchase() {
+        let result = allocate_purchase_price(PurchasePriceAllocationInput {
+            purchase_price: dec!(600),
+            ..input()
+        })
+        .unwrap();
+
+        assert_eq!(result.goodwill, Decimal::ZERO);
+        assert_eq!(result.bargain_purchase_gain, dec!(102.5));
+    }
+
+    #[test]
+    fn test_step_up_requires_life() {
+        let result = allocate_purchase_price(PurchasePriceAllocationInput {
+            ppe_useful_life: None,
+            ..input()
+        });
+        assert!(result.is_err());
+    }
+}
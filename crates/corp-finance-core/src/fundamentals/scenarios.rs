@@ -0,0 +1,455 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::error::{FinanceError, Result};
+use super::three_statement_model::build_three_statement_model;
+use super::types::{ThreeStatementInput, ThreeStatementOutput};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioInput {
+    pub base: ThreeStatementInput,
+    pub cases: Vec<ScenarioCase>,
+}
+
+/// A named case expressed as sparse overrides to the base input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioCase {
+    pub name: String,
+    pub overrides: Vec<ScenarioOverride>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioOverride {
+    pub field: ScenarioField,
+    pub adjustment: ScenarioAdjustment,
+    pub value: Decimal,      // % fields are in percentage points (1 = 100bps)
+    pub years: Option<Vec<u32>>,  // 1-based projection years; all years if omitted
+}
+
+/// Base input assumptions a scenario can override
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioField {
+    RevenueGrowth,  // implied year-on-year growth, from year 2
+    // Per-year schedules
+    Revenue,
+    Capex,
+    Depreciation,
+    Amortization,
+    OtherIncome,
+    // Margins, scoped to years through the per-year margin paths
+    CogsPercent,
+    OpexPercent,
+    SbcPercent,
+    // Single assumptions for all years
+    TaxRate,
+    NwcPercentRevenue,
+    InterestRate,
+    InterestIncomeRate,
+    DividendPayoutRatio,
+    BuybackPercentFcf,
+}
+
+impl ScenarioField {
+    fn name(self) -> &'static str {
+        match self {
+            ScenarioField::RevenueGrowth => "revenue_growth",
+            ScenarioField::Revenue => "revenue",
+            ScenarioField::Capex => "capex",
+            ScenarioField::Depreciation => "depreciation",
+            ScenarioField::Amortization => "amortization",
+            ScenarioField::OtherIncome => "other_income",
+            ScenarioField::CogsPercent => "cogs_percent",
+            ScenarioField::OpexPercent => "opex_percent",
+            ScenarioField::SbcPercent => "sbc_percent",
+            ScenarioField::TaxRate => "tax_rate",
+            ScenarioField::NwcPercentRevenue => "nwc_percent_revenue",
+            ScenarioField::InterestRate => "interest_rate",
+            ScenarioField::InterestIncomeRate => "interest_income_rate",
+            ScenarioField::DividendPayoutRatio => "dividend_payout_ratio",
+            ScenarioField::BuybackPercentFcf => "buyback_percent_fcf",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioAdjustment {
+    Set,
+    Add,
+    PercentChange,  // value is the % change, e.g. 10 scales by 1.1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioOutput {
+    pub cases: Vec<ScenarioResult>,  // base case first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub model: ThreeStatementOutput,
+    pub key_metrics: Vec<ScenarioMetrics>,
+    pub deltas_vs_base: Vec<ScenarioMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioMetrics {
+    pub year: u32,
+    pub revenue: Decimal,
+    pub ebitda: Decimal,
+    pub ebitda_margin: Decimal,  // as %
+    pub net_income: Decimal,
+    pub free_cash_flow: Decimal,  // CFO - capex
+    pub cash: Decimal,
+}
+
+/// Run a base three-statement model plus named cases in one call
+///
+/// Each case starts from the base input and applies its overrides in order.
+/// Per-year schedules (revenue, capex, depreciation, amortization, other_income)
+/// can be overridden for selected years; `revenue_growth` shifts the implied
+/// year-on-year growth rate from year 2 onwards and re-chains the revenue path.
+/// Margins (COGS, opex, SBC) can also be scoped to years; they are written to the
+/// model's per-year margin paths. Other scalar assumptions (rates, payout) apply to
+/// every year.
+pub fn run_scenarios(input: ScenarioInput) -> Result<ScenarioOutput> {
+    let mut names = HashSet::new();
+    names.insert("Base".to_string());
+    for case in &input.cases {
+        if !names.insert(case.name.clone()) {
+            return Err(FinanceError::InvalidInput(format!(
+                "Duplicate scenario name '{}'",
+                case.name
+            )));
+        }
+    }
+
+    let base_model = build_three_statement_model(input.base.clone())?;
+    let base_metrics = key_metrics(&base_model);
+
+    let mut cases = vec![ScenarioResult {
+        name: "Base".to_string(),
+        deltas_vs_base: metric_deltas(&base_metrics, &base_metrics),
+        key_metrics: base_metrics.clone(),
+        model: base_model,
+    }];
+
+    for case in &input.cases {
+        let mut case_input = input.base.clone();
+        for adjustment in &case.overrides {
+            apply_override(&mut case_input, adjustment).map_err(|e| {
+                FinanceError::InvalidInput(format!("Scenario '{}': {}", case.name, e))
+            })?;
+        }
+
+        let model = build_three_statement_model(case_input)?;
+        let metrics = key_metrics(&model);
+
+        cases.push(ScenarioResult {
+            name: case.name.clone(),
+            deltas_vs_base: metric_deltas(&metrics, &base_metrics),
+            key_metrics: metrics,
+            model,
+        });
+    }
+
+    Ok(ScenarioOutput { cases })
+}
+
+fn apply_override(input: &mut ThreeStatementInput, adjustment: &ScenarioOverride) -> Result<()> {
+    let num_years = input.revenue.len();
+    let applies = |year: usize| match &adjustment.years {
+        Some(years) => years.contains(&(year as u32 + 1)),
+        None => true,
+    };
+
+    if let Some(years) = &adjustment.years {
+        if let Some(bad) = years.iter().find(|&&y| y == 0 || y as usize > num_years) {
+            return Err(FinanceError::OutOfRange(format!(
+                "year {} is outside the 1-{} projection period",
+                bad, num_years
+            )));
+        }
+    }
+
+    match adjustment.field {
+        ScenarioField::RevenueGrowth => {
+            if adjustment.years.as_ref().is_some_and(|years| years.contains(&1)) {
+                return Err(FinanceError::InvalidInput(
+                    "revenue_growth overrides apply from year 2 (year 1 growth is not defined)"
+                        .to_string(),
+                ));
+            }
+
+            let original = input.revenue.clone();
+            for year in 1..num_years {
+                if original[year - 1] == Decimal::ZERO {
+                    return Err(FinanceError::DivisionByZero(format!(
+                        "revenue growth for year {}",
+                        year + 1
+                    )));
+                }
+                let growth = (original[year] / original[year - 1] - Decimal::ONE) * dec!(100);
+                let new_growth = if applies(year) {
+                    adjust(growth, adjustment)
+                } else {
+                    growth
+                };
+                input.revenue[year] = input.revenue[year - 1] * (Decimal::ONE + new_growth / dec!(100));
+            }
+        }
+        ScenarioField::Depreciation | ScenarioField::Amortization if input.fixed_assets.is_some() => {
+            return Err(FinanceError::InvalidInput(format!(
+                "{} comes from the fixed asset schedule; override capex or the fixed asset assumptions instead",
+                adjustment.field.name()
+            )));
+        }
+        ScenarioField::Revenue
+        | ScenarioField::Capex
+        | ScenarioField::Depreciation
+        | ScenarioField::Amortization
+        | ScenarioField::OtherIncome => {
+            let schedule = match adjustment.field {
+                ScenarioField::Revenue => &mut input.revenue,
+                ScenarioField::Capex => &mut input.capex,
+                ScenarioField::Depreciation => &mut input.depreciation,
+                ScenarioField::Amortization => input.amortization.get_or_insert_with(|| vec![Decimal::ZERO; num_years]),
+                _ => input.other_income.get_or_insert_with(|| vec![Decimal::ZERO; num_years]),
+            };
+            for (year, value) in schedule.iter_mut().enumerate().take(num_years) {
+                if applies(year) {
+                    *value = adjust(*value, adjustment);
+                }
+            }
+        }
+        ScenarioField::CogsPercent | ScenarioField::OpexPercent | ScenarioField::SbcPercent => {
+            let (scalar, by_year) = match adjustment.field {
+                ScenarioField::CogsPercent => (input.cogs_percent, &mut input.cogs_percent_by_year),
+                ScenarioField::OpexPercent => (input.opex_percent, &mut input.opex_percent_by_year),
+                _ => (input.sbc_percent.unwrap_or(Decimal::ZERO), &mut input.sbc_percent_by_year),
+            };
+            let schedule = by_year.get_or_insert_with(|| vec![scalar; num_years]);
+            for (year, value) in schedule.iter_mut().enumerate().take(num_years) {
+                if applies(year) {
+                    *value = adjust(*value, adjustment);
+                }
+            }
+        }
+        ScenarioField::TaxRate
+        | ScenarioField::NwcPercentRevenue
+        | ScenarioField::InterestRate
+        | ScenarioField::InterestIncomeRate
+        | ScenarioField::DividendPayoutRatio
+        | ScenarioField::BuybackPercentFcf => {
+            if adjustment.years.is_some() {
+                return Err(FinanceError::InvalidInput(format!(
+                    "{} is a single assumption for all years and cannot be overridden by year",
+                    adjustment.field.name()
+                )));
+            }
+            let value = match adjustment.field {
+                ScenarioField::TaxRate => &mut input.tax_rate,
+                ScenarioField::NwcPercentRevenue => &mut input.nwc_percent_revenue,
+                ScenarioField::InterestRate => &mut input.interest_rate,
+                ScenarioField::InterestIncomeRate => input.interest_income_rate.get_or_insert(Decimal::ZERO),
+                ScenarioField::DividendPayoutRatio => input.dividend_payout_ratio.get_or_insert(Decimal::ZERO),
+                _ => input.buyback_percent_fcf.get_or_insert(Decimal::ZERO),
+            };
+            *value = adjust(*value, adjustment);
+        }
+    }
+
+    Ok(())
+}
+
+fn adjust(value: Decimal, adjustment: &ScenarioOverride) -> Decimal {
+    match adjustment.adjustment {
+        ScenarioAdjustment::Set => adjustment.value,
+        ScenarioAdjustment::Add => value + adjustment.value,
+        ScenarioAdjustment::PercentChange => value * (Decimal::ONE + adjustment.value / dec!(100)),
+    }
+}
+
+fn key_metrics(model: &ThreeStatementOutput) -> Vec<ScenarioMetrics> {
+    model
+        .years
+        .iter()
+        .enumerate()
+        .map(|(i, &year)| {
+            let is = &model.income_statements[i];
+            let cf = &model.cash_flows[i];
+            let ebitda_margin = if is.revenue != Decimal::ZERO {
+                is.ebitda / is.revenue * dec!(100)
+            } else {
+                Decimal::ZERO
+            };
+            ScenarioMetrics {
+                year,
+                revenue: is.revenue,
+                ebitda: is.ebitda,
+                ebitda_margin,
+                net_income: is.net_income,
+                free_cash_flow: cf.cfo - cf.capex,
+                cash: model.balance_sheets[i].cash,
+            }
+        })
+        .collect()
+}
+
+fn metric_deltas(case: &[ScenarioMetrics], base: &[ScenarioMetrics]) -> Vec<ScenarioMetrics> {
+    case.iter()
+        .zip(base.iter())
+        .map(|(c, b)| ScenarioMetrics {
+            year: c.year,
+            revenue: c.revenue - b.revenue,
+            ebitda: c.ebitda - b.ebitda,
+            ebitda_margin: c.ebitda_margin - b.ebitda_margin,
+            net_income: c.net_income - b.net_income,
+            free_cash_flow: c.free_cash_flow - b.free_cash_flow,
+            cash: c.cash - b.cash,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::three_statement_model::base_input;
+    use super::super::types::{DepreciationMethod, FixedAssetInput};
+
+    fn override_of(
+        field: ScenarioField,
+        adjustment: ScenarioAdjustment,
+        value: Decimal,
+        years: Option<Vec<u32>>,
+    ) -> ScenarioOverride {
+        ScenarioOverride {
+            field,
+            adjustment,
+            value,
+            years,
+        }
+    }
+
+    #[test]
+    fn test_downside_growth_and_margin() {
+        let input = ScenarioInput {
+            base: base_input(),
+            cases: vec![ScenarioCase {
+                name: "Downside".to_string(),
+                overrides: vec![
+                    override_of(ScenarioField::RevenueGrowth, ScenarioAdjustment::Add, dec!(-5), Some(vec![2, 3])),
+                    override_of(ScenarioField::OpexPercent, ScenarioAdjustment::Add, dec!(1), None),
+                ],
+            }],
+        };
+
+        let result = run_scenarios(input).unwrap();
+        assert_eq!(result.cases.len(), 2);
+        assert_eq!(result.cases[0].name, "Base");
+
+        let downside = &result.cases[1];
+        // Year 1 unchanged, growth drops from 10% to 5% in years 2 and 3
+        assert_eq!(downside.key_metrics[0].revenue, dec!(1000));
+        assert_eq!(downside.key_metrics[1].revenue, dec!(1050));
+        assert_eq!(downside.key_metrics[2].revenue, dec!(1102.5));
+
+        // Margin down 100bps in every year
+        assert_eq!(downside.deltas_vs_base[0].ebitda_margin, dec!(-1));
+        assert_eq!(downside.deltas_vs_base[0].ebitda, dec!(-10));
+        assert_eq!(downside.deltas_vs_base[1].revenue, dec!(-50));
+
+        // Base deltas are zero
+        assert_eq!(result.cases[0].deltas_vs_base[2].net_income, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_schedule_overrides_by_year() {
+        let input = ScenarioInput {
+            base: base_input(),
+            cases: vec![ScenarioCase {
+                name: "Capex push".to_string(),
+                overrides: vec![
+                    override_of(ScenarioField::Capex, ScenarioAdjustment::PercentChange, dec!(50), Some(vec![1])),
+                    override_of(ScenarioField::OtherIncome, ScenarioAdjustment::Set, dec!(20), Some(vec![3])),
+                ],
+            }],
+        };
+
+        let result = run_scenarios(input).unwrap();
+        let model = &result.cases[1].model;
+        assert_eq!(model.cash_flows[0].capex, dec!(150));
+        assert_eq!(model.cash_flows[1].capex, dec!(110));
+        assert_eq!(model.income_statements[2].other_income, dec!(20));
+        assert_eq!(result.cases[1].deltas_vs_base[0].free_cash_flow, dec!(-50));
+    }
+
+    #[test]
+    fn test_margin_override_by_year() {
+        let input = ScenarioInput {
+            base: base_input(),
+            cases: vec![ScenarioCase {
+                name: "Margin squeeze".to_string(),
+                overrides: vec![override_of(ScenarioField::CogsPercent, ScenarioAdjustment::Add, dec!(2), Some(vec![2, 3]))],
+            }],
+        };
+
+        let result = run_scenarios(input).unwrap();
+        let deltas = &result.cases[1].deltas_vs_base;
+        assert_eq!(deltas[0].ebitda_margin, Decimal::ZERO);
+        assert_eq!(deltas[1].ebitda_margin, dec!(-2));
+        assert_eq!(deltas[2].ebitda, dec!(-24.2));
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let bad_field = r#"{"field": "ebitda", "adjustment": "add", "value": 1, "years": null}"#;
+        assert!(serde_json::from_str::<ScenarioOverride>(bad_field).is_err());
+        let bad_adjustment = r#"{"field": "tax_rate", "adjustment": "multiply", "value": 1, "years": null}"#;
+        assert!(serde_json::from_str::<ScenarioOverride>(bad_adjustment).is_err());
+        let parsed: ScenarioOverride =
+            serde_json::from_str(r#"{"field": "cogs_percent", "adjustment": "percent_change", "value": 5, "years": [2]}"#)
+                .unwrap();
+        assert_eq!(parsed.field, ScenarioField::CogsPercent);
+        assert_eq!(parsed.adjustment, ScenarioAdjustment::PercentChange);
+
+        let scalar_by_year = ScenarioInput {
+            base: base_input(),
+            cases: vec![ScenarioCase {
+                name: "Bad".to_string(),
+                overrides: vec![override_of(ScenarioField::TaxRate, ScenarioAdjustment::Set, dec!(30), Some(vec![2]))],
+            }],
+        };
+        assert!(run_scenarios(scalar_by_year).is_err());
+
+        let mut fixed_asset_base = base_input();
+        fixed_asset_base.fixed_assets = Some(FixedAssetInput {
+            method: DepreciationMethod::StraightLine,
+            useful_life: 10,
+            declining_balance_rate: None,
+            existing_ppe_remaining_life: Some(8),
+            intangibles_remaining_life: None,
+            intangible_additions: None,
+        });
+        let depreciation_with_fixed_assets = ScenarioInput {
+            base: fixed_asset_base,
+            cases: vec![ScenarioCase {
+                name: "Bad".to_string(),
+                overrides: vec![override_of(ScenarioField::Depreciation, ScenarioAdjustment::Add, dec!(10), None)],
+            }],
+        };
+        assert!(run_scenarios(depreciation_with_fixed_assets).is_err());
+
+        let duplicate = ScenarioInput {
+            base: base_input(),
+            cases: vec![ScenarioCase {
+                name: "Base".to_string(),
+                overrides: vec![],
+            }],
+        };
+        assert!(run_scenarios(duplicate).is_err());
+    }
+}
@@ -1,7 +1,14 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
-use crate::error::Result;
+use crate::error::{FinanceError, Result};
+
+// Names of the standard uses, matched by the pro forma balance sheet
+pub const PURCHASE_EQUITY_VALUE: &str = "Purchase Equity Value";
+pub const REFINANCE_EXISTING_DEBT: &str = "Refinance Existing Debt";
+pub const TRANSACTION_FEES: &str = "Transaction Fees";
+pub const FINANCING_FEES: &str = "Financing Fees";
+pub const CASH_TO_BALANCE_SHEET: &str = "Cash to Balance Sheet";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceItem {
@@ -33,6 +40,26 @@ pub struct SourcesAndUsesInput {
     pub transaction_fees: Decimal,
     pub financing_fees: Decimal,
     pub other_uses: Vec<UseItem>,
+    pub minimum_cash: Option<Decimal>,  // cash left on the balance sheet at close
+
+    // Fees derived from the tranches they relate to (override the fixed amounts)
+    pub financing_fee_percent: Option<Decimal>,  // % of senior and subordinated debt
+    pub advisory_fee_percent: Option<Decimal>,  // % of enterprise value
+    pub enterprise_value: Option<Decimal>,  // defaults to purchase equity value + refinanced debt
+
+    // Plug mode: solve one source so that sources equal uses
+    pub balance_with_plug: Option<bool>,
+    pub plug_source: Option<PlugSource>,  // defaults to sponsor equity
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlugSource {
+    SponsorEquity,
+    SeniorDebt,
+    SubordinatedDebt,
+    RolloverEquity,
+    SellerNote,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +70,8 @@ pub struct SourcesAndUsesOutput {
     pub total_uses: Decimal,
     pub balanced: bool,
     pub imbalance: Decimal,
+    pub plug_source: Option<PlugSource>,
+    pub plug_amount: Option<Decimal>,
 
     // Summary metrics
     pub total_debt: Decimal,
@@ -52,7 +81,44 @@ pub struct SourcesAndUsesOutput {
 }
 
 /// Build Sources and Uses table for a transaction
-pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUsesOutput> {
+///
+/// Fees can be given as amounts or derived as percentages: financing fees on the
+/// senior and subordinated debt raised, advisory fees on enterprise value. In plug
+/// mode one source (sponsor equity by default) is solved so the table balances;
+/// when the plug is a fee-bearing debt tranche its own financing fee is included.
+pub fn build_sources_and_uses(mut input: SourcesAndUsesInput) -> Result<SourcesAndUsesOutput> {
+    let financing_fee_rate = input.financing_fee_percent.map(|pct| pct / dec!(100));
+    if let Some(rate) = financing_fee_rate {
+        if rate < Decimal::ZERO || rate >= Decimal::ONE {
+            return Err(FinanceError::OutOfRange(
+                "financing_fee_percent must be at least 0 and below 100".to_string(),
+            ));
+        }
+        input.financing_fees = (input.senior_debt + input.subordinated_debt) * rate;
+    }
+    if let Some(pct) = input.advisory_fee_percent {
+        if pct < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(
+                "advisory_fee_percent cannot be negative".to_string(),
+            ));
+        }
+        let enterprise_value = input
+            .enterprise_value
+            .unwrap_or(input.purchase_equity_value + input.refinanced_debt);
+        input.transaction_fees = enterprise_value * pct / dec!(100);
+    }
+
+    let mut plug = None;
+    if input.balance_with_plug.unwrap_or(false) {
+        let plug_source = input.plug_source.unwrap_or(PlugSource::SponsorEquity);
+        let amount = solve_plug(&input, plug_source, financing_fee_rate)?;
+        *plug_source_field(&mut input, plug_source) = amount;
+        if let Some(rate) = financing_fee_rate {
+            input.financing_fees = (input.senior_debt + input.subordinated_debt) * rate;
+        }
+        plug = Some((plug_source, amount));
+    }
+
     let mut sources = Vec::new();
     let mut uses = Vec::new();
 
@@ -109,7 +175,7 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
     // === USES ===
     if input.purchase_equity_value > Decimal::ZERO {
         uses.push(UseItem {
-            name: "Purchase Equity Value".to_string(),
+            name: PURCHASE_EQUITY_VALUE.to_string(),
             amount: input.purchase_equity_value,
             pct_of_total: Decimal::ZERO,
         });
@@ -117,7 +183,7 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
 
     if input.refinanced_debt > Decimal::ZERO {
         uses.push(UseItem {
-            name: "Refinance Existing Debt".to_string(),
+            name: REFINANCE_EXISTING_DEBT.to_string(),
             amount: input.refinanced_debt,
             pct_of_total: Decimal::ZERO,
         });
@@ -125,7 +191,7 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
 
     if input.transaction_fees > Decimal::ZERO {
         uses.push(UseItem {
-            name: "Transaction Fees".to_string(),
+            name: TRANSACTION_FEES.to_string(),
             amount: input.transaction_fees,
             pct_of_total: Decimal::ZERO,
         });
@@ -133,12 +199,22 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
 
     if input.financing_fees > Decimal::ZERO {
         uses.push(UseItem {
-            name: "Financing Fees".to_string(),
+            name: FINANCING_FEES.to_string(),
             amount: input.financing_fees,
             pct_of_total: Decimal::ZERO,
         });
     }
 
+    if let Some(minimum_cash) = input.minimum_cash {
+        if minimum_cash > Decimal::ZERO {
+            uses.push(UseItem {
+                name: CASH_TO_BALANCE_SHEET.to_string(),
+                amount: minimum_cash,
+                pct_of_total: Decimal::ZERO,
+            });
+        }
+    }
+
     // Add other uses
     for use_item in input.other_uses {
         if use_item.amount > Decimal::ZERO {
@@ -193,6 +269,8 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
         total_uses,
         balanced,
         imbalance,
+        plug_source: plug.map(|(source, _)| source),
+        plug_amount: plug.map(|(_, amount)| amount),
         total_debt,
         total_equity,
         debt_to_equity_ratio,
@@ -200,6 +278,74 @@ pub fn build_sources_and_uses(input: SourcesAndUsesInput) -> Result<SourcesAndUs
     })
 }
 
+/// Amount of the plug source that makes total sources equal total uses
+fn solve_plug(input: &SourcesAndUsesInput, plug: PlugSource, financing_fee_rate: Option<Decimal>) -> Result<Decimal> {
+    let fee_bearing = matches!(plug, PlugSource::SeniorDebt | PlugSource::SubordinatedDebt);
+    let rate = financing_fee_rate.unwrap_or(Decimal::ZERO);
+
+    let mut other_fee_debt = input.senior_debt + input.subordinated_debt;
+    let mut other_sources = input.senior_debt
+        + input.subordinated_debt
+        + input.equity_contribution
+        + input.rollover_equity
+        + input.seller_note.unwrap_or(Decimal::ZERO);
+    let plug_current = match plug {
+        PlugSource::SponsorEquity => input.equity_contribution,
+        PlugSource::SeniorDebt => input.senior_debt,
+        PlugSource::SubordinatedDebt => input.subordinated_debt,
+        PlugSource::RolloverEquity => input.rollover_equity,
+        PlugSource::SellerNote => input.seller_note.unwrap_or(Decimal::ZERO),
+    };
+    other_sources -= plug_current;
+    if fee_bearing {
+        other_fee_debt -= plug_current;
+    }
+    other_sources += input
+        .other_sources
+        .iter()
+        .filter(|source| source.amount > Decimal::ZERO)
+        .map(|source| source.amount)
+        .sum::<Decimal>();
+
+    let uses_before_financing_fees = input.purchase_equity_value
+        + input.refinanced_debt
+        + input.transaction_fees
+        + input.minimum_cash.unwrap_or(Decimal::ZERO).max(Decimal::ZERO)
+        + input
+            .other_uses
+            .iter()
+            .filter(|item| item.amount > Decimal::ZERO)
+            .map(|item| item.amount)
+            .sum::<Decimal>();
+
+    let amount = match financing_fee_rate {
+        // Fee on the plug itself: plug = uses + rate × (other debt + plug) − other sources
+        Some(_) if fee_bearing => {
+            (uses_before_financing_fees + rate * other_fee_debt - other_sources) / (Decimal::ONE - rate)
+        }
+        Some(_) => uses_before_financing_fees + rate * other_fee_debt - other_sources,
+        None => uses_before_financing_fees + input.financing_fees - other_sources,
+    };
+
+    if amount < Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(format!(
+            "Other sources exceed uses by {}; the plug would be negative",
+            -amount
+        )));
+    }
+    Ok(amount)
+}
+
+fn plug_source_field(input: &mut SourcesAndUsesInput, plug: PlugSource) -> &mut Decimal {
+    match plug {
+        PlugSource::SponsorEquity => &mut input.equity_contribution,
+        PlugSource::SeniorDebt => &mut input.senior_debt,
+        PlugSource::SubordinatedDebt => &mut input.subordinated_debt,
+        PlugSource::RolloverEquity => &mut input.rollover_equity,
+        PlugSource::SellerNote => input.seller_note.get_or_insert(Decimal::ZERO),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,6 +365,12 @@ mod tests {
             transaction_fees: dec!(50),
             financing_fees: dec!(50),
             other_uses: vec![],
+            minimum_cash: None,
+            financing_fee_percent: None,
+            advisory_fee_percent: None,
+            enterprise_value: None,
+            balance_with_plug: None,
+            plug_source: None,
         };
 
         let result = build_sources_and_uses(input).unwrap();
@@ -258,6 +410,12 @@ mod tests {
             transaction_fees: dec!(30),
             financing_fees: dec!(20),
             other_uses: vec![],
+            minimum_cash: None,
+            financing_fee_percent: None,
+            advisory_fee_percent: None,
+            enterprise_value: None,
+            balance_with_plug: None,
+            plug_source: None,
         };
 
         let result = build_sources_and_uses(input).unwrap();
@@ -283,6 +441,12 @@ mod tests {
             transaction_fees: dec!(30),
             financing_fees: dec!(20),
             other_uses: vec![],
+            minimum_cash: None,
+            financing_fee_percent: None,
+            advisory_fee_percent: None,
+            enterprise_value: None,
+            balance_with_plug: None,
+            plug_source: None,
         };
 
         let result = build_sources_and_uses(input).unwrap();
@@ -308,6 +472,12 @@ mod tests {
             transaction_fees: dec!(30),
             financing_fees: dec!(20),
             other_uses: vec![],
+            minimum_cash: None,
+            financing_fee_percent: None,
+            advisory_fee_percent: None,
+            enterprise_value: None,
+            balance_with_plug: None,
+            plug_source: None,
         };
 
         let result = build_sources_and_uses(input).unwrap();
@@ -328,4 +498,62 @@ mod tests {
             .pct_of_total;
         assert_eq!(senior_pct, dec!(60));
     }
+
+    fn leveraged_input() -> SourcesAndUsesInput {
+        SourcesAndUsesInput {
+            senior_debt: dec!(400),
+            subordinated_debt: dec!(100),
+            equity_contribution: dec!(0),
+            rollover_equity: dec!(50),
+            seller_note: None,
+            other_sources: vec![],
+            purchase_equity_value: dec!(800),
+            refinanced_debt: dec!(200),
+            transaction_fees: dec!(0),
+            financing_fees: dec!(0),
+            other_uses: vec![],
+            minimum_cash: Some(dec!(25)),
+            financing_fee_percent: Some(dec!(2)),
+            advisory_fee_percent: Some(dec!(1.5)),
+            enterprise_value: None,
+            balance_with_plug: Some(true),
+            plug_source: None,
+        }
+    }
+
+    #[test]
+    fn test_sponsor_equity_plug_with_derived_fees() {
+        let result = build_sources_and_uses(leveraged_input()).unwrap();
+
+        // Uses: 800 + 200 + 15 advisory (1.5% × 1,000 EV) + 10 financing (2% × 500) + 25 cash = 1,050
+        assert_eq!(result.total_uses, dec!(1050));
+        assert!(result.balanced);
+        assert_eq!(result.plug_source, Some(PlugSource::SponsorEquity));
+        assert_eq!(result.plug_amount, Some(dec!(500)));
+
+        let cash = result.uses.iter().find(|u| u.name == CASH_TO_BALANCE_SHEET).unwrap();
+        assert_eq!(cash.amount, dec!(25));
+    }
+
+    #[test]
+    fn test_debt_plug_includes_its_own_financing_fee() {
+        let mut input = leveraged_input();
+        input.equity_contribution = dec!(500);
+        input.senior_debt = dec!(0);
+        input.plug_source = Some(PlugSource::SeniorDebt);
+
+        let result = build_sources_and_uses(input).unwrap();
+
+        // Senior = (1,040 + 2% × 100 − 650) / 0.98 = 400
+        assert_eq!(result.plug_amount, Some(dec!(400)));
+        assert!(result.balanced);
+        assert_eq!(result.total_debt, dec!(500));
+    }
+
+    #[test]
+    fn test_negative_plug_rejected() {
+        let mut input = leveraged_input();
+        input.senior_debt = dec!(2000);
+        assert!(build_sources_and_uses(input).is_err());
+    }
 }
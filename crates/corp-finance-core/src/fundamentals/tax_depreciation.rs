@@ -0,0 +1,155 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::types::{TaxDepreciationInput, TaxDepreciationMethod};
+
+/// MACRS rates (half-year convention, IRS Pub. 946 Table A-1) as % of cost
+pub fn macrs_rates(recovery_period: u32) -> Result<Vec<Decimal>> {
+    let rates = match recovery_period {
+        3 => vec![dec!(33.33), dec!(44.45), dec!(14.81), dec!(7.41)],
+        5 => vec![dec!(20.00), dec!(32.00), dec!(19.20), dec!(11.52), dec!(11.52), dec!(5.76)],
+        7 => vec![
+            dec!(14.29), dec!(24.49), dec!(17.49), dec!(12.49),
+            dec!(8.93), dec!(8.92), dec!(8.93), dec!(4.46),
+        ],
+        10 => vec![
+            dec!(10.00), dec!(18.00), dec!(14.40), dec!(11.52), dec!(9.22), dec!(7.37),
+            dec!(6.55), dec!(6.55), dec!(6.56), dec!(6.55), dec!(3.28),
+        ],
+        15 => vec![
+            dec!(5.00), dec!(9.50), dec!(8.55), dec!(7.70), dec!(6.93), dec!(6.23),
+            dec!(5.90), dec!(5.90), dec!(5.91), dec!(5.90), dec!(5.91), dec!(5.90),
+            dec!(5.91), dec!(5.90), dec!(5.91), dec!(2.95),
+        ],
+        20 => vec![
+            dec!(3.750), dec!(7.219), dec!(6.677), dec!(6.177), dec!(5.713), dec!(5.285),
+            dec!(4.888), dec!(4.522), dec!(4.462), dec!(4.461), dec!(4.462), dec!(4.461),
+            dec!(4.462), dec!(4.461), dec!(4.462), dec!(4.461), dec!(4.462), dec!(4.461),
+            dec!(4.462), dec!(4.461), dec!(2.231),
+        ],
+        _ => {
+            return Err(FinanceError::InvalidInput(format!(
+                "MACRS recovery period must be 3, 5, 7, 10, 15 or 20 years, got {}",
+                recovery_period
+            )));
+        }
+    };
+    Ok(rates)
+}
+
+/// Build the annual tax depreciation schedule for the projection period
+///
+/// `Schedule`: tax depreciation is supplied directly.
+/// `Macrs`: bonus depreciation is taken on each year's capex when spent, the
+/// remainder is depreciated over the MACRS table, and tax depreciation on the
+/// opening asset base (`existing_assets`) is added on top.
+pub fn build_tax_depreciation(
+    config: &TaxDepreciationInput,
+    capex: &[Decimal],
+    num_years: usize,
+) -> Result<Vec<Decimal>> {
+    match config.method {
+        TaxDepreciationMethod::Schedule => {
+            let schedule = config.schedule.as_ref().ok_or_else(|| {
+                FinanceError::MissingField("tax_depreciation.schedule".to_string())
+            })?;
+            if schedule.len() < num_years {
+                return Err(FinanceError::InvalidInput(format!(
+                    "tax_depreciation.schedule must have a value for each of the {} projection years",
+                    num_years
+                )));
+            }
+            Ok(schedule[..num_years].to_vec())
+        }
+        TaxDepreciationMethod::Macrs => {
+            let recovery_period = config.recovery_period.ok_or_else(|| {
+                FinanceError::MissingField("tax_depreciation.recovery_period".to_string())
+            })?;
+            let rates = macrs_rates(recovery_period)?;
+            let bonus_percent = config.bonus_percent.unwrap_or(Decimal::ZERO);
+            if bonus_percent < Decimal::ZERO || bonus_percent > dec!(100) {
+                return Err(FinanceError::OutOfRange(
+                    "tax_depreciation.bonus_percent must be between 0 and 100".to_string(),
+                ));
+            }
+
+            let mut schedule = match &config.existing_assets {
+                Some(existing) if existing.len() < num_years => {
+                    return Err(FinanceError::InvalidInput(format!(
+                        "tax_depreciation.existing_assets must have a value for each of the {} projection years",
+                        num_years
+                    )));
+                }
+                Some(existing) => existing[..num_years].to_vec(),
+                None => vec![Decimal::ZERO; num_years],
+            };
+            for (vintage, &spend) in capex.iter().enumerate().take(num_years) {
+                let bonus = spend * bonus_percent / dec!(100);
+                schedule[vintage] += bonus;
+
+                let depreciable_basis = spend - bonus;
+                for (age, rate) in rates.iter().enumerate() {
+                    let year = vintage + age;
+                    if year >= num_years {
+                        break;
+                    }
+                    schedule[year] += depreciable_basis * rate / dec!(100);
+                }
+            }
+            Ok(schedule)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_macrs_tables_sum_to_100() {
+        for period in [3, 5, 7, 10, 15, 20] {
+            let total: Decimal = macrs_rates(period).unwrap().iter().sum();
+            assert!((total - dec!(100)).abs() < dec!(0.01), "{}-year table sums to {}", period, total);
+        }
+        assert!(macrs_rates(4).is_err());
+    }
+
+    #[test]
+    fn test_macrs_with_bonus() {
+        let config = TaxDepreciationInput {
+            method: TaxDepreciationMethod::Macrs,
+            recovery_period: Some(5),
+            bonus_percent: Some(dec!(50)),
+            existing_assets: Some(vec![dec!(10), dec!(10), dec!(10)]),
+            schedule: None,
+        };
+
+        let schedule = build_tax_depreciation(&config, &[dec!(100), dec!(0), dec!(0)], 3).unwrap();
+
+        // Year 1: 10 existing + 50 bonus + 20% × 50 = 70
+        assert_eq!(schedule[0], dec!(70));
+        // Year 2: 10 + 32% × 50 = 26
+        assert_eq!(schedule[1], dec!(26));
+        // Year 3: 10 + 19.2% × 50 = 19.6
+        assert_eq!(schedule[2], dec!(19.6));
+    }
+
+    #[test]
+    fn test_short_existing_assets_rejected() {
+        let config = TaxDepreciationInput {
+            method: TaxDepreciationMethod::Macrs,
+            recovery_period: Some(5),
+            bonus_percent: None,
+            existing_assets: Some(vec![dec!(10), dec!(10)]),
+            schedule: None,
+        };
+        assert!(build_tax_depreciation(&config, &[dec!(100), dec!(0), dec!(0)], 3).is_err());
+    }
+
+    #[test]
+    fn test_unknown_method_fails_to_deserialize() {
+        let json = r#"{"method": "macr", "recovery_period": 5}"#;
+        assert!(serde_json::from_str::<TaxDepreciationInput>(json).is_err());
+    }
+}
@@ -1,59 +1,199 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use crate::error::Result;
+use crate::error::{FinanceError, Result};
+use super::fixed_assets::build_fixed_asset_schedule;
+use super::tax_depreciation::build_tax_depreciation;
 use super::types::{
-    ThreeStatementInput, ThreeStatementOutput, IncomeStatement, BalanceSheet, CashFlow,
+    ThreeStatementInput, ThreeStatementOutput, IncomeStatement, BalanceSheet, CashFlow, ShareCount,
 };
 
 /// Build linked three-statement financial model
 /// Links income statement → balance sheet → cash flow statement
+///
+/// `cogs_percent_by_year`, `opex_percent_by_year` and `sbc_percent_by_year` replace
+/// the matching scalar margin year by year when given.
+///
+/// Optional lines (SBC, amortisation, other income, interest income, equity-method
+/// income, NCI, preferred dividends) default to zero when not supplied. Tax losses
+/// are carried forward as NOLs and shield future taxable income, subject to the
+/// optional annual and % of income utilisation limits. The NOL is tax-effected in
+/// full as a DTA (no valuation allowance): losses give a deferred tax credit and
+/// utilisation reverses it, so a loss year shows a tax benefit at the statutory rate.
+///
+/// When `starting_basic_shares` is given, the basic share count is rolled forward
+/// for buybacks (repurchased at `buyback_share_price`) and EPS/DPS are reported.
+/// Dividends and buybacks are paid from cash in CFF, reducing the equity plug.
+///
+/// With `tax_depreciation`, current tax is computed on taxable income using tax
+/// depreciation; the book/tax timing difference is booked as deferred tax, rolled
+/// forward as a DTL (or DTA) and added back in CFO so cash reflects current tax only.
+///
+/// With `fixed_assets`, depreciation, amortisation and net PP&E come from the capex
+/// vintage waterfall instead of the `depreciation` / `amortization` inputs.
+///
+/// With `purchase_accounting`, the acquisition step-ups are added to the opening
+/// balances and the incremental D&A and inventory step-up release to the book
+/// charges. Non-deductible charges are added back to taxable income and unwind the
+/// acquisition DTL through deferred tax.
 pub fn build_three_statement_model(input: ThreeStatementInput) -> Result<ThreeStatementOutput> {
     let num_years = input.revenue.len();
+
+    validate_schedule("capex", Some(&input.capex), num_years)?;
+    if input.fixed_assets.is_none() {
+        validate_schedule("depreciation", Some(&input.depreciation), num_years)?;
+        validate_schedule("amortization", input.amortization.as_ref(), num_years)?;
+    }
+    validate_schedule("cogs_percent_by_year", input.cogs_percent_by_year.as_ref(), num_years)?;
+    validate_schedule("opex_percent_by_year", input.opex_percent_by_year.as_ref(), num_years)?;
+    validate_schedule("sbc_percent_by_year", input.sbc_percent_by_year.as_ref(), num_years)?;
+    validate_schedule("other_income", input.other_income.as_ref(), num_years)?;
+    validate_schedule("equity_method_income", input.equity_method_income.as_ref(), num_years)?;
+    validate_schedule("preferred_dividends", input.preferred_dividends.as_ref(), num_years)?;
+    validate_schedule("dividends_per_share", input.dividends_per_share.as_ref(), num_years)?;
+    validate_schedule("buyback_amount", input.buyback_amount.as_ref(), num_years)?;
+    validate_schedule("buyback_share_price", input.buyback_share_price.as_ref(), num_years)?;
+    validate_capital_returns(&input)?;
+
+    // Book D&A comes from the fixed asset waterfall when given, else the input schedules
+    let fixed_asset_schedule = match &input.fixed_assets {
+        Some(config) => build_fixed_asset_schedule(
+            config,
+            &input.capex,
+            input.starting_ppe,
+            input.starting_intangibles.unwrap_or(Decimal::ZERO),
+            num_years,
+        )?,
+        None => Vec::new(),
+    };
+    let (depreciation_schedule, amortization_schedule, intangible_additions) = if fixed_asset_schedule.is_empty() {
+        (
+            input.depreciation[..num_years].to_vec(),
+            (0..num_years).map(|year| schedule_value(input.amortization.as_ref(), year)).collect(),
+            vec![Decimal::ZERO; num_years],
+        )
+    } else {
+        (
+            fixed_asset_schedule.iter().map(|y| y.depreciation).collect(),
+            fixed_asset_schedule.iter().map(|y| y.amortization).collect::<Vec<_>>(),
+            fixed_asset_schedule.iter().map(|y| y.intangible_additions).collect(),
+        )
+    };
+
+    // Tax depreciation defaults to book depreciation (no timing differences)
+    let mut tax_depreciation_schedule = match &input.tax_depreciation {
+        Some(config) => build_tax_depreciation(config, &input.capex, num_years)?,
+        None => depreciation_schedule.clone(),
+    };
+
+    // Purchase accounting charges sit on top of the organic book schedules
+    let purchase_accounting = input.purchase_accounting.as_ref();
+    let mut depreciation_schedule = depreciation_schedule;
+    let mut amortization_schedule = amortization_schedule;
+    let mut inventory_step_up_release = vec![Decimal::ZERO; num_years];
+    let mut nondeductible_charges = vec![Decimal::ZERO; num_years];
+    if let Some(ppa) = purchase_accounting {
+        for year in 0..num_years {
+            let depreciation = schedule_value(Some(&ppa.depreciation), year);
+            let amortization = schedule_value(Some(&ppa.amortization), year);
+            let inventory = schedule_value(Some(&ppa.inventory_step_up_release), year);
+            let tax_amortization = schedule_value(ppa.tax_amortization.as_ref(), year);
+            depreciation_schedule[year] += depreciation;
+            amortization_schedule[year] += amortization;
+            inventory_step_up_release[year] = inventory;
+            if ppa.tax_deductible {
+                tax_depreciation_schedule[year] += depreciation;
+            } else {
+                nondeductible_charges[year] = amortization + inventory - tax_amortization;
+            }
+        }
+    }
+
     let mut income_statements = Vec::new();
     let mut balance_sheets = Vec::new();
     let mut cash_flows = Vec::new();
+    let mut share_counts = Vec::new();
     let mut years = Vec::new();
 
+    let sbc_percent = input.sbc_percent.unwrap_or(Decimal::ZERO);
+    let interest_income_rate = input.interest_income_rate.unwrap_or(Decimal::ZERO);
+    let nci_percent = input.nci_percent.unwrap_or(Decimal::ZERO);
+
     // Previous balances (start with year 0)
     let mut prev_cash = input.starting_cash;
     let mut prev_ar = input.starting_ar;
-    let mut prev_inventory = input.starting_inventory;
+    let mut prev_inventory_step_up = purchase_accounting.map_or(Decimal::ZERO, |ppa| ppa.inventory_step_up);
+    let mut prev_inventory = input.starting_inventory + prev_inventory_step_up;
     let mut prev_ap = input.starting_ap;
-    let mut prev_ppe = input.starting_ppe;
+    let mut prev_ppe = input.starting_ppe + purchase_accounting.map_or(Decimal::ZERO, |ppa| ppa.ppe_step_up);
     let mut prev_debt = input.starting_debt;
-    let mut prev_equity = input.starting_equity;
+    let mut prev_intangibles = input.starting_intangibles.unwrap_or(Decimal::ZERO)
+        + purchase_accounting.map_or(Decimal::ZERO, |ppa| ppa.intangibles_step_up);
+    let mut prev_equity_investments = input.starting_equity_investments.unwrap_or(Decimal::ZERO);
+    let mut prev_nci = input.starting_nci.unwrap_or(Decimal::ZERO);
+    let mut nol_balance = input.starting_nol.unwrap_or(Decimal::ZERO);
+    let mut prev_basic_shares = input.starting_basic_shares;
+    let mut prev_net_dtl = input.starting_deferred_tax_liability.unwrap_or(Decimal::ZERO)
+        + purchase_accounting.map_or(Decimal::ZERO, |ppa| ppa.deferred_tax_liability)
+        - nol_balance * input.tax_rate / dec!(100);
 
     for year in 0..num_years {
         years.push(year as u32 + 1);
 
         // === INCOME STATEMENT ===
         let revenue = input.revenue[year];
-        let cogs = revenue * input.cogs_percent / dec!(100);
+        let cogs_percent = margin(input.cogs_percent_by_year.as_ref(), input.cogs_percent, year);
+        let opex_percent = margin(input.opex_percent_by_year.as_ref(), input.opex_percent, year);
+        let sbc_percent = margin(input.sbc_percent_by_year.as_ref(), sbc_percent, year);
+        let cogs = revenue * cogs_percent / dec!(100) + inventory_step_up_release[year];
         let gross_profit = revenue - cogs;
-        let opex = revenue * input.opex_percent / dec!(100);
-        let depreciation = input.depreciation[year];
-        let ebitda = gross_profit - opex;
-        let ebit = ebitda - depreciation;
+        let opex = revenue * opex_percent / dec!(100);
+        let sbc = revenue * sbc_percent / dec!(100);
+        let depreciation = depreciation_schedule[year];
+        let amortization = amortization_schedule[year];
+        let ebitda = gross_profit - opex - sbc;
+        let ebit = ebitda - depreciation - amortization;
 
-        // Interest calculated on beginning debt balance
+        // Interest calculated on beginning debt and cash balances
         let interest_expense = prev_debt * input.interest_rate / dec!(100);
-        let ebt = ebit - interest_expense;
-        let tax = ebt.max(Decimal::ZERO) * input.tax_rate / dec!(100);
-        let net_income = ebt - tax;
+        let interest_income = prev_cash.max(Decimal::ZERO) * interest_income_rate / dec!(100);
+        let other_income = schedule_value(input.other_income.as_ref(), year);
+        let ebt = ebit - interest_expense + interest_income + other_income;
 
-        income_statements.push(IncomeStatement {
-            revenue,
-            cogs,
-            gross_profit,
-            opex,
-            ebitda,
-            depreciation,
-            ebit,
-            interest_expense,
-            ebt,
-            tax,
-            net_income,
-        });
+        // Taxable income uses tax depreciation; the book/tax gap reverses via deferred tax
+        let tax_depreciation = tax_depreciation_schedule[year];
+        let pre_nol_taxable_income = ebt + depreciation - tax_depreciation + nondeductible_charges[year];
+
+        // NOLs: losses add to the carryforward, profits use it up (subject to limits)
+        let (nol_utilized, taxable_income) = if pre_nol_taxable_income > Decimal::ZERO {
+            let mut usable = nol_balance.min(pre_nol_taxable_income);
+            if let Some(limit_pct) = input.nol_income_limit_percent {
+                usable = usable.min(pre_nol_taxable_income * limit_pct / dec!(100));
+            }
+            if let Some(annual_limit) = input.nol_annual_limit {
+                usable = usable.min(annual_limit);
+            }
+            nol_balance -= usable;
+            (usable, pre_nol_taxable_income - usable)
+        } else {
+            nol_balance += -pre_nol_taxable_income;
+            (Decimal::ZERO, Decimal::ZERO)
+        };
+        let current_tax = taxable_income * input.tax_rate / dec!(100);
+
+        // Timing differences plus the DTA on the NOL: built up by losses, released as it is used
+        let nol_added = (-pre_nol_taxable_income).max(Decimal::ZERO);
+        let deferred_tax = (tax_depreciation - depreciation - nondeductible_charges[year] + nol_utilized - nol_added)
+            * input.tax_rate
+            / dec!(100);
+        let tax = current_tax + deferred_tax;
+
+        // Equity-method income is reported after tax
+        let equity_method_income = schedule_value(input.equity_method_income.as_ref(), year);
+        let net_income = ebt - tax + equity_method_income;
+        let net_income_to_nci = net_income * nci_percent / dec!(100);
+        let net_income_to_parent = net_income - net_income_to_nci;
+        let preferred_dividends = schedule_value(input.preferred_dividends.as_ref(), year);
+        let net_income_to_common = net_income_to_parent - preferred_dividends;
 
         // === BALANCE SHEET ===
         // Calculate NWC items based on revenue
@@ -61,61 +201,195 @@ pub fn build_three_statement_model(input: ThreeStatementInput) -> Result<ThreeSt
 
         // Simplified: distribute NWC across AR, Inventory, AP proportionally
         let accounts_receivable = nwc_target * dec!(0.4);
-        let inventory = nwc_target * dec!(0.3);
+        let organic_inventory = nwc_target * dec!(0.3);
         let accounts_payable = nwc_target * dec!(0.3);
 
+        // Written-up inventory sits on top of the organic balance until released through COGS
+        let inventory_step_up = (prev_inventory_step_up - inventory_step_up_release[year]).max(Decimal::ZERO);
+        let inventory = organic_inventory + inventory_step_up;
+
         // PPE: Previous PPE + Capex - Depreciation
         let capex = input.capex[year];
         let ppe_net = prev_ppe + capex - depreciation;
 
-        // Assets side
-        let total_assets_pre_cash = accounts_receivable + inventory + ppe_net;
+        // Intangibles amortise down; associates grow with equity-method income
+        let intangibles_purchased = intangible_additions[year];
+        let intangibles = prev_intangibles + intangibles_purchased - amortization;
+        let equity_investments = prev_equity_investments + equity_method_income;
 
         // === CASH FLOW STATEMENT ===
         // Operating activities
         let change_ar = accounts_receivable - prev_ar;
-        let change_inventory = inventory - prev_inventory;
+        // The step-up release is non-cash and added back below, so only organic inventory moves NWC
+        let change_inventory = organic_inventory - (prev_inventory - prev_inventory_step_up);
         let change_ap = accounts_payable - prev_ap;
         let change_in_nwc = change_ar + change_inventory - change_ap;
 
-        let cfo = net_income + depreciation - change_in_nwc;
+        // Add back non-cash charges, strip out non-cash equity-method income
+        let cfo = net_income + depreciation + amortization + sbc + deferred_tax
+            + inventory_step_up_release[year]
+            - equity_method_income
+            - change_in_nwc;
 
         // Investing activities
-        let cfi = -capex;
+        let cfi = -capex - intangibles_purchased;
 
-        // Financing activities - debt stays constant for simplicity, plug with equity
+        // Financing activities - debt stays constant for simplicity
         let debt = prev_debt;
-        let net_change_cash = cfo + cfi;
+        let preferred_dividends_paid = preferred_dividends;
+
+        // Common dividends: payout of net income to common, or DPS × beginning shares
+        let dividends_paid = if let Some(payout_ratio) = input.dividend_payout_ratio {
+            net_income_to_common.max(Decimal::ZERO) * payout_ratio / dec!(100)
+        } else if let (Some(dps), Some(shares)) = (input.dividends_per_share.as_ref(), prev_basic_shares) {
+            dps[year] * shares
+        } else {
+            Decimal::ZERO
+        };
+
+        // Buybacks: fixed programme or a share of free cash flow
+        let fcf = cfo - capex;
+        let share_repurchases = if let Some(amounts) = input.buyback_amount.as_ref() {
+            amounts[year]
+        } else if let Some(fcf_pct) = input.buyback_percent_fcf {
+            fcf.max(Decimal::ZERO) * fcf_pct / dec!(100)
+        } else {
+            Decimal::ZERO
+        };
+
+        let cff = -preferred_dividends_paid - dividends_paid - share_repurchases;
+        let net_change_cash = cfo + cfi + cff;
         let cash = prev_cash + net_change_cash;
 
-        // Equity is the plug to balance the balance sheet
-        let total_liabilities = accounts_payable + debt;
-        let equity = cash + accounts_receivable + inventory + ppe_net - total_liabilities;
+        // Net deferred tax position: liability when tax depreciation runs ahead of book
+        let net_dtl = prev_net_dtl + deferred_tax;
+        let deferred_tax_liability = net_dtl.max(Decimal::ZERO);
+        let deferred_tax_asset = (-net_dtl).max(Decimal::ZERO);
+
+        // Equity is the plug to balance the balance sheet; NCI rolls forward separately
+        let total_assets = cash + accounts_receivable + inventory + ppe_net
+            + intangibles
+            + equity_investments
+            + deferred_tax_asset;
+        let total_liabilities = accounts_payable + debt + deferred_tax_liability;
+        let noncontrolling_interest = prev_nci + net_income_to_nci;
+        let equity = total_assets - total_liabilities - noncontrolling_interest;
+
+        let total_liabilities_equity = total_liabilities + equity + noncontrolling_interest;
+
+        // === SHARE COUNT & PER-SHARE DATA ===
+        let (eps_basic, eps_diluted, dps) = match prev_basic_shares {
+            Some(beginning_basic_shares) => {
+                let shares_repurchased = match input.buyback_share_price.as_ref() {
+                    Some(prices) if share_repurchases != Decimal::ZERO => {
+                        if prices[year] <= Decimal::ZERO {
+                            return Err(FinanceError::InvalidInput(
+                                "buyback_share_price must be positive".to_string(),
+                            ));
+                        }
+                        share_repurchases / prices[year]
+                    }
+                    _ => Decimal::ZERO,
+                };
+                let ending_basic_shares = beginning_basic_shares - shares_repurchased;
+
+                // Repurchases assumed evenly through the year
+                let weighted_basic_shares = (beginning_basic_shares + ending_basic_shares) / dec!(2);
+                let dilutive_shares = input.dilutive_shares.unwrap_or(Decimal::ZERO);
+                let weighted_diluted_shares = weighted_basic_shares + dilutive_shares;
 
-        let total_assets = cash + accounts_receivable + inventory + ppe_net;
-        let total_liabilities_equity = accounts_payable + debt + equity;
+                if ending_basic_shares <= Decimal::ZERO || weighted_basic_shares <= Decimal::ZERO {
+                    return Err(FinanceError::CalculationError(format!(
+                        "Share count falls to zero or below in year {}",
+                        year + 1
+                    )));
+                }
+
+                share_counts.push(ShareCount {
+                    beginning_basic_shares,
+                    shares_repurchased,
+                    ending_basic_shares,
+                    weighted_basic_shares,
+                    dilutive_shares,
+                    weighted_diluted_shares,
+                });
+                prev_basic_shares = Some(ending_basic_shares);
+
+                (
+                    Some(net_income_to_common / weighted_basic_shares),
+                    Some(net_income_to_common / weighted_diluted_shares),
+                    Some(dividends_paid / beginning_basic_shares),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        income_statements.push(IncomeStatement {
+            revenue,
+            cogs,
+            gross_profit,
+            opex,
+            sbc,
+            ebitda,
+            depreciation,
+            amortization,
+            ebit,
+            interest_expense,
+            interest_income,
+            other_income,
+            ebt,
+            nol_utilized,
+            tax_depreciation,
+            taxable_income,
+            current_tax,
+            deferred_tax,
+            tax,
+            equity_method_income,
+            net_income,
+            net_income_to_nci,
+            net_income_to_parent,
+            preferred_dividends,
+            net_income_to_common,
+            nol_balance,
+            eps_basic,
+            eps_diluted,
+            dps,
+        });
 
         balance_sheets.push(BalanceSheet {
             cash,
             accounts_receivable,
             inventory,
             ppe_net,
+            intangibles,
+            equity_investments,
+            deferred_tax_asset,
             total_assets,
             accounts_payable,
             debt,
+            deferred_tax_liability,
             equity,
+            noncontrolling_interest,
             total_liabilities_equity,
         });
 
         cash_flows.push(CashFlow {
             net_income,
             depreciation,
+            amortization,
+            sbc,
+            equity_method_income,
+            deferred_tax,
             change_in_nwc,
             cfo,
             capex,
+            intangibles_purchased,
             cfi,
             debt_issuance: Decimal::ZERO,
-            cff: Decimal::ZERO,
+            preferred_dividends_paid,
+            dividends_paid,
+            share_repurchases,
+            cff,
             net_change_cash,
         });
 
@@ -123,44 +397,139 @@ pub fn build_three_statement_model(input: ThreeStatementInput) -> Result<ThreeSt
         prev_cash = cash;
         prev_ar = accounts_receivable;
         prev_inventory = inventory;
+        prev_inventory_step_up = inventory_step_up;
         prev_ap = accounts_payable;
         prev_ppe = ppe_net;
         prev_debt = debt;
-        prev_equity = equity;
+        prev_intangibles = intangibles;
+        prev_equity_investments = equity_investments;
+        prev_nci = noncontrolling_interest;
+        prev_net_dtl = net_dtl;
     }
 
     Ok(ThreeStatementOutput {
         income_statements,
         balance_sheets,
         cash_flows,
+        share_counts,
+        fixed_asset_schedule,
         years,
     })
 }
 
+/// Reject ambiguous or incomplete dividend / buyback assumptions
+fn validate_capital_returns(input: &ThreeStatementInput) -> Result<()> {
+    if input.dividend_payout_ratio.is_some() && input.dividends_per_share.is_some() {
+        return Err(FinanceError::InvalidInput(
+            "Specify either dividend_payout_ratio or dividends_per_share, not both".to_string(),
+        ));
+    }
+    if input.dividends_per_share.is_some() && input.starting_basic_shares.is_none() {
+        return Err(FinanceError::MissingField(
+            "starting_basic_shares (required for dividends_per_share)".to_string(),
+        ));
+    }
+    if input.buyback_amount.is_some() && input.buyback_percent_fcf.is_some() {
+        return Err(FinanceError::InvalidInput(
+            "Specify either buyback_amount or buyback_percent_fcf, not both".to_string(),
+        ));
+    }
+    let has_buybacks = input.buyback_amount.is_some() || input.buyback_percent_fcf.is_some();
+    if has_buybacks && input.starting_basic_shares.is_some() && input.buyback_share_price.is_none() {
+        return Err(FinanceError::MissingField(
+            "buyback_share_price (required to reduce the share count)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensure an optional per-year schedule covers every projection year
+fn validate_schedule(name: &str, schedule: Option<&Vec<Decimal>>, num_years: usize) -> Result<()> {
+    if let Some(values) = schedule {
+        if values.len() < num_years {
+            return Err(FinanceError::InvalidInput(format!(
+                "{} must have a value for each of the {} projection years (got {})",
+                name, num_years, values.len()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Margin for a given year from an optional per-year path, else the scalar assumption
+fn margin(by_year: Option<&Vec<Decimal>>, scalar: Decimal, year: usize) -> Decimal {
+    by_year
+        .and_then(|values| values.get(year).copied())
+        .unwrap_or(scalar)
+}
+
+/// Value for a given year from an optional schedule, zero when not supplied
+fn schedule_value(schedule: Option<&Vec<Decimal>>, year: usize) -> Decimal {
+    schedule
+        .and_then(|values| values.get(year).copied())
+        .unwrap_or(Decimal::ZERO)
+}
+
+/// Base case shared by the model and scenario tests
+#[cfg(test)]
+pub(crate) fn base_input() -> ThreeStatementInput {
+    ThreeStatementInput {
+        starting_cash: dec!(100),
+        starting_debt: dec!(500),
+        starting_equity: dec!(1000),
+        starting_inventory: dec!(200),
+        starting_ar: dec!(150),
+        starting_ap: dec!(100),
+        starting_ppe: dec!(800),
+        revenue: vec![dec!(1000), dec!(1100), dec!(1210)],
+        cogs_percent: dec!(60),
+        opex_percent: dec!(20),
+        tax_rate: dec!(25),
+        capex: vec![dec!(100), dec!(110), dec!(120)],
+        depreciation: vec![dec!(80), dec!(88), dec!(96)],
+        nwc_percent_revenue: dec!(10),
+        interest_rate: dec!(5),
+        sbc_percent: None,
+        amortization: None,
+        starting_intangibles: None,
+        other_income: None,
+        interest_income_rate: None,
+        equity_method_income: None,
+        starting_equity_investments: None,
+        nci_percent: None,
+        starting_nci: None,
+        preferred_dividends: None,
+        starting_nol: None,
+        nol_annual_limit: None,
+        nol_income_limit_percent: None,
+        starting_basic_shares: None,
+        dilutive_shares: None,
+        dividend_payout_ratio: None,
+        dividends_per_share: None,
+        buyback_amount: None,
+        buyback_percent_fcf: None,
+        buyback_share_price: None,
+        tax_depreciation: None,
+        starting_deferred_tax_liability: None,
+        fixed_assets: None,
+        purchase_accounting: None,
+        cogs_percent_by_year: None,
+        opex_percent_by_year: None,
+        sbc_percent_by_year: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rust_decimal_macros::dec;
+    use super::super::types::{
+        DepreciationMethod, FixedAssetInput, IntangibleAddition, PurchaseAccountingAdjustments, TaxDepreciationInput, TaxDepreciationMethod,
+    };
 
     #[test]
     fn test_three_statement_model() {
-        let input = ThreeStatementInput {
-            starting_cash: dec!(100),
-            starting_debt: dec!(500),
-            starting_equity: dec!(1000),
-            starting_inventory: dec!(200),
-            starting_ar: dec!(150),
-            starting_ap: dec!(100),
-            starting_ppe: dec!(800),
-            revenue: vec![dec!(1000), dec!(1100), dec!(1210)],
-            cogs_percent: dec!(60),
-            opex_percent: dec!(20),
-            tax_rate: dec!(25),
-            capex: vec![dec!(100), dec!(110), dec!(120)],
-            depreciation: vec![dec!(80), dec!(88), dec!(96)],
-            nwc_percent_revenue: dec!(10),
-            interest_rate: dec!(5),
-        };
+        let input = base_input();
 
         let result = build_three_statement_model(input).unwrap();
 
@@ -182,21 +551,10 @@ mod tests {
     #[test]
     fn test_linked_statements() {
         let input = ThreeStatementInput {
-            starting_cash: dec!(100),
-            starting_debt: dec!(500),
-            starting_equity: dec!(1000),
-            starting_inventory: dec!(200),
-            starting_ar: dec!(150),
-            starting_ap: dec!(100),
-            starting_ppe: dec!(800),
             revenue: vec![dec!(1000)],
-            cogs_percent: dec!(60),
-            opex_percent: dec!(20),
-            tax_rate: dec!(25),
             capex: vec![dec!(100)],
             depreciation: vec![dec!(80)],
-            nwc_percent_revenue: dec!(10),
-            interest_rate: dec!(5),
+            ..base_input()
         };
 
         let result = build_three_statement_model(input).unwrap();
@@ -212,4 +570,463 @@ mod tests {
         let expected_cash = dec!(100) + result.cash_flows[0].net_change_cash;
         assert_eq!(result.balance_sheets[0].cash, expected_cash);
     }
+
+    #[test]
+    fn test_full_income_statement_lines() {
+        let input = ThreeStatementInput {
+            revenue: vec![dec!(1000)],
+            capex: vec![dec!(100)],
+            depreciation: vec![dec!(80)],
+            sbc_percent: Some(dec!(2)),
+            amortization: Some(vec![dec!(10)]),
+            starting_intangibles: Some(dec!(50)),
+            other_income: Some(vec![dec!(-5)]),
+            interest_income_rate: Some(dec!(4)),
+            equity_method_income: Some(vec![dec!(12)]),
+            starting_equity_investments: Some(dec!(60)),
+            nci_percent: Some(dec!(10)),
+            preferred_dividends: Some(vec![dec!(8)]),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+        let is = &result.income_statements[0];
+
+        // EBITDA = 1000 - 600 - 200 - 20 SBC = 180; EBIT = 180 - 80 - 10 = 90
+        assert_eq!(is.sbc, dec!(20));
+        assert_eq!(is.ebitda, dec!(180));
+        assert_eq!(is.ebit, dec!(90));
+
+        // EBT = 90 - 25 interest + 4 interest income - 5 other = 64
+        assert_eq!(is.interest_income, dec!(4));
+        assert_eq!(is.ebt, dec!(64));
+        assert_eq!(is.tax, dec!(16));
+
+        // NI = 64 - 16 + 12 = 60; NCI 10% = 6; to common = 54 - 8 = 46
+        assert_eq!(is.net_income, dec!(60));
+        assert_eq!(is.net_income_to_nci, dec!(6));
+        assert_eq!(is.net_income_to_parent, dec!(54));
+        assert_eq!(is.net_income_to_common, dec!(46));
+
+        let cf = &result.cash_flows[0];
+        assert_eq!(cf.preferred_dividends_paid, dec!(8));
+        assert_eq!(cf.cff, dec!(-8));
+
+        let bs = &result.balance_sheets[0];
+        assert_eq!(bs.intangibles, dec!(40));
+        assert_eq!(bs.equity_investments, dec!(72));
+        assert_eq!(bs.noncontrolling_interest, dec!(6));
+        assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+        assert_eq!(bs.cash, dec!(100) + cf.net_change_cash);
+    }
+
+    #[test]
+    fn test_nol_carryforward_with_limits() {
+        // Year 1 loss of 100 creates an NOL; year 2 profit can use at most 80%
+        let input = ThreeStatementInput {
+            starting_debt: dec!(0),
+            revenue: vec![dec!(500), dec!(1000)],
+            capex: vec![dec!(0), dec!(0)],
+            depreciation: vec![dec!(200), dec!(100)],
+            starting_nol: Some(dec!(50)),
+            nol_income_limit_percent: Some(dec!(80)),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Year 1: EBIT = 500 × 20% - 200 = -100, no current tax, NOL = 50 + 100
+        let is = &result.income_statements[0];
+        assert_eq!(is.ebt, dec!(-100));
+        assert_eq!(is.current_tax, Decimal::ZERO);
+        assert_eq!(is.nol_balance, dec!(150));
+
+        // The loss is tax-effected as a DTA: benefit of 100 × 25%
+        assert_eq!(is.deferred_tax, dec!(-25));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+        assert_eq!(result.balance_sheets[0].deferred_tax_asset, dec!(37.5));
+
+        // Year 2: EBT = 100, NOL capped at 80, taxable = 20; the DTA unwinds by 80 × 25%
+        let is = &result.income_statements[1];
+        assert_eq!(is.nol_utilized, dec!(80));
+        assert_eq!(is.taxable_income, dec!(20));
+        assert_eq!(is.current_tax, dec!(5));
+        assert_eq!(is.deferred_tax, dec!(20));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+        assert_eq!(is.nol_balance, dec!(70));
+        assert_eq!(result.balance_sheets[1].deferred_tax_asset, dec!(17.5));
+
+        // Deferred tax is non-cash: cash only reflects current tax
+        assert_eq!(result.cash_flows[0].deferred_tax, dec!(-25));
+        for bs in &result.balance_sheets {
+            assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+        }
+    }
+
+    #[test]
+    fn test_loss_year_with_accelerated_tax_depreciation() {
+        // Book loss widened for tax by accelerated depreciation: no current tax, full benefit booked
+        let input = ThreeStatementInput {
+            starting_debt: dec!(0),
+            revenue: vec![dec!(500)],
+            capex: vec![dec!(0)],
+            depreciation: vec![dec!(150)],
+            tax_depreciation: Some(TaxDepreciationInput {
+                method: TaxDepreciationMethod::Schedule,
+                recovery_period: None,
+                bonus_percent: None,
+                existing_assets: None,
+                schedule: Some(vec![dec!(250)]),
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+        let is = &result.income_statements[0];
+
+        // EBT = 100 - 150 = -50; tax loss = -150 goes to the NOL
+        assert_eq!(is.ebt, dec!(-50));
+        assert_eq!(is.current_tax, Decimal::ZERO);
+        assert_eq!(is.nol_balance, dec!(150));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+
+        // DTL of 25 on the depreciation gap, DTA of 37.5 on the NOL
+        let bs = &result.balance_sheets[0];
+        assert_eq!(bs.deferred_tax_asset, dec!(12.5));
+        assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+    }
+
+    #[test]
+    fn test_nol_annual_limit() {
+        let input = ThreeStatementInput {
+            starting_debt: dec!(0),
+            revenue: vec![dec!(1000)],
+            capex: vec![dec!(0)],
+            depreciation: vec![dec!(100)],
+            starting_nol: Some(dec!(500)),
+            nol_annual_limit: Some(dec!(30)),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        assert_eq!(result.income_statements[0].nol_utilized, dec!(30));
+        assert_eq!(result.income_statements[0].taxable_income, dec!(70));
+        assert_eq!(result.income_statements[0].nol_balance, dec!(470));
+    }
+
+    #[test]
+    fn test_short_schedule_rejected() {
+        let input = ThreeStatementInput {
+            amortization: Some(vec![dec!(10)]),
+            ..base_input()
+        };
+
+        assert!(build_three_statement_model(input).is_err());
+    }
+
+    #[test]
+    fn test_dividends_and_buybacks() {
+        let input = ThreeStatementInput {
+            revenue: vec![dec!(1000), dec!(1100)],
+            capex: vec![dec!(100), dec!(110)],
+            depreciation: vec![dec!(80), dec!(88)],
+            starting_basic_shares: Some(dec!(100)),
+            dilutive_shares: Some(dec!(5)),
+            dividends_per_share: Some(vec![dec!(0.5), dec!(0.6)]),
+            buyback_amount: Some(vec![dec!(40), dec!(40)]),
+            buyback_share_price: Some(vec![dec!(20), dec!(25)]),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Year 1: DPS 0.5 × 100 beginning shares = 50; 40 / 20 = 2 shares repurchased
+        let cf = &result.cash_flows[0];
+        assert_eq!(cf.dividends_paid, dec!(50));
+        assert_eq!(cf.share_repurchases, dec!(40));
+        assert_eq!(cf.cff, dec!(-90));
+
+        let shares = &result.share_counts[0];
+        assert_eq!(shares.shares_repurchased, dec!(2));
+        assert_eq!(shares.ending_basic_shares, dec!(98));
+        assert_eq!(shares.weighted_basic_shares, dec!(99));
+        assert_eq!(shares.weighted_diluted_shares, dec!(104));
+
+        let is = &result.income_statements[0];
+        assert_eq!(is.dps, Some(dec!(0.5)));
+        assert_eq!(is.eps_basic, Some(is.net_income_to_common / dec!(99)));
+        assert_eq!(is.eps_diluted, Some(is.net_income_to_common / dec!(104)));
+
+        // Year 2 starts from the reduced share count
+        assert_eq!(result.share_counts[1].beginning_basic_shares, dec!(98));
+        assert_eq!(result.cash_flows[1].dividends_paid, dec!(58.8));
+
+        let bs = &result.balance_sheets[1];
+        assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+    }
+
+    #[test]
+    fn test_distributions_reduce_equity() {
+        let without = build_three_statement_model(base_input()).unwrap();
+        let with = build_three_statement_model(ThreeStatementInput {
+            dividend_payout_ratio: Some(dec!(50)),
+            buyback_percent_fcf: Some(dec!(25)),
+            ..base_input()
+        })
+        .unwrap();
+
+        let cf = &with.cash_flows[0];
+        assert_eq!(cf.dividends_paid, with.income_statements[0].net_income_to_common / dec!(2));
+        assert_eq!(cf.share_repurchases, (cf.cfo - cf.capex) / dec!(4));
+
+        // Equity falls by exactly the cash distributed in year 1
+        let distributed = cf.dividends_paid + cf.share_repurchases;
+        assert_eq!(without.balance_sheets[0].equity - with.balance_sheets[0].equity, distributed);
+
+        // No share count supplied, so no per-share data
+        assert!(with.share_counts.is_empty());
+        assert_eq!(with.income_statements[0].eps_basic, None);
+    }
+
+    #[test]
+    fn test_conflicting_dividend_policies_rejected() {
+        let input = ThreeStatementInput {
+            starting_basic_shares: Some(dec!(100)),
+            dividend_payout_ratio: Some(dec!(30)),
+            dividends_per_share: Some(vec![dec!(1), dec!(1), dec!(1)]),
+            ..base_input()
+        };
+        assert!(build_three_statement_model(input).is_err());
+
+        let missing_price = ThreeStatementInput {
+            starting_basic_shares: Some(dec!(100)),
+            buyback_percent_fcf: Some(dec!(50)),
+            ..base_input()
+        };
+        assert!(build_three_statement_model(missing_price).is_err());
+    }
+
+    #[test]
+    fn test_buybacks_beyond_share_count_rejected() {
+        // 300 / 20 = 15 shares repurchased from 10; the weighted average alone stays positive
+        let input = ThreeStatementInput {
+            revenue: vec![dec!(1000)],
+            capex: vec![dec!(100)],
+            depreciation: vec![dec!(80)],
+            starting_basic_shares: Some(dec!(10)),
+            buyback_amount: Some(vec![dec!(300)]),
+            buyback_share_price: Some(vec![dec!(20)]),
+            ..base_input()
+        };
+
+        assert!(build_three_statement_model(input).is_err());
+    }
+
+    #[test]
+    fn test_deferred_tax_from_accelerated_depreciation() {
+        let input = ThreeStatementInput {
+            revenue: vec![dec!(1000), dec!(1000)],
+            capex: vec![dec!(100), dec!(0)],
+            depreciation: vec![dec!(80), dec!(80)],
+            tax_depreciation: Some(TaxDepreciationInput {
+                method: TaxDepreciationMethod::Schedule,
+                recovery_period: None,
+                bonus_percent: None,
+                existing_assets: None,
+                schedule: Some(vec![dec!(160), dec!(40)]),
+            }),
+            starting_deferred_tax_liability: Some(dec!(20)),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Year 1: EBT = 200 - 80 - 25 = 95; taxable = 95 + 80 - 160 = 15
+        let is = &result.income_statements[0];
+        assert_eq!(is.taxable_income, dec!(15));
+        assert_eq!(is.current_tax, dec!(3.75));
+        assert_eq!(is.deferred_tax, dec!(20));
+        assert_eq!(is.tax, dec!(23.75));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+
+        // Deferred tax is non-cash and builds the DTL
+        assert_eq!(result.cash_flows[0].deferred_tax, dec!(20));
+        assert_eq!(result.balance_sheets[0].deferred_tax_liability, dec!(40));
+
+        // Year 2: tax depreciation below book, DTL unwinds by (80 - 40) × 25% = 10
+        assert_eq!(result.income_statements[1].deferred_tax, dec!(-10));
+        assert_eq!(result.balance_sheets[1].deferred_tax_liability, dec!(30));
+
+        for bs in &result.balance_sheets {
+            assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+        }
+    }
+
+    #[test]
+    fn test_deferred_tax_asset_when_book_runs_ahead() {
+        let input = ThreeStatementInput {
+            revenue: vec![dec!(1000)],
+            capex: vec![dec!(100)],
+            depreciation: vec![dec!(80)],
+            tax_depreciation: Some(TaxDepreciationInput {
+                method: TaxDepreciationMethod::Schedule,
+                recovery_period: None,
+                bonus_percent: None,
+                existing_assets: None,
+                schedule: Some(vec![dec!(40)]),
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+        let bs = &result.balance_sheets[0];
+        assert_eq!(bs.deferred_tax_asset, dec!(10));
+        assert_eq!(bs.deferred_tax_liability, Decimal::ZERO);
+        assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+    }
+
+    #[test]
+    fn test_fixed_asset_schedule_drives_model() {
+        let input = ThreeStatementInput {
+            depreciation: vec![],
+            starting_intangibles: Some(dec!(100)),
+            fixed_assets: Some(FixedAssetInput {
+                method: DepreciationMethod::StraightLine,
+                useful_life: 10,
+                declining_balance_rate: None,
+                existing_ppe_remaining_life: Some(8),
+                intangibles_remaining_life: Some(5),
+                intangible_additions: Some(vec![IntangibleAddition {
+                    year: 2,
+                    amount: dec!(40),
+                    useful_life: 4,
+                }]),
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Year 1: 800 / 8 + 100 / 10 = 110 depreciation; 100 / 5 = 20 amortisation
+        assert_eq!(result.income_statements[0].depreciation, dec!(110));
+        assert_eq!(result.income_statements[0].amortization, dec!(20));
+        assert_eq!(result.balance_sheets[0].ppe_net, dec!(790));
+        assert_eq!(result.balance_sheets[0].ppe_net, result.fixed_asset_schedule[0].ending_ppe);
+
+        // Year 2: intangible purchase is an investing outflow and amortises from that year
+        assert_eq!(result.cash_flows[1].intangibles_purchased, dec!(40));
+        assert_eq!(result.cash_flows[1].cfi, dec!(-150));
+        assert_eq!(result.income_statements[1].amortization, dec!(30));
+        assert_eq!(result.balance_sheets[1].intangibles, dec!(90));
+
+        for bs in &result.balance_sheets {
+            assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+        }
+    }
+
+    #[test]
+    fn test_purchase_accounting_charges_unwind_dtl() {
+        let input = ThreeStatementInput {
+            purchase_accounting: Some(PurchaseAccountingAdjustments {
+                ppe_step_up: dec!(100),
+                intangibles_step_up: dec!(600),
+                inventory_step_up: dec!(20),
+                deferred_tax_liability: dec!(117.5),
+                depreciation: vec![dec!(10), dec!(10), dec!(10)],
+                amortization: vec![dec!(40), dec!(40), dec!(40)],
+                inventory_step_up_release: vec![dec!(20)],
+                tax_amortization: None,
+                tax_deductible: false,
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Year 1: COGS 600 + 20 release; D&A 80 + 10 and 40; EBT = 180 - 90 - 40 - 25 = 25
+        let is = &result.income_statements[0];
+        assert_eq!(is.cogs, dec!(620));
+        assert_eq!(is.depreciation, dec!(90));
+        assert_eq!(is.ebt, dec!(25));
+
+        // Non-deductible charges of 70 are added back for current tax and unwind the DTL
+        assert_eq!(is.taxable_income, dec!(95));
+        assert_eq!(is.current_tax, dec!(23.75));
+        assert_eq!(is.deferred_tax, dec!(-17.5));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+
+        let bs = &result.balance_sheets[0];
+        assert_eq!(bs.deferred_tax_liability, dec!(100));
+        assert_eq!(bs.ppe_net, dec!(910));
+        assert_eq!(bs.intangibles, dec!(560));
+        assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+    }
+
+    #[test]
+    fn test_inventory_step_up_runs_off_and_equity_rolls_forward() {
+        let input = ThreeStatementInput {
+            sbc_percent: Some(dec!(2)),
+            dividend_payout_ratio: Some(dec!(30)),
+            purchase_accounting: Some(PurchaseAccountingAdjustments {
+                ppe_step_up: dec!(100),
+                intangibles_step_up: dec!(600),
+                inventory_step_up: dec!(30),
+                deferred_tax_liability: dec!(117.5),
+                depreciation: vec![dec!(10), dec!(10), dec!(10)],
+                amortization: vec![dec!(40), dec!(40), dec!(40)],
+                inventory_step_up_release: vec![dec!(20), dec!(10)],
+                tax_amortization: None,
+                tax_deductible: false,
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+
+        // Organic inventory is 30% of NWC; the step-up sits on top until released
+        assert_eq!(result.balance_sheets[0].inventory, dec!(40));
+        assert_eq!(result.balance_sheets[1].inventory, dec!(33));
+        assert_eq!(result.balance_sheets[2].inventory, dec!(36.3));
+
+        // Opening equity: 100 + 150 + 230 + 900 + 600 - 100 - 500 - 117.5
+        let mut prev_equity = dec!(1262.5);
+        for ((is, cf), bs) in result.income_statements.iter()
+            .zip(&result.cash_flows)
+            .zip(&result.balance_sheets)
+        {
+            assert_eq!(
+                bs.equity,
+                prev_equity + is.net_income_to_common + is.sbc - cf.dividends_paid - cf.share_repurchases
+            );
+            assert_eq!(bs.total_assets, bs.total_liabilities_equity);
+            prev_equity = bs.equity;
+        }
+    }
+
+    #[test]
+    fn test_carried_over_tax_basis_stays_deductible() {
+        let input = ThreeStatementInput {
+            purchase_accounting: Some(PurchaseAccountingAdjustments {
+                ppe_step_up: dec!(0),
+                intangibles_step_up: dec!(400),
+                inventory_step_up: dec!(0),
+                deferred_tax_liability: dec!(90),
+                depreciation: vec![],
+                amortization: vec![dec!(40), dec!(40), dec!(40)],
+                inventory_step_up_release: vec![],
+                tax_amortization: Some(vec![dec!(4), dec!(4), dec!(4)]),
+                tax_deductible: false,
+            }),
+            ..base_input()
+        };
+
+        let result = build_three_statement_model(input).unwrap();
+        let is = &result.income_statements[0];
+
+        // Only the 36 of amortisation above the carried-over deduction unwinds the DTL
+        assert_eq!(is.deferred_tax, dec!(-9));
+        assert_eq!(is.tax, is.ebt * dec!(0.25));
+        assert_eq!(result.balance_sheets[0].deferred_tax_liability, dec!(81));
+    }
 }
@@ -1,5 +1,7 @@
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::types::ExerciseStyle;
 
 /// Three Statement Model Input
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,9 +21,115 @@ pub struct ThreeStatementInput {
     pub opex_percent: Decimal,          // as % of revenue
     pub tax_rate: Decimal,              // as %
     pub capex: Vec<Decimal>,
-    pub depreciation: Vec<Decimal>,
+    #[serde(default)]
+    pub depreciation: Vec<Decimal>,     // may be omitted when fixed_assets is given
     pub nwc_percent_revenue: Decimal,   // NWC as % of revenue
     pub interest_rate: Decimal,         // as %
+
+    // Per-year margin paths (as % of revenue); replace the scalar margin when given
+    pub cogs_percent_by_year: Option<Vec<Decimal>>,
+    pub opex_percent_by_year: Option<Vec<Decimal>>,
+    pub sbc_percent_by_year: Option<Vec<Decimal>>,
+
+    // Additional income statement lines (all optional, default to zero)
+    pub sbc_percent: Option<Decimal>,               // stock-based comp as % of revenue
+    pub amortization: Option<Vec<Decimal>>,         // amortisation of acquired intangibles
+    pub starting_intangibles: Option<Decimal>,
+    pub other_income: Option<Vec<Decimal>>,         // other income / (expense), pre-tax
+    pub interest_income_rate: Option<Decimal>,      // as %, earned on beginning cash
+    pub equity_method_income: Option<Vec<Decimal>>, // share of associates' profit, after tax
+    pub starting_equity_investments: Option<Decimal>,
+    pub nci_percent: Option<Decimal>,               // % of net income attributable to NCI
+    pub starting_nci: Option<Decimal>,
+    pub preferred_dividends: Option<Vec<Decimal>>,
+
+    // Net operating loss carryforwards
+    pub starting_nol: Option<Decimal>,              // its DTA is recognised on top of starting_deferred_tax_liability
+    pub nol_annual_limit: Option<Decimal>,          // absolute cap on NOL used per year
+    pub nol_income_limit_percent: Option<Decimal>,  // cap as % of pre-NOL taxable income
+
+    // Share count and capital returns
+    pub starting_basic_shares: Option<Decimal>,
+    pub dilutive_shares: Option<Decimal>,           // incremental diluted shares (e.g. from TSM)
+    pub dividend_payout_ratio: Option<Decimal>,     // % of net income to common
+    pub dividends_per_share: Option<Vec<Decimal>>,  // alternative to payout ratio
+    pub buyback_amount: Option<Vec<Decimal>>,       // fixed repurchase programme
+    pub buyback_percent_fcf: Option<Decimal>,       // alternative: % of FCF (CFO - capex)
+    pub buyback_share_price: Option<Vec<Decimal>>,  // assumed average repurchase price
+
+    // Book vs tax depreciation (book depreciation stays as `depreciation`)
+    pub tax_depreciation: Option<TaxDepreciationInput>,
+    pub starting_deferred_tax_liability: Option<Decimal>,  // net DTL, negative = net DTA
+
+    // PP&E / intangibles waterfall; replaces `depreciation` and `amortization` when given
+    pub fixed_assets: Option<FixedAssetInput>,
+
+    // Acquirer's purchase accounting (see `purchase_accounting::allocate_purchase_price`)
+    pub purchase_accounting: Option<PurchaseAccountingAdjustments>,
+}
+
+/// Tax depreciation assumptions for deferred tax modelling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxDepreciationInput {
+    pub method: TaxDepreciationMethod,
+    pub recovery_period: Option<u32>,          // MACRS class: 3, 5, 7, 10, 15 or 20 years
+    pub bonus_percent: Option<Decimal>,        // bonus depreciation on new capex, as %
+    pub existing_assets: Option<Vec<Decimal>>, // tax depreciation on the opening asset base
+    pub schedule: Option<Vec<Decimal>>,        // explicit tax depreciation for `schedule`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxDepreciationMethod {
+    Macrs,     // MACRS tables with optional bonus depreciation
+    Schedule,  // tax depreciation supplied directly
+}
+
+/// Purchase accounting layered on top of the acquirer's book balances
+///
+/// Step-ups are added to the opening balances and their incremental D&A to the
+/// book schedules. When the step-ups are not tax deductible (a stock deal), the
+/// extra charges above `tax_amortization` reduce book tax only and unwind the
+/// acquisition DTL. The organic `amortization` input should then leave out the
+/// target's written-off intangibles, whose deduction `tax_amortization` carries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseAccountingAdjustments {
+    pub ppe_step_up: Decimal,
+    pub intangibles_step_up: Decimal,         // identified intangibles at fair value plus goodwill
+    #[serde(default)]
+    pub inventory_step_up: Decimal,           // added to opening inventory, run off by the release
+    pub deferred_tax_liability: Decimal,
+    pub depreciation: Vec<Decimal>,           // incremental depreciation on the PP&E step-up
+    pub amortization: Vec<Decimal>,           // amortisation of identified intangibles
+    pub inventory_step_up_release: Vec<Decimal>,  // written-up inventory expensed through COGS
+    pub tax_amortization: Option<Vec<Decimal>>,  // target's carried-over intangible tax basis, still deductible
+    pub tax_deductible: bool,
+}
+
+/// Fixed asset schedule assumptions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedAssetInput {
+    pub method: DepreciationMethod,
+    pub useful_life: u32,                           // years, for each new capex vintage
+    pub declining_balance_rate: Option<Decimal>,    // as %, default 200 (double-declining)
+    pub existing_ppe_remaining_life: Option<u32>,   // opening PP&E, straight-line; required if starting PP&E > 0
+    pub intangibles_remaining_life: Option<u32>,    // opening intangibles, straight-line; none = indefinite-lived
+    pub intangible_additions: Option<Vec<IntangibleAddition>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepreciationMethod {
+    StraightLine,
+    DecliningBalance,  // at `declining_balance_rate`, switching to straight-line
+}
+
+/// Intangibles purchased for cash during the projection period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntangibleAddition {
+    pub year: u32,          // 1-based projection year
+    pub amount: Decimal,
+    pub useful_life: u32,   // years, straight-line
 }
 
 /// Three Statement Model Output
@@ -30,6 +138,8 @@ pub struct ThreeStatementOutput {
     pub income_statements: Vec<IncomeStatement>,
     pub balance_sheets: Vec<BalanceSheet>,
     pub cash_flows: Vec<CashFlow>,
+    pub share_counts: Vec<ShareCount>,  // empty unless starting_basic_shares is given
+    pub fixed_asset_schedule: Vec<FixedAssetYear>,  // empty unless fixed_assets is given
     pub years: Vec<u32>,
 }
 
@@ -39,13 +149,31 @@ pub struct IncomeStatement {
     pub cogs: Decimal,
     pub gross_profit: Decimal,
     pub opex: Decimal,
+    pub sbc: Decimal,
     pub ebitda: Decimal,
     pub depreciation: Decimal,
+    pub amortization: Decimal,
     pub ebit: Decimal,
     pub interest_expense: Decimal,
+    pub interest_income: Decimal,
+    pub other_income: Decimal,
     pub ebt: Decimal,
-    pub tax: Decimal,
-    pub net_income: Decimal,
+    pub nol_utilized: Decimal,
+    pub tax_depreciation: Decimal,
+    pub taxable_income: Decimal,
+    pub current_tax: Decimal,
+    pub deferred_tax: Decimal,
+    pub tax: Decimal,                   // total tax expense (current + deferred)
+    pub equity_method_income: Decimal,
+    pub net_income: Decimal,            // consolidated, incl. NCI share
+    pub net_income_to_nci: Decimal,
+    pub net_income_to_parent: Decimal,
+    pub preferred_dividends: Decimal,
+    pub net_income_to_common: Decimal,
+    pub nol_balance: Decimal,           // NOL carryforward remaining at year end
+    pub eps_basic: Option<Decimal>,
+    pub eps_diluted: Option<Decimal>,
+    pub dps: Option<Decimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,10 +182,15 @@ pub struct BalanceSheet {
     pub accounts_receivable: Decimal,
     pub inventory: Decimal,
     pub ppe_net: Decimal,
+    pub intangibles: Decimal,
+    pub equity_investments: Decimal,
+    pub deferred_tax_asset: Decimal,
     pub total_assets: Decimal,
     pub accounts_payable: Decimal,
     pub debt: Decimal,
-    pub equity: Decimal,
+    pub deferred_tax_liability: Decimal,
+    pub equity: Decimal,                // attributable to parent
+    pub noncontrolling_interest: Decimal,
     pub total_liabilities_equity: Decimal,
 }
 
@@ -65,25 +198,145 @@ pub struct BalanceSheet {
 pub struct CashFlow {
     pub net_income: Decimal,
     pub depreciation: Decimal,
+    pub amortization: Decimal,
+    pub sbc: Decimal,
+    pub equity_method_income: Decimal,  // non-cash, deducted
+    pub deferred_tax: Decimal,
     pub change_in_nwc: Decimal,
     pub cfo: Decimal,
     pub capex: Decimal,
+    pub intangibles_purchased: Decimal,
     pub cfi: Decimal,
     pub debt_issuance: Decimal,
+    pub preferred_dividends_paid: Decimal,
+    pub dividends_paid: Decimal,
+    pub share_repurchases: Decimal,
     pub cff: Decimal,
     pub net_change_cash: Decimal,
 }
 
+/// Basic/diluted share count roll-forward for one projection year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareCount {
+    pub beginning_basic_shares: Decimal,
+    pub shares_repurchased: Decimal,
+    pub ending_basic_shares: Decimal,
+    pub weighted_basic_shares: Decimal,
+    pub dilutive_shares: Decimal,
+    pub weighted_diluted_shares: Decimal,
+}
+
+/// PP&E and intangibles waterfall for one projection year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedAssetYear {
+    pub year: u32,
+    pub beginning_ppe: Decimal,
+    pub capex: Decimal,
+    pub existing_ppe_depreciation: Decimal,
+    pub new_capex_depreciation: Decimal,
+    pub depreciation: Decimal,
+    pub ending_ppe: Decimal,
+    pub beginning_intangibles: Decimal,
+    pub intangible_additions: Decimal,
+    pub amortization: Decimal,
+    pub ending_intangibles: Decimal,
+}
+
 /// Equity Enterprise Bridge
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EquityEnterpriseInput {
-    pub direction: String,  // "equity_to_ev" or "ev_to_equity"
+    pub direction: BridgeDirection,
     pub value: Decimal,
     pub cash: Decimal,
     pub debt: Decimal,
     pub minority_interest: Decimal,
     pub associates: Decimal,
     pub preferred_stock: Decimal,
+    pub adjustments: Option<Vec<BridgeAdjustment>>,
+    pub verify_roundtrip: Option<bool>,  // run the bridge back the other way and check it ties
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeDirection {
+    EquityToEv,
+    EvToEquity,
+}
+
+impl BridgeDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            BridgeDirection::EquityToEv => BridgeDirection::EvToEquity,
+            BridgeDirection::EvToEquity => BridgeDirection::EquityToEv,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeAdjustmentType {
+    OperatingLease,
+    PensionDeficit,
+    ConvertibleDebt,
+    ContingentConsideration,
+    OtherDebtLike,
+    TrappedCash,
+    NonOperatingAssets,
+    OtherCashLike,
+    TaxAssets,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValuationBasis {
+    Book,
+    Market,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaseStandard {
+    Ifrs16,
+    Asc842,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConvertibleTreatment {
+    Debt,
+    Equity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentTreatment {
+    DebtLike,
+    CashLike,
+    Excluded,
+}
+
+/// Additional typed EV bridge item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeAdjustment {
+    pub name: String,
+    pub adjustment_type: BridgeAdjustmentType,
+    pub book_value: Decimal,
+    pub market_value: Option<Decimal>,
+    pub valuation_basis: Option<ValuationBasis>,    // default book
+    pub tax_rate: Option<Decimal>,        // as %, for pension, trapped cash and tax assets
+    pub lease_standard: Option<LeaseStandard>,      // default IFRS 16
+    pub convertible_treatment: Option<ConvertibleTreatment>,  // default debt
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeAdjustmentResult {
+    pub name: String,
+    pub adjustment_type: BridgeAdjustmentType,
+    pub valuation_basis: ValuationBasis,
+    pub gross_value: Decimal,
+    pub bridge_amount: Decimal,   // after tax effects, always positive
+    pub treatment: AdjustmentTreatment,
+    pub note: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,36 +344,119 @@ pub struct EquityEnterpriseOutput {
     pub equity_value: Decimal,
     pub enterprise_value: Decimal,
     pub net_debt: Decimal,
+    pub total_debt_like_items: Decimal,
+    pub total_cash_like_items: Decimal,
+    pub adjustments: Vec<BridgeAdjustmentResult>,
     pub bridge_items: Vec<BridgeItem>,
+    pub roundtrip: Option<RoundTripCheck>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeItem {
     pub item: String,
     pub amount: Decimal,
-    pub direction: String,  // "add" or "subtract"
+    pub direction: BridgeItemDirection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeItemDirection {
+    Start,
+    Add,
+    Subtract,
+    End,
+}
+
+/// Result of running the bridge back in the reverse direction; only returned when it
+/// ties, since a mismatch is an error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundTripCheck {
+    pub reverse_direction: BridgeDirection,
+    pub reverse_result: Decimal,   // equals the original input value within 0.01
+    pub difference: Decimal,
 }
 
 /// Diluted Shares Calculation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DilutedSharesInput {
     pub basic_shares: Decimal,
+    #[serde(default)]
     pub stock_price: Decimal,
     pub options: Vec<OptionGrant>,
     pub rsus: Decimal,
     pub convertibles: Vec<Convertible>,
+    pub equity_purchase_price: Option<Decimal>,  // total offer value; solves for the per-share price instead of using stock_price
+    pub warrants: Option<Vec<Warrant>>,
+    pub psus: Option<Vec<PerformanceShareUnit>>,
+    pub exercisable_only: Option<bool>,  // count vested (or accelerated) options only
+    pub change_of_control: Option<bool>,  // triggers acceleration on grants that carry it
+    pub option_valuation: Option<OptionValuationAssumptions>,  // also report fair-value option dilution
+}
+
+/// Assumptions for valuing options at fair value instead of the treasury stock method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionValuationAssumptions {
+    pub risk_free_rate: Decimal,  // %
+    pub volatility: Decimal,  // %
+    pub dividend_yield: Option<Decimal>,  // %
+    pub time_to_expiry: Decimal,  // years, used where a grant has no term of its own
+    pub exercise_style: Option<ExerciseStyle>,  // American uses a binomial tree
+    pub binomial_steps: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptionGrant {
     pub quantity: Decimal,
     pub strike_price: Decimal,
+    pub vested_quantity: Option<Decimal>,  // defaults to fully vested
+    pub accelerates_on_change_of_control: Option<bool>,
+    pub time_to_expiry: Option<Decimal>,  // remaining term in years, for fair-value dilution
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warrant {
+    pub quantity: Decimal,
+    pub exercise_price: Decimal,  // paid per warrant, not per share delivered
+    pub shares_per_warrant: Option<Decimal>,  // defaults to 1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceShareUnit {
+    pub target_units: Decimal,
+    pub achievement_percent: Decimal,  // % of target earned, e.g. 150 for maximum payout
+    pub probability_percent: Option<Decimal>,  // likelihood of vesting, defaults to 100
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Convertible {
     pub principal: Decimal,
     pub conversion_price: Decimal,
+    pub settlement: Option<ConvertibleSettlement>,  // defaults to if-converted
+    pub make_whole: Option<MakeWholeTable>,
+    pub make_whole_date: Option<NaiveDate>,  // effective date of the make-whole event
+    pub call_spread: Option<CallSpread>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConvertibleSettlement {
+    IfConverted,  // all principal converts into shares
+    NetShare,     // principal settled in cash, conversion value above principal in shares
+}
+
+/// Additional shares per 1,000 of principal on a make-whole fundamental change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MakeWholeTable {
+    pub stock_prices: Vec<Decimal>,  // ascending
+    pub dates: Vec<NaiveDate>,  // ascending
+    pub additional_shares: Vec<Vec<Decimal>>,  // one row per date, one column per stock price
+}
+
+/// Purchased call at the lower strike and sold call at the cap (capped call)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSpread {
+    pub lower_strike: Option<Decimal>,  // defaults to the conversion price
+    pub cap_price: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,9 +465,43 @@ pub struct DilutedSharesOutput {
     pub options_dilution: Decimal,
     pub rsu_dilution: Decimal,
     pub convertibles_dilution: Decimal,
+    pub call_spread_offset: Decimal,
+    pub warrants_dilution: Decimal,
+    pub psu_dilution: Decimal,
+    pub unvested_options_excluded: Decimal,
     pub fully_diluted_shares: Decimal,
     pub dilution_percentage: Decimal,
     pub breakdown: Vec<DilutionItem>,
+    pub implied_share_price: Option<Decimal>,
+    pub convergence_path: Option<Vec<DilutionIteration>>,
+    pub option_value_dilution: Option<OptionValueDilution>,
+}
+
+/// Unvested options valued at fair value and converted to equivalent shares at the share price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionValueDilution {
+    pub model: String,
+    pub grants: Vec<OptionGrantValue>,
+    pub total_option_value: Decimal,
+    pub equivalent_shares: Decimal,
+    pub tsm_dilution_replaced: Decimal,  // TSM dilution of the unvested options now valued
+    pub fully_diluted_shares: Decimal,  // TSM result with that dilution replaced by equivalent shares
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionGrantValue {
+    pub quantity: Decimal,
+    pub strike_price: Decimal,
+    pub time_to_expiry: Decimal,
+    pub value_per_option: Decimal,
+    pub total_value: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DilutionIteration {
+    pub iteration: u32,
+    pub share_price: Decimal,
+    pub fully_diluted_shares: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -146,16 +516,28 @@ pub struct DilutionItem {
 pub struct AccountingFlowInput {
     pub transaction: String,
     pub amount: Decimal,
-    pub transaction_type: String,  // "depreciation", "amortization", "capex", "debt_issuance", etc.
+    pub transaction_type: String,  // "depreciation", "amortization", "capex", "debt_issuance", "custom", etc.
+    pub carrying_value: Option<Decimal>,  // asset_sale: book value of the asset sold
+    pub interest_portion: Option<Decimal>,  // lease_payment: part of the payment that is interest
+    pub journal_entries: Option<Vec<JournalLine>>,  // custom: debits and credits to post
+    pub tax_rate: Option<Decimal>,  // %, defaults to 0 (pre-tax view)
+    pub taxable_amount: Option<Decimal>,  // effect on taxable income this period, if different from book
+    pub permanent_difference: Option<bool>,  // book/tax difference never reverses: no deferred tax
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountingFlowOutput {
     pub transaction: String,
     pub amount: Decimal,
+    pub journal_entries: Vec<JournalLine>,
     pub income_statement_impact: Vec<AccountingImpact>,
     pub balance_sheet_impact: Vec<AccountingImpact>,
     pub cash_flow_impact: Vec<AccountingImpact>,
+    pub pre_tax_income_impact: Decimal,
+    pub current_tax: Decimal,  // positive = tax paid, negative = tax saved
+    pub deferred_tax: Decimal,  // positive = deferred tax expense, negative = deferred tax benefit
+    pub net_income_impact: Decimal,
+    pub cash_impact: Decimal,
     pub explanation: String,
 }
 
@@ -165,3 +547,135 @@ pub struct AccountingImpact {
     pub impact: Decimal,
     pub sign: String,  // "positive", "negative", "neutral"
 }
+
+/// A sequence of transactions applied to a starting balance sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingScenarioInput {
+    pub starting_balance_sheet: BalanceSheet,
+    pub transactions: Vec<AccountingFlowInput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingScenarioOutput {
+    pub steps: Vec<AccountingStep>,
+    pub balance_sheet: Vec<BalanceSheetLine>,  // starting vs ending
+    pub income_statement: Vec<AccountingImpact>,  // cumulative over all transactions
+    pub cash_flow: Vec<AccountingImpact>,
+    pub net_income: Decimal,
+    pub net_change_in_cash: Decimal,
+    pub balanced: bool,  // every step balanced
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingStep {
+    pub transaction: String,
+    pub journal_entries: Vec<JournalLine>,
+    pub balance_sheet: Vec<BalanceSheetLine>,
+    pub net_income_impact: Decimal,
+    pub cash_impact: Decimal,
+    pub total_assets: Decimal,
+    pub total_liabilities_equity: Decimal,
+    pub balanced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceSheetLine {
+    pub line_item: String,
+    pub before: Decimal,
+    pub after: Decimal,
+    pub change: Decimal,
+}
+
+/// One side of a double-entry journal entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalLine {
+    pub account: Account,
+    #[serde(default)]
+    pub debit: Decimal,
+    #[serde(default)]
+    pub credit: Decimal,
+    pub cash_flow_line: Option<CashFlowLine>,  // overrides the account's default cash flow mapping
+}
+
+/// Chart of accounts; each account maps to a statement line and a default cash flow line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Account {
+    // Assets
+    Cash,
+    AccountsReceivable,
+    Inventory,
+    PrepaidExpenses,
+    Ppe,
+    Intangibles,
+    RightOfUseAsset,
+    DeferredTaxAsset,
+    // Liabilities
+    AccountsPayable,
+    AccruedLiabilities,
+    DeferredRevenue,
+    TaxPayable,
+    Debt,
+    LeaseLiability,
+    DeferredTaxLiability,
+    // Equity
+    PaidInCapital,
+    RetainedEarnings,
+    // Income statement
+    Revenue,
+    Cogs,
+    OperatingExpenses,
+    StockBasedCompensation,
+    DepreciationExpense,
+    AmortizationExpense,
+    ImpairmentLoss,
+    InterestExpense,
+    GainLossOnSale,
+    TaxExpense,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountClass {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+/// Indirect-method cash flow statement lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CashFlowLine {
+    NetIncome,
+    Depreciation,
+    Amortization,
+    Impairment,
+    StockBasedCompensation,
+    InventoryWriteDown,
+    DeferredTax,
+    ChangeInReceivables,
+    ChangeInInventory,
+    ChangeInPrepaidExpenses,
+    ChangeInPayables,
+    ChangeInAccruedLiabilities,
+    ChangeInDeferredRevenue,
+    ChangeInTaxPayable,
+    CapitalExpenditures,
+    AssetSaleProceeds,
+    IntangiblesPurchased,
+    Debt,
+    LeasePrincipal,
+    EquityIssuance,
+    Dividends,
+    NonCash,  // non-cash transaction, not shown on the cash flow statement
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CashFlowSection {
+    Operating,
+    Investing,
+    Financing,
+}
@@ -22,7 +22,7 @@ pub use types::*;
 
 // Re-export core utilities
 pub use core::{
-    calculate_npv, calculate_irr, calculate_xirr, calculate_moic,
+    calculate_npv, calculate_irr, calculate_xirr, calculate_moic, moic_to_irr,
     solve_circular, solve_circular_newton,
 };
 
@@ -39,13 +39,41 @@ pub use fundamentals::{
     equity_enterprise_bridge,
     calculate_diluted_shares,
     analyze_accounting_flow,
+    apply_accounting_flows,
     build_sources_and_uses,
     SourcesAndUsesInput,
     SourcesAndUsesOutput,
+    build_pro_forma_balance_sheet,
+    ProFormaBalanceSheetInput,
+    ProFormaBalanceSheetOutput,
+    allocate_purchase_price,
+    PurchasePriceAllocationInput,
+    PurchasePriceAllocationOutput,
+    run_scenarios,
+    ScenarioInput,
+    ScenarioOutput,
+    calculate_implied_multiples,
+    ImpliedMultiplesInput,
+    ImpliedMultiplesOutput,
 };
 pub use valuation::{
     create_football_field,
+    render_football_field_svg,
+    render_football_field_html,
+    FootballFieldChartInput,
     calculate_paper_lbo,
+    build_lbo_model,
+    LboInput,
+    LboOutput,
+    solve_lbo_target,
+    calculate_lbo_sensitivity,
+    LboTargetInput,
+    LboTargetOutput,
+    LboSensitivityInput,
+    LboSensitivityOutput,
+    price_option,
+    OptionPricingInput,
+    OptionPricingOutput,
 };
 
 // Re-export Phase 3 functions
@@ -53,4 +81,7 @@ pub use pe::{
     calculate_value_bridge,
     ValueBridgeInput,
     ValueBridgeOutput,
+    calculate_equity_structure,
+    EquityStructureInput,
+    EquityStructureOutput,
 };
@@ -0,0 +1,377 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use crate::core::moic_to_irr;
+use crate::error::{FinanceError, Result};
+use crate::valuation::LboExit;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityStructureInput {
+    pub exit_equity_value: Decimal,  // proceeds available to all equity classes at exit
+    pub hold_period_years: Decimal,
+
+    // Institutional strip
+    pub sponsor_investment: Decimal,
+    pub strip_preferred_percent: Decimal,  // % of the sponsor investment in the preferred instrument
+    pub preferred_instrument: Option<PreferredInstrument>,  // defaults to shareholder loan
+    pub preferred_coupon: Decimal,  // % per annum, rolled up and compounding
+
+    // Management sweet equity
+    pub management_investment: Decimal,
+    pub management_ordinary_percent: Decimal,  // % of ordinary shares before any ratchet
+    pub ratchet: Option<Vec<RatchetTier>>,
+}
+
+impl EquityStructureInput {
+    /// Split an LBO exit between the sponsor and management
+    ///
+    /// Takes the exit equity value and holding period from the exit. The strip is
+    /// all ordinary equity with no ratchet; set the preferred and ratchet fields
+    /// with struct update syntax to layer them on.
+    pub fn from_lbo_exit(
+        exit: &LboExit,
+        sponsor_investment: Decimal,
+        management_investment: Decimal,
+        management_ordinary_percent: Decimal,
+    ) -> Self {
+        EquityStructureInput {
+            exit_equity_value: exit.equity_value,
+            hold_period_years: Decimal::from(exit.year),
+            sponsor_investment,
+            strip_preferred_percent: Decimal::ZERO,
+            preferred_instrument: None,
+            preferred_coupon: Decimal::ZERO,
+            management_investment,
+            management_ordinary_percent,
+            ratchet: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreferredInstrument {
+    ShareholderLoan,
+    PreferenceShares,
+}
+
+/// Management's ordinary stake steps up to `management_percent` once the sponsor's
+/// MOIC, measured after the ratchet, reaches `sponsor_moic`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatchetTier {
+    pub sponsor_moic: Decimal,
+    pub management_percent: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityStructureOutput {
+    pub preferred_accrued: Decimal,
+    pub preferred_paid: Decimal,
+    pub ordinary_proceeds: Decimal,
+    pub management_ordinary_percent: Decimal,  // after the ratchet
+    pub ratchet_threshold_met: Option<Decimal>,  // sponsor MOIC threshold of the tier reached
+    pub envy_ratio: Option<Decimal>,  // sponsor cost per % of ordinary / management cost per %, None for free sweet equity
+    pub classes: Vec<ClassDistribution>,
+    pub holders: Vec<HolderReturns>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassDistribution {
+    pub class: String,
+    pub holder: String,
+    pub invested: Decimal,
+    pub proceeds: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderReturns {
+    pub holder: String,
+    pub invested: Decimal,
+    pub proceeds: Decimal,
+    pub moic: Option<Decimal>,  // None when nothing was invested
+    pub irr: Option<Decimal>,  // %, None when nothing was invested or nothing is returned
+}
+
+/// Distribute exit proceeds through a buyout equity structure
+///
+/// The sponsor invests an institutional strip: a shareholder loan or preference
+/// shares accruing a compounding coupon, plus ordinary shares. Management buys
+/// sweet equity, a larger share of the ordinary shares than its money would buy
+/// pro rata. At exit the preferred instrument is repaid with its rolled-up coupon
+/// first; the remainder goes to the ordinary shares, with management's stake
+/// stepping up through the ratchet tiers if the sponsor's MOIC clears them.
+pub fn calculate_equity_structure(input: EquityStructureInput) -> Result<EquityStructureOutput> {
+    validate(&input)?;
+
+    let strip_percent = input.strip_preferred_percent / dec!(100);
+    let preferred_invested = input.sponsor_investment * strip_percent;
+    let sponsor_ordinary_invested = input.sponsor_investment - preferred_invested;
+
+    let preferred_accrued = (Decimal::ONE + input.preferred_coupon / dec!(100))
+        .checked_powd(input.hold_period_years)
+        .and_then(|growth| preferred_invested.checked_mul(growth))
+        .ok_or_else(|| {
+            FinanceError::CalculationError(format!(
+                "A {}% preferred coupon rolled up over {} years overflows",
+                input.preferred_coupon, input.hold_period_years
+            ))
+        })?;
+    let preferred_paid = preferred_accrued.min(input.exit_equity_value.max(Decimal::ZERO));
+    let ordinary_proceeds = (input.exit_equity_value - preferred_paid).max(Decimal::ZERO);
+
+    let sponsor_proceeds_at = |management_percent: Decimal| {
+        preferred_paid + ordinary_proceeds * (Decimal::ONE - management_percent / dec!(100))
+    };
+
+    // The highest tier whose threshold is met with that tier's stake applied
+    let mut tiers = input.ratchet.clone().unwrap_or_default();
+    tiers.sort_by_key(|tier| std::cmp::Reverse(tier.sponsor_moic));
+    let reached = tiers.iter().find(|tier| {
+        sponsor_proceeds_at(tier.management_percent) / input.sponsor_investment >= tier.sponsor_moic
+    });
+    let management_ordinary_percent = reached.map_or(input.management_ordinary_percent, |tier| tier.management_percent);
+
+    let management_proceeds = ordinary_proceeds * management_ordinary_percent / dec!(100);
+    let sponsor_ordinary_proceeds = ordinary_proceeds - management_proceeds;
+
+    let preferred_name = match input.preferred_instrument.unwrap_or(PreferredInstrument::ShareholderLoan) {
+        PreferredInstrument::ShareholderLoan => "Shareholder Loan",
+        PreferredInstrument::PreferenceShares => "Preference Shares",
+    };
+    let classes = vec![
+        ClassDistribution {
+            class: preferred_name.to_string(),
+            holder: "Sponsor".to_string(),
+            invested: preferred_invested,
+            proceeds: preferred_paid,
+        },
+        ClassDistribution {
+            class: "Ordinary Shares".to_string(),
+            holder: "Sponsor".to_string(),
+            invested: sponsor_ordinary_invested,
+            proceeds: sponsor_ordinary_proceeds,
+        },
+        ClassDistribution {
+            class: "Sweet Equity".to_string(),
+            holder: "Management".to_string(),
+            invested: input.management_investment,
+            proceeds: management_proceeds,
+        },
+    ];
+
+    let total_invested = input.sponsor_investment + input.management_investment;
+    let holders = vec![
+        holder_returns("Sponsor", input.sponsor_investment, preferred_paid + sponsor_ordinary_proceeds, input.hold_period_years)?,
+        holder_returns("Management", input.management_investment, management_proceeds, input.hold_period_years)?,
+        holder_returns("Total", total_invested, preferred_paid + ordinary_proceeds, input.hold_period_years)?,
+    ];
+
+    let envy_ratio = if input.management_investment.is_zero() {
+        None
+    } else {
+        Some(
+            (input.sponsor_investment / (dec!(100) - input.management_ordinary_percent))
+                / (input.management_investment / input.management_ordinary_percent),
+        )
+    };
+
+    Ok(EquityStructureOutput {
+        preferred_accrued,
+        preferred_paid,
+        ordinary_proceeds,
+        management_ordinary_percent,
+        ratchet_threshold_met: reached.map(|tier| tier.sponsor_moic),
+        envy_ratio,
+        classes,
+        holders,
+    })
+}
+
+fn holder_returns(holder: &str, invested: Decimal, proceeds: Decimal, years: Decimal) -> Result<HolderReturns> {
+    let moic = if invested.is_zero() { None } else { Some(proceeds / invested) };
+    let irr = match moic {
+        Some(moic) if moic > Decimal::ZERO => Some(moic_to_irr(moic, years)?),
+        _ => None,
+    };
+
+    Ok(HolderReturns {
+        holder: holder.to_string(),
+        invested,
+        proceeds,
+        moic,
+        irr,
+    })
+}
+
+fn validate(input: &EquityStructureInput) -> Result<()> {
+    if input.sponsor_investment <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "sponsor_investment must be positive".to_string(),
+        ));
+    }
+    if input.management_investment < Decimal::ZERO {
+        return Err(FinanceError::NegativeValue(
+            "management_investment cannot be negative".to_string(),
+        ));
+    }
+    if input.hold_period_years <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "hold_period_years must be positive".to_string(),
+        ));
+    }
+    if input.preferred_coupon < Decimal::ZERO {
+        return Err(FinanceError::NegativeValue(
+            "preferred_coupon cannot be negative".to_string(),
+        ));
+    }
+    if input.strip_preferred_percent < Decimal::ZERO || input.strip_preferred_percent >= dec!(100) {
+        return Err(FinanceError::OutOfRange(
+            "strip_preferred_percent must be at least 0 and below 100".to_string(),
+        ));
+    }
+    let percent_in_range = |percent: Decimal| percent > Decimal::ZERO && percent < dec!(100);
+    if !percent_in_range(input.management_ordinary_percent) {
+        return Err(FinanceError::OutOfRange(
+            "management_ordinary_percent must be between 0 and 100".to_string(),
+        ));
+    }
+    for tier in input.ratchet.iter().flatten() {
+        if !percent_in_range(tier.management_percent) || tier.sponsor_moic <= Decimal::ZERO {
+            return Err(FinanceError::OutOfRange(format!(
+                "Ratchet tier at {}x must have a positive threshold and a management percent between 0 and 100",
+                tier.sponsor_moic
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn input(exit_equity_value: Decimal) -> EquityStructureInput {
+        EquityStructureInput {
+            exit_equity_value,
+            hold_period_years: dec!(5),
+            sponsor_investment: dec!(1000),
+            strip_preferred_percent: dec!(90),
+            preferred_instrument: None,
+            preferred_coupon: dec!(10),
+            management_investment: dec!(10),
+            management_ordinary_percent: dec!(15),
+            ratchet: Some(vec![
+                RatchetTier { sponsor_moic: dec!(2.5), management_percent: dec!(20) },
+                RatchetTier { sponsor_moic: dec!(3), management_percent: dec!(25) },
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_strip_and_sweet_equity_with_ratchet() {
+        let result = calculate_equity_structure(input(dec!(3500))).unwrap();
+
+        // 900 of loan notes compounding at 10% for 5 years
+        assert!((result.preferred_accrued - dec!(1449.459)).abs() < dec!(0.001));
+        assert_eq!(result.preferred_paid, result.preferred_accrued);
+
+        // 25% would leave the sponsor below 3.0x, so the 2.5x tier (20%) applies
+        assert_eq!(result.ratchet_threshold_met, Some(dec!(2.5)));
+        assert_eq!(result.management_ordinary_percent, dec!(20));
+
+        let sponsor = &result.holders[0];
+        let management = &result.holders[1];
+        assert!((sponsor.moic.unwrap() - dec!(3.0899)).abs() < dec!(0.0001));
+        assert!((management.proceeds - dec!(410.108)).abs() < dec!(0.001));
+        assert!(management.moic.unwrap() > dec!(40));
+        assert!(management.irr.unwrap() > sponsor.irr.unwrap());
+
+        // Proceeds are fully distributed across the classes
+        let distributed: Decimal = result.classes.iter().map(|c| c.proceeds).sum();
+        assert_eq!(distributed, dec!(3500));
+
+        // Sponsor pays 1000 for 85%, management 10 for 15%
+        assert!((result.envy_ratio.unwrap() - dec!(17.647)).abs() < dec!(0.001));
+    }
+
+    #[test]
+    fn test_no_ratchet_below_threshold() {
+        let result = calculate_equity_structure(input(dec!(2200))).unwrap();
+
+        assert_eq!(result.ratchet_threshold_met, None);
+        assert_eq!(result.management_ordinary_percent, dec!(15));
+    }
+
+    #[test]
+    fn test_loan_notes_absorb_a_weak_exit() {
+        let result = calculate_equity_structure(input(dec!(1200))).unwrap();
+
+        assert_eq!(result.preferred_paid, dec!(1200));
+        assert_eq!(result.ordinary_proceeds, Decimal::ZERO);
+
+        let management = &result.holders[1];
+        assert_eq!(management.moic, Some(Decimal::ZERO));
+        assert_eq!(management.irr, None);
+    }
+
+    #[test]
+    fn test_free_sweet_equity() {
+        let result = calculate_equity_structure(EquityStructureInput {
+            management_investment: Decimal::ZERO,
+            ..input(dec!(3500))
+        })
+        .unwrap();
+
+        let management = &result.holders[1];
+        assert!(management.proceeds > Decimal::ZERO);
+        assert_eq!(management.moic, None);
+        assert_eq!(management.irr, None);
+        assert_eq!(result.envy_ratio, None);
+
+        // The sponsor's returns do not depend on what management paid
+        let paid = calculate_equity_structure(input(dec!(3500))).unwrap();
+        assert_eq!(result.holders[0].moic, paid.holders[0].moic);
+    }
+
+    #[test]
+    fn test_invalid_preferred_coupon_rejected() {
+        let negative = EquityStructureInput {
+            preferred_coupon: dec!(-150),
+            ..input(dec!(3000))
+        };
+        assert!(matches!(calculate_equity_structure(negative), Err(FinanceError::NegativeValue(_))));
+
+        // 11^40 is beyond Decimal's range
+        let overflowing = EquityStructureInput {
+            preferred_coupon: dec!(1000),
+            hold_period_years: dec!(40),
+            ..input(dec!(3000))
+        };
+        assert!(matches!(calculate_equity_structure(overflowing), Err(FinanceError::CalculationError(_))));
+    }
+
+    #[test]
+    fn test_from_lbo_exit() {
+        let exit = LboExit {
+            year: 5,
+            exit_ebitda: dec!(150),
+            enterprise_value: dec!(1500),
+            net_debt: dec!(300),
+            equity_value: dec!(1200),
+            moic: dec!(2.4),
+            irr: Some(dec!(19.1)),
+        };
+        let result = calculate_equity_structure(EquityStructureInput {
+            strip_preferred_percent: dec!(90),
+            preferred_coupon: dec!(10),
+            ..EquityStructureInput::from_lbo_exit(&exit, dec!(490), dec!(10), dec!(10))
+        })
+        .unwrap();
+
+        // 441 of loan notes at 10% for 5 years are repaid ahead of the ordinary shares
+        assert!((result.preferred_paid - dec!(710.235)).abs() < dec!(0.001));
+        assert_eq!(result.holders[2].proceeds, dec!(1200));
+        assert!((result.holders[1].proceeds - dec!(48.9765)).abs() < dec!(0.0001));
+    }
+}
@@ -1,3 +1,8 @@
 pub mod value_bridge;
+pub mod equity_structure;
 
 pub use value_bridge::{ValueBridgeInput, ValueBridgeOutput, BridgeStep, calculate_value_bridge};
+pub use equity_structure::{
+    EquityStructureInput, EquityStructureOutput, PreferredInstrument, RatchetTier, ClassDistribution,
+    HolderReturns, calculate_equity_structure,
+};
@@ -14,14 +14,21 @@ pub struct ValueBridgeInput {
     pub exit_ebitda: Decimal,
     pub exit_multiple: Decimal,
     pub exit_net_debt: Decimal,
+
+    // Cash flows during the hold
+    pub interim_distributions: Option<Decimal>,  // cash returned before exit, e.g. dividend recaps
+    pub additional_equity_invested: Option<Decimal>,  // equity injected after entry, e.g. add-ons
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValueBridgeOutput {
     pub entry_equity: Decimal,
     pub exit_equity: Decimal,
+    pub interim_distributions: Decimal,
+    pub additional_equity_invested: Decimal,
+    pub total_value: Decimal,  // exit equity plus cash returned before exit
     pub total_return: Decimal,
-    pub moic: Decimal,
+    pub moic: Decimal,  // total value / (entry equity + additional equity)
 
     // Attribution breakdown
     pub ebitda_growth_value: Decimal,
@@ -36,6 +43,9 @@ pub struct ValueBridgeOutput {
     pub other_value: Decimal,
     pub other_pct: Decimal,
 
+    pub interim_distributions_pct: Decimal,
+    pub additional_equity_pct: Decimal,  // negative: a drag on the return
+
     // Bridge steps for visualization
     pub bridge_steps: Vec<BridgeStep>,
 }
@@ -49,6 +59,9 @@ pub struct BridgeStep {
 
 /// Calculate returns attribution bridge
 /// Decomposes equity returns into: EBITDA growth, multiple expansion, and deleveraging
+///
+/// Cash returned before exit is added to the exit equity and equity injected after
+/// entry deducted, each as its own step after the exit equity value.
 pub fn calculate_value_bridge(input: ValueBridgeInput) -> Result<ValueBridgeOutput> {
     // Calculate entry and exit equity values
     let entry_ev = input.entry_ebitda * input.entry_multiple;
@@ -57,9 +70,14 @@ pub fn calculate_value_bridge(input: ValueBridgeInput) -> Result<ValueBridgeOutp
     let exit_ev = input.exit_ebitda * input.exit_multiple;
     let exit_equity = exit_ev - input.exit_net_debt;
 
-    let total_return = exit_equity - entry_equity;
-    let moic = if entry_equity > Decimal::ZERO {
-        exit_equity / entry_equity
+    let interim_distributions = input.interim_distributions.unwrap_or(Decimal::ZERO);
+    let additional_equity_invested = input.additional_equity_invested.unwrap_or(Decimal::ZERO);
+    let total_value = exit_equity + interim_distributions;
+    let total_invested = entry_equity + additional_equity_invested;
+
+    let total_return = total_value - total_invested;
+    let moic = if total_invested > Decimal::ZERO {
+        total_value / total_invested
     } else {
         Decimal::ZERO
     };
@@ -129,34 +147,46 @@ pub fn calculate_value_bridge(input: ValueBridgeInput) -> Result<ValueBridgeOutp
         cumulative: exit_equity,
     });
 
-    // Calculate percentages of total return
-    let ebitda_growth_pct = if total_return != Decimal::ZERO {
-        (ebitda_growth_value / total_return) * dec!(100)
-    } else {
-        Decimal::ZERO
-    };
-
-    let multiple_expansion_pct = if total_return != Decimal::ZERO {
-        (multiple_expansion_value / total_return) * dec!(100)
-    } else {
-        Decimal::ZERO
-    };
-
-    let deleveraging_pct = if total_return != Decimal::ZERO {
-        (deleveraging_value / total_return) * dec!(100)
-    } else {
-        Decimal::ZERO
-    };
+    // Cash flows during the hold sit on top of the exit equity
+    cumulative = exit_equity;
+    if !interim_distributions.is_zero() {
+        cumulative += interim_distributions;
+        bridge_steps.push(BridgeStep {
+            description: "Cash Returned Before Exit".to_string(),
+            value: interim_distributions,
+            cumulative,
+        });
+    }
+    if !additional_equity_invested.is_zero() {
+        cumulative -= additional_equity_invested;
+        bridge_steps.push(BridgeStep {
+            description: "Additional Equity Invested".to_string(),
+            value: -additional_equity_invested,
+            cumulative,
+        });
+    }
 
-    let other_pct = if total_return != Decimal::ZERO {
-        (other_value / total_return) * dec!(100)
-    } else {
-        Decimal::ZERO
+    // Calculate percentages of total return
+    let share = |value: Decimal| {
+        if total_return != Decimal::ZERO {
+            (value / total_return) * dec!(100)
+        } else {
+            Decimal::ZERO
+        }
     };
+    let ebitda_growth_pct = share(ebitda_growth_value);
+    let multiple_expansion_pct = share(multiple_expansion_value);
+    let deleveraging_pct = share(deleveraging_value);
+    let other_pct = share(other_value);
+    let interim_distributions_pct = share(interim_distributions);
+    let additional_equity_pct = share(-additional_equity_invested);
 
     Ok(ValueBridgeOutput {
         entry_equity,
         exit_equity,
+        interim_distributions,
+        additional_equity_invested,
+        total_value,
         total_return,
         moic,
         ebitda_growth_value,
@@ -167,6 +197,8 @@ pub fn calculate_value_bridge(input: ValueBridgeInput) -> Result<ValueBridgeOutp
         deleveraging_pct,
         other_value,
         other_pct,
+        interim_distributions_pct,
+        additional_equity_pct,
         bridge_steps,
     })
 }
@@ -185,6 +217,8 @@ mod tests {
             exit_ebitda: dec!(150),
             exit_multiple: dec!(10), // No multiple expansion
             exit_net_debt: dec!(500), // No deleveraging
+            interim_distributions: None,
+            additional_equity_invested: None,
         };
 
         let result = calculate_value_bridge(input).unwrap();
@@ -223,6 +257,8 @@ mod tests {
             exit_ebitda: dec!(100), // No EBITDA growth
             exit_multiple: dec!(10), // Multiple expansion
             exit_net_debt: dec!(400), // No deleveraging
+            interim_distributions: None,
+            additional_equity_invested: None,
         };
 
         let result = calculate_value_bridge(input).unwrap();
@@ -250,6 +286,8 @@ mod tests {
             exit_ebitda: dec!(100), // No growth
             exit_multiple: dec!(10), // No multiple expansion
             exit_net_debt: dec!(300), // Paid down 300 of debt
+            interim_distributions: None,
+            additional_equity_invested: None,
         };
 
         let result = calculate_value_bridge(input).unwrap();
@@ -275,6 +313,8 @@ mod tests {
             exit_ebitda: dec!(130), // 30% EBITDA growth
             exit_multiple: dec!(11), // 1 turn multiple expansion
             exit_net_debt: dec!(300), // 200 deleveraging
+            interim_distributions: None,
+            additional_equity_invested: None,
         };
 
         let result = calculate_value_bridge(input).unwrap();
@@ -313,4 +353,42 @@ mod tests {
             + result.other_pct;
         assert!((total_pct - dec!(100)).abs() < dec!(0.1));
     }
+
+    #[test]
+    fn test_value_bridge_with_recap_and_add_on() {
+        let input = ValueBridgeInput {
+            entry_ebitda: dec!(100),
+            entry_multiple: dec!(10),
+            entry_net_debt: dec!(500),
+            exit_ebitda: dec!(130),
+            exit_multiple: dec!(10),
+            exit_net_debt: dec!(600), // 200 recap debt, 100 repaid
+            interim_distributions: Some(dec!(200)),
+            additional_equity_invested: Some(dec!(50)),
+        };
+
+        let result = calculate_value_bridge(input).unwrap();
+
+        // Exit equity = 1300 - 600 = 700; total value includes the 200 recap
+        assert_eq!(result.exit_equity, dec!(700));
+        assert_eq!(result.total_value, dec!(900));
+
+        // Return on 550 invested = 350; MOIC = 900 / 550
+        assert_eq!(result.total_return, dec!(350));
+        assert_eq!(result.moic, dec!(900) / dec!(550));
+
+        // Recap debt shows as negative deleveraging, offset by the distribution
+        assert_eq!(result.deleveraging_value, dec!(-100));
+        let total_pct = result.ebitda_growth_pct
+            + result.multiple_expansion_pct
+            + result.deleveraging_pct
+            + result.other_pct
+            + result.interim_distributions_pct
+            + result.additional_equity_pct;
+        assert!((total_pct - dec!(100)).abs() < dec!(0.1));
+
+        let last = result.bridge_steps.last().unwrap();
+        assert_eq!(last.description, "Additional Equity Invested");
+        assert_eq!(last.cumulative, dec!(850));
+    }
 }
@@ -98,3 +98,11 @@ pub struct CovenantOutput {
     pub results: Vec<CovenantResult>,
     pub violations: Vec<String>,
 }
+
+/// Option exercise style, shared by option pricing and fair-value option dilution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseStyle {
+    European,  // Black-Scholes-Merton
+    American,  // Cox-Ross-Rubinstein binomial tree with early exercise
+}
@@ -1,69 +1,194 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use crate::error::Result;
-use super::types::{FootballFieldInput, FootballFieldOutput, ValuationMethodology};
+use crate::error::{FinanceError, Result};
+use super::types::{FootballFieldInput, FootballFieldOutput, MethodologyRange, ValuationMethodology};
 
 /// Create football field valuation summary
 /// Summarizes valuation ranges from multiple methodologies
+///
+/// Methodologies are the legacy DCF / comps / precedents pairs followed by any
+/// named `methodologies`. The legacy pairs are always per share; with
+/// `diluted_shares` the named methodologies are treated as equity values and
+/// converted to per share. Without it they stay in their own units, so they cannot
+/// be mixed with the legacy pairs, reference bars or `current_price`. The 52-week trading range and analyst price
+/// targets are shown as reference bars and kept out of the overall and blended
+/// ranges. The blended range weights each methodology's low, high and base:
+/// equally when no weights are given, otherwise every methodology needs one, so the
+/// weighted blend cannot be combined with the (unweighted) legacy pairs.
 pub fn create_football_field(input: FootballFieldInput) -> Result<FootballFieldOutput> {
-    let mut methodologies = Vec::new();
-
-    // DCF methodology
-    let dcf_midpoint = (input.dcf_low + input.dcf_high) / dec!(2);
-    let dcf_range_width = input.dcf_high - input.dcf_low;
-    methodologies.push(ValuationMethodology {
-        method: "DCF Analysis".to_string(),
-        low: input.dcf_low,
-        high: input.dcf_high,
-        midpoint: dcf_midpoint,
-        range_width: dcf_range_width,
-    });
-
-    // Comparable Companies
-    let comps_midpoint = (input.comps_low + input.comps_high) / dec!(2);
-    let comps_range_width = input.comps_high - input.comps_low;
-    methodologies.push(ValuationMethodology {
-        method: "Comparable Companies".to_string(),
-        low: input.comps_low,
-        high: input.comps_high,
-        midpoint: comps_midpoint,
-        range_width: comps_range_width,
-    });
-
-    // Precedent Transactions
-    let precedents_midpoint = (input.precedents_low + input.precedents_high) / dec!(2);
-    let precedents_range_width = input.precedents_high - input.precedents_low;
-    methodologies.push(ValuationMethodology {
-        method: "Precedent Transactions".to_string(),
-        low: input.precedents_low,
-        high: input.precedents_high,
-        midpoint: precedents_midpoint,
-        range_width: precedents_range_width,
-    });
+    // Legacy pairs are always per share; named methodologies only once converted
+    let per_share = input.diluted_shares.is_some() || input.methodologies.iter().flatten().next().is_none();
+    if !per_share {
+        let per_share_inputs: Vec<&str> = [
+            ("DCF / comps / precedents pairs", input.dcf_low.or(input.dcf_high)
+                .or(input.comps_low).or(input.comps_high)
+                .or(input.precedents_low).or(input.precedents_high).is_some()),
+            ("52-week range", input.fifty_two_week_low.or(input.fifty_two_week_high).is_some()),
+            ("analyst_targets", input.analyst_targets.is_some()),
+            ("current_price", input.current_price.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, given)| *given)
+        .map(|(name, _)| name)
+        .collect();
+        if !per_share_inputs.is_empty() {
+            return Err(FinanceError::InvalidInput(format!(
+                "{} are per share; give diluted_shares to convert the named methodologies",
+                per_share_inputs.join(", ")
+            )));
+        }
+    }
+
+    let mut ranges = Vec::new();
+    for (name, low, high) in [
+        ("DCF Analysis", input.dcf_low, input.dcf_high),
+        ("Comparable Companies", input.comps_low, input.comps_high),
+        ("Precedent Transactions", input.precedents_low, input.precedents_high),
+    ] {
+        match (low, high) {
+            (Some(low), Some(high)) => ranges.push(MethodologyRange {
+                name: name.to_string(),
+                low,
+                high,
+                base: None,
+                weight: None,
+            }),
+            (None, None) => {}
+            _ => {
+                return Err(FinanceError::MissingField(format!(
+                    "{} needs both a low and a high value",
+                    name
+                )));
+            }
+        }
+    }
+
+    // Per-share conversion of the named methodologies' equity values
+    let divisor = match input.diluted_shares {
+        Some(shares) if shares <= Decimal::ZERO => {
+            return Err(FinanceError::InvalidInput(
+                "diluted_shares must be positive".to_string(),
+            ));
+        }
+        Some(shares) => shares,
+        None => Decimal::ONE,
+    };
+    ranges.extend(input.methodologies.iter().flatten().map(|range| MethodologyRange {
+        low: range.low / divisor,
+        high: range.high / divisor,
+        base: range.base.map(|base| base / divisor),
+        ..range.clone()
+    }));
+
+    if ranges.is_empty() {
+        return Err(FinanceError::InvalidInput(
+            "At least one valuation methodology is required".to_string(),
+        ));
+    }
+    for range in &ranges {
+        validate_range(range)?;
+    }
+
+    // Equal weights unless given, and then given for every methodology
+    let unweighted: Vec<&str> = ranges
+        .iter()
+        .filter(|range| range.weight.is_none())
+        .map(|range| range.name.as_str())
+        .collect();
+    if !unweighted.is_empty() && unweighted.len() < ranges.len() {
+        return Err(FinanceError::MissingField(format!(
+            "weight for {} (weights must be given for every methodology or none)",
+            unweighted.join(", ")
+        )));
+    }
+    let weights: Vec<Decimal> = ranges
+        .iter()
+        .map(|range| range.weight.unwrap_or(Decimal::ONE))
+        .collect();
+    let total_weight: Decimal = weights.iter().sum();
+    if total_weight <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "Methodology weights must sum to a positive number".to_string(),
+        ));
+    }
+
+    let mut methodologies: Vec<ValuationMethodology> = ranges
+        .iter()
+        .zip(&weights)
+        .map(|(range, weight)| {
+            let midpoint = (range.low + range.high) / dec!(2);
+            ValuationMethodology {
+                method: range.name.clone(),
+                low: range.low,
+                high: range.high,
+                midpoint,
+                range_width: range.high - range.low,
+                base: range.base.unwrap_or(midpoint),
+                weight: Some(weight / total_weight * dec!(100)),
+                reference: false,
+            }
+        })
+        .collect();
 
     // Calculate overall range
-    let overall_low = input.dcf_low.min(input.comps_low).min(input.precedents_low);
-    let overall_high = input.dcf_high.max(input.comps_high).max(input.precedents_high);
+    let overall_low = methodologies.iter().map(|m| m.low).min().unwrap_or(Decimal::ZERO);
+    let overall_high = methodologies.iter().map(|m| m.high).max().unwrap_or(Decimal::ZERO);
     let overall_midpoint = (overall_low + overall_high) / dec!(2);
 
-    // Calculate implied upside/downside if current price provided
-    let implied_upside_downside = if let Some(current_price) = input.current_price {
-        if current_price > Decimal::ZERO {
-            Some(((overall_midpoint - current_price) / current_price) * dec!(100))
+    let blend = |value: fn(&ValuationMethodology) -> Decimal| -> Decimal {
+        methodologies
+            .iter()
+            .zip(&weights)
+            .map(|(m, weight)| value(m) * weight)
+            .sum::<Decimal>()
+            / total_weight
+    };
+    let blended_low = blend(|m| m.low);
+    let blended_high = blend(|m| m.high);
+    let blended_base = blend(|m| m.base);
+
+    // Reference bars, already per share
+    match (input.fifty_two_week_low, input.fifty_two_week_high) {
+        (Some(low), Some(high)) => methodologies.push(reference_bar("52-Week Trading Range", low, high, None)?),
+        (None, None) => {}
+        _ => {
+            return Err(FinanceError::MissingField(
+                "52-week range needs both a low and a high".to_string(),
+            ));
+        }
+    }
+    if let Some(targets) = input.analyst_targets.as_ref().filter(|targets| !targets.is_empty()) {
+        let mut sorted = targets.clone();
+        sorted.sort();
+        let n = sorted.len();
+        let median = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / dec!(2)
         } else {
-            None
+            sorted[n / 2]
+        };
+        methodologies.push(reference_bar("Analyst Price Targets", sorted[0], sorted[n - 1], Some(median))?);
+    }
+
+    // Calculate implied upside/downside if current price provided, per share only
+    let upside = |value: Decimal| match input.current_price {
+        Some(current_price) if per_share && current_price > Decimal::ZERO => {
+            Some(((value - current_price) / current_price) * dec!(100))
         }
-    } else {
-        None
+        _ => None,
     };
+    let implied_upside_downside = upside(overall_midpoint);
+    let blended_upside_downside = upside(blended_base);
 
     // Generate summary
+    let ranges_text: Vec<String> = methodologies
+        .iter()
+        .map(|m| format!("{}: ${:.2}-${:.2}", m.method, m.low, m.high))
+        .collect();
     let summary = format!(
-        "Valuation range: ${:.2} - ${:.2} (midpoint: ${:.2}). DCF: ${:.2}-${:.2}, Comps: ${:.2}-${:.2}, Precedents: ${:.2}-${:.2}",
+        "Valuation range: ${:.2} - ${:.2} (midpoint: ${:.2}). Blended: ${:.2} - ${:.2} (base: ${:.2}). {}",
         overall_low, overall_high, overall_midpoint,
-        input.dcf_low, input.dcf_high,
-        input.comps_low, input.comps_high,
-        input.precedents_low, input.precedents_high
+        blended_low, blended_high, blended_base,
+        ranges_text.join(", ")
     );
 
     Ok(FootballFieldOutput {
@@ -71,27 +196,104 @@ pub fn create_football_field(input: FootballFieldInput) -> Result<FootballFieldO
         overall_low,
         overall_high,
         overall_midpoint,
+        blended_low,
+        blended_high,
+        blended_base,
+        per_share,
         current_price: input.current_price,
         implied_upside_downside,
+        blended_upside_downside,
         summary,
     })
 }
 
+fn validate_range(range: &MethodologyRange) -> Result<()> {
+    if range.low > range.high {
+        return Err(FinanceError::InvalidInput(format!(
+            "{}: low {} is above high {}",
+            range.name, range.low, range.high
+        )));
+    }
+    if let Some(base) = range.base {
+        if base < range.low || base > range.high {
+            return Err(FinanceError::OutOfRange(format!(
+                "{}: base {} is outside the range {} - {}",
+                range.name, base, range.low, range.high
+            )));
+        }
+    }
+    if range.weight.is_some_and(|weight| weight < Decimal::ZERO) {
+        return Err(FinanceError::NegativeValue(format!(
+            "{}: weight cannot be negative",
+            range.name
+        )));
+    }
+    Ok(())
+}
+
+fn reference_bar(name: &str, low: Decimal, high: Decimal, base: Option<Decimal>) -> Result<ValuationMethodology> {
+    if low > high {
+        return Err(FinanceError::InvalidInput(format!(
+            "{}: low {} is above high {}",
+            name, low, high
+        )));
+    }
+    let midpoint = (low + high) / dec!(2);
+    Ok(ValuationMethodology {
+        method: name.to_string(),
+        low,
+        high,
+        midpoint,
+        range_width: high - low,
+        base: base.unwrap_or(midpoint),
+        weight: None,
+        reference: true,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rust_decimal_macros::dec;
 
+    fn legacy() -> FootballFieldInput {
+        FootballFieldInput {
+            dcf_low: None,
+            dcf_high: None,
+            comps_low: None,
+            comps_high: None,
+            precedents_low: None,
+            precedents_high: None,
+            current_price: None,
+            methodologies: None,
+            fifty_two_week_low: None,
+            fifty_two_week_high: None,
+            analyst_targets: None,
+            diluted_shares: None,
+        }
+    }
+
+    fn method(name: &str, low: Decimal, high: Decimal, base: Option<Decimal>, weight: Option<Decimal>) -> MethodologyRange {
+        MethodologyRange {
+            name: name.to_string(),
+            low,
+            high,
+            base,
+            weight,
+        }
+    }
+
     #[test]
     fn test_football_field() {
         let input = FootballFieldInput {
-            dcf_low: dec!(90),
-            dcf_high: dec!(110),
-            comps_low: dec!(85),
-            comps_high: dec!(105),
-            precedents_low: dec!(95),
-            precedents_high: dec!(115),
+            dcf_low: Some(dec!(90)),
+            dcf_high: Some(dec!(110)),
+            comps_low: Some(dec!(85)),
+            comps_high: Some(dec!(105)),
+            precedents_low: Some(dec!(95)),
+            precedents_high: Some(dec!(115)),
             current_price: Some(dec!(80)),
+            ..legacy()
         };
 
         let result = create_football_field(input).unwrap();
@@ -111,13 +313,14 @@ mod tests {
     #[test]
     fn test_football_field_no_current_price() {
         let input = FootballFieldInput {
-            dcf_low: dec!(100),
-            dcf_high: dec!(120),
-            comps_low: dec!(95),
-            comps_high: dec!(115),
-            precedents_low: dec!(105),
-            precedents_high: dec!(125),
+            dcf_low: Some(dec!(100)),
+            dcf_high: Some(dec!(120)),
+            comps_low: Some(dec!(95)),
+            comps_high: Some(dec!(115)),
+            precedents_low: Some(dec!(105)),
+            precedents_high: Some(dec!(125)),
             current_price: None,
+            ..legacy()
         };
 
         let result = create_football_field(input).unwrap();
@@ -129,13 +332,14 @@ mod tests {
     #[test]
     fn test_football_field_range_widths() {
         let input = FootballFieldInput {
-            dcf_low: dec!(80),
-            dcf_high: dec!(120),
-            comps_low: dec!(90),
-            comps_high: dec!(100),
-            precedents_low: dec!(95),
-            precedents_high: dec!(105),
+            dcf_low: Some(dec!(80)),
+            dcf_high: Some(dec!(120)),
+            comps_low: Some(dec!(90)),
+            comps_high: Some(dec!(100)),
+            precedents_low: Some(dec!(95)),
+            precedents_high: Some(dec!(105)),
             current_price: None,
+            ..legacy()
         };
 
         let result = create_football_field(input).unwrap();
@@ -149,4 +353,163 @@ mod tests {
         // Precedents has narrowest range (10)
         assert_eq!(result.methodologies[2].range_width, dec!(10));
     }
+
+    #[test]
+    fn test_arbitrary_methodologies_weighted_per_share() {
+        let input = FootballFieldInput {
+            current_price: Some(dec!(20)),
+            methodologies: Some(vec![
+                method("DCF", dec!(2000), dec!(3000), Some(dec!(2400)), Some(dec!(50))),
+                method("Trading Comps", dec!(1800), dec!(2600), None, Some(dec!(30))),
+                method("Precedents", dec!(2200), dec!(3200), None, Some(dec!(20))),
+                method("LBO", dec!(1600), dec!(2200), None, Some(dec!(0))),
+            ]),
+            fifty_two_week_low: Some(dec!(15)),
+            fifty_two_week_high: Some(dec!(24)),
+            analyst_targets: Some(vec![dec!(28), dec!(19), dec!(25), dec!(22)]),
+            diluted_shares: Some(dec!(100)),
+            ..legacy()
+        };
+
+        let result = create_football_field(input).unwrap();
+        assert!(result.per_share);
+        assert_eq!(result.methodologies.len(), 6);
+
+        // Per-share conversion; reference bars stay out of the overall range
+        assert_eq!(result.methodologies[0].low, dec!(20));
+        assert_eq!(result.overall_low, dec!(16));
+        assert_eq!(result.overall_high, dec!(32));
+
+        // Weights normalised over 100; the LBO is shown but carries no weight
+        assert_eq!(result.methodologies[3].weight, Some(dec!(0)));
+
+        // Blended low = 0.5 × 20 + 0.3 × 18 + 0.2 × 22
+        assert_eq!(result.blended_low, dec!(19.8));
+        // Blended base = 0.5 × 24 + 0.3 × 22 + 0.2 × 27
+        assert_eq!(result.blended_base, dec!(24));
+        assert_eq!(result.blended_upside_downside, Some(dec!(20)));
+
+        let targets = &result.methodologies[5];
+        assert!(targets.reference);
+        assert_eq!(targets.low, dec!(19));
+        assert_eq!(targets.high, dec!(28));
+        assert_eq!(targets.base, dec!(23.5));
+    }
+
+    #[test]
+    fn test_diluted_shares_leave_legacy_pairs_per_share() {
+        let input = FootballFieldInput {
+            dcf_low: Some(dec!(18)),
+            dcf_high: Some(dec!(26)),
+            methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, None)]),
+            diluted_shares: Some(dec!(100)),
+            ..legacy()
+        };
+
+        let result = create_football_field(input).unwrap();
+        assert_eq!(result.methodologies[0].low, dec!(18));
+        assert_eq!(result.methodologies[0].high, dec!(26));
+        assert_eq!(result.methodologies[1].low, dec!(16));
+        assert_eq!(result.methodologies[1].high, dec!(22));
+        assert_eq!(result.overall_low, dec!(16));
+        assert_eq!(result.overall_high, dec!(26));
+    }
+
+    #[test]
+    fn test_equal_weights_by_default() {
+        let input = FootballFieldInput {
+            methodologies: Some(vec![
+                method("A", dec!(10), dec!(20), None, None),
+                method("B", dec!(20), dec!(40), None, None),
+            ]),
+            ..legacy()
+        };
+
+        let result = create_football_field(input).unwrap();
+        assert_eq!(result.blended_low, dec!(15));
+        assert_eq!(result.blended_high, dec!(30));
+        assert_eq!(result.methodologies[0].weight, Some(dec!(50)));
+    }
+
+    #[test]
+    fn test_mixed_weighted_and_unweighted_rejected() {
+        let input = FootballFieldInput {
+            dcf_low: Some(dec!(18)),
+            dcf_high: Some(dec!(26)),
+            methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, Some(dec!(40)))]),
+            diluted_shares: Some(dec!(100)),
+            ..legacy()
+        };
+        assert!(matches!(create_football_field(input), Err(FinanceError::MissingField(_))));
+    }
+
+    #[test]
+    fn test_unconverted_methodologies_not_mixed_with_per_share_inputs() {
+        // Equity values next to per-share pairs would otherwise report a 5445% upside
+        let mixed = FootballFieldInput {
+            dcf_low: Some(dec!(18)),
+            dcf_high: Some(dec!(26)),
+            current_price: Some(dec!(20)),
+            methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, None)]),
+            ..legacy()
+        };
+        assert!(matches!(create_football_field(mixed), Err(FinanceError::InvalidInput(_))));
+
+        for per_share_input in [
+            FootballFieldInput { current_price: Some(dec!(20)), ..legacy() },
+            FootballFieldInput { fifty_two_week_low: Some(dec!(15)), fifty_two_week_high: Some(dec!(25)), ..legacy() },
+            FootballFieldInput { analyst_targets: Some(vec![dec!(22)]), ..legacy() },
+        ] {
+            let input = FootballFieldInput {
+                methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, None)]),
+                ..per_share_input
+            };
+            assert!(create_football_field(input).is_err());
+        }
+
+        let converted = FootballFieldInput {
+            dcf_low: Some(dec!(18)),
+            dcf_high: Some(dec!(26)),
+            current_price: Some(dec!(20)),
+            methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, None)]),
+            diluted_shares: Some(dec!(100)),
+            ..legacy()
+        };
+        let result = create_football_field(converted).unwrap();
+        assert!(result.per_share);
+        assert_eq!(result.implied_upside_downside, Some(dec!(5)));
+    }
+
+    #[test]
+    fn test_per_share_flag() {
+        let legacy_only = FootballFieldInput {
+            dcf_low: Some(dec!(18)),
+            dcf_high: Some(dec!(26)),
+            ..legacy()
+        };
+        assert!(create_football_field(legacy_only).unwrap().per_share);
+
+        let unconverted = FootballFieldInput {
+            methodologies: Some(vec![method("LBO", dec!(1600), dec!(2200), None, None)]),
+            ..legacy()
+        };
+        assert!(!create_football_field(unconverted).unwrap().per_share);
+    }
+
+    #[test]
+    fn test_invalid_ranges_rejected() {
+        let base_outside = FootballFieldInput {
+            methodologies: Some(vec![method("A", dec!(10), dec!(20), Some(dec!(25)), None)]),
+            ..legacy()
+        };
+        assert!(create_football_field(base_outside).is_err());
+
+        let half_legacy = FootballFieldInput {
+            dcf_low: Some(dec!(10)),
+            ..legacy()
+        };
+        assert!(create_football_field(half_legacy).is_err());
+
+        assert!(create_football_field(legacy()).is_err());
+    }
 }
@@ -0,0 +1,322 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::types::{FootballFieldChartInput, FootballFieldOutput};
+
+const DEFAULT_TITLE: &str = "Valuation Summary";
+const DEFAULT_WIDTH: u32 = 800;
+const MIN_WIDTH: u32 = 400;
+const LABEL_MARGIN: Decimal = dec!(200);
+const RIGHT_MARGIN: Decimal = dec!(60);
+const TOP_MARGIN: Decimal = dec!(60);
+const ROW_HEIGHT: Decimal = dec!(40);
+const BAR_HEIGHT: Decimal = dec!(20);
+const AXIS_HEIGHT: Decimal = dec!(40);
+const AXIS_TICKS: u32 = 4;
+
+const VALUATION_FILL: &str = "#1f4e79";
+const REFERENCE_FILL: &str = "#a6a6a6";
+const PRICE_STROKE: &str = "#c00000";
+
+/// Render the football field as a standalone SVG
+///
+/// One horizontal bar per methodology in output order, with the base value marked,
+/// low and high labelled either side, the blended range shaded behind the bars and
+/// the current price drawn as a dashed line. Reference bars are grey. Coordinates
+/// are computed in decimals and written to one decimal place, so the same output
+/// always renders to the same string.
+pub fn render_football_field_svg(input: FootballFieldChartInput) -> Result<String> {
+    let title = input.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    svg(&input.football_field, title, input.width.unwrap_or(DEFAULT_WIDTH))
+}
+
+/// Render the football field SVG in a minimal HTML page with the summary beneath
+pub fn render_football_field_html(input: FootballFieldChartInput) -> Result<String> {
+    let title = input.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let chart = svg(&input.football_field, title, input.width.unwrap_or(DEFAULT_WIDTH))?;
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}<p>{}</p>\n</body>\n</html>\n",
+        escape(title),
+        chart,
+        escape(&input.football_field.summary)
+    ))
+}
+
+fn svg(field: &FootballFieldOutput, title: &str, width: u32) -> Result<String> {
+    if field.methodologies.is_empty() {
+        return Err(FinanceError::InvalidInput(
+            "Football field has no methodologies to chart".to_string(),
+        ));
+    }
+    if width < MIN_WIDTH {
+        return Err(FinanceError::OutOfRange(format!(
+            "Chart width must be at least {} pixels",
+            MIN_WIDTH
+        )));
+    }
+
+    let width = Decimal::from(width);
+    let rows = Decimal::from(field.methodologies.len());
+    let axis_y = TOP_MARGIN + rows * ROW_HEIGHT;
+    let height = axis_y + AXIS_HEIGHT;
+    let scale = Scale::new(field, width);
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"12\">\n",
+        w = px(width),
+        h = px(height)
+    ));
+    out.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n",
+        px(width),
+        px(height)
+    ));
+    out.push_str(&format!(
+        "<text x=\"{}\" y=\"28\" text-anchor=\"middle\" font-size=\"16\" font-weight=\"bold\">{}</text>\n",
+        px(width / dec!(2)),
+        escape(title)
+    ));
+
+    // Blended range behind the bars
+    let blended_x = scale.x(field.blended_low);
+    out.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.1\"/>\n",
+        px(blended_x),
+        px(TOP_MARGIN),
+        px((scale.x(field.blended_high) - blended_x).max(Decimal::ONE)),
+        px(rows * ROW_HEIGHT),
+        VALUATION_FILL
+    ));
+
+    // Axis with evenly spaced ticks
+    out.push_str(&format!(
+        "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#000000\"/>\n",
+        px(LABEL_MARGIN),
+        px(width - RIGHT_MARGIN),
+        y = px(axis_y)
+    ));
+    for tick in 0..=AXIS_TICKS {
+        let value = scale.min + (scale.max - scale.min) * Decimal::from(tick) / Decimal::from(AXIS_TICKS);
+        let x = px(scale.x(value));
+        out.push_str(&format!(
+            "<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{}\" stroke=\"#000000\"/>\n",
+            px(axis_y),
+            px(axis_y + dec!(5))
+        ));
+        out.push_str(&format!(
+            "<text x=\"{x}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            px(axis_y + dec!(20)),
+            money(value)
+        ));
+    }
+
+    for (row, methodology) in field.methodologies.iter().enumerate() {
+        let center = TOP_MARGIN + Decimal::from(row) * ROW_HEIGHT + ROW_HEIGHT / dec!(2);
+        let bar_top = center - BAR_HEIGHT / dec!(2);
+        let low_x = scale.x(methodology.low);
+        let high_x = scale.x(methodology.high);
+        let base_x = px(scale.x(methodology.base));
+        let fill = if methodology.reference { REFERENCE_FILL } else { VALUATION_FILL };
+
+        out.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+            px(LABEL_MARGIN - dec!(10)),
+            px(center),
+            escape(&methodology.method)
+        ));
+        out.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            px(low_x),
+            px(bar_top),
+            px((high_x - low_x).max(Decimal::ONE)),
+            px(BAR_HEIGHT),
+            fill
+        ));
+        out.push_str(&format!(
+            "<line x1=\"{base_x}\" y1=\"{}\" x2=\"{base_x}\" y2=\"{}\" stroke=\"#ffffff\" stroke-width=\"2\"/>\n",
+            px(bar_top),
+            px(bar_top + BAR_HEIGHT)
+        ));
+        out.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\" dominant-baseline=\"middle\" font-size=\"10\">{}</text>\n",
+            px(low_x - dec!(4)),
+            px(center),
+            money(methodology.low)
+        ));
+        out.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"start\" dominant-baseline=\"middle\" font-size=\"10\">{}</text>\n",
+            px(high_x + dec!(4)),
+            px(center),
+            money(methodology.high)
+        ));
+    }
+
+    if let Some(price) = field.current_price {
+        let x = px(scale.x(price));
+        out.push_str(&format!(
+            "<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"1.5\" stroke-dasharray=\"4 3\"/>\n",
+            px(TOP_MARGIN - dec!(8)),
+            px(axis_y),
+            PRICE_STROKE
+        ));
+        out.push_str(&format!(
+            "<text x=\"{x}\" y=\"{}\" text-anchor=\"middle\" fill=\"{}\">Current {}</text>\n",
+            px(TOP_MARGIN - dec!(12)),
+            PRICE_STROKE,
+            money(price)
+        ));
+    }
+
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+/// Linear mapping from values to x coordinates, padded 5% either side
+struct Scale {
+    min: Decimal,
+    max: Decimal,
+    left: Decimal,
+    plot_width: Decimal,
+}
+
+impl Scale {
+    fn new(field: &FootballFieldOutput, width: Decimal) -> Self {
+        let values = field
+            .methodologies
+            .iter()
+            .flat_map(|m| [m.low, m.high])
+            .chain(field.current_price);
+        let low = values.clone().min().unwrap_or(Decimal::ZERO);
+        let high = values.max().unwrap_or(Decimal::ZERO);
+        let padding = if high > low {
+            (high - low) * dec!(0.05)
+        } else {
+            Decimal::ONE
+        };
+
+        Scale {
+            min: low - padding,
+            max: high + padding,
+            left: LABEL_MARGIN,
+            plot_width: width - LABEL_MARGIN - RIGHT_MARGIN,
+        }
+    }
+
+    fn x(&self, value: Decimal) -> Decimal {
+        self.left + (value - self.min) / (self.max - self.min) * self.plot_width
+    }
+}
+
+fn px(value: Decimal) -> String {
+    format!("{:.1}", value.round_dp(1))
+}
+
+fn money(value: Decimal) -> String {
+    format!("${:.2}", value.round_dp(2))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valuation::{create_football_field, FootballFieldInput, MethodologyRange};
+    use rust_decimal_macros::dec;
+
+    fn chart_input(title: Option<&str>) -> FootballFieldChartInput {
+        let field = create_football_field(FootballFieldInput {
+            dcf_low: Some(dec!(80)),
+            dcf_high: Some(dec!(120)),
+            comps_low: None,
+            comps_high: None,
+            precedents_low: None,
+            precedents_high: None,
+            current_price: Some(dec!(100)),
+            methodologies: Some(vec![MethodologyRange {
+                name: "Sum-of-the-Parts & <Other>".to_string(),
+                low: dec!(9000),
+                high: dec!(14000),
+                base: Some(dec!(11000)),
+                weight: None,
+            }]),
+            fifty_two_week_low: Some(dec!(70)),
+            fifty_two_week_high: Some(dec!(105)),
+            analyst_targets: None,
+            diluted_shares: Some(dec!(100)),
+        })
+        .unwrap();
+
+        FootballFieldChartInput {
+            football_field: field,
+            title: title.map(str::to_string),
+            width: None,
+        }
+    }
+
+    #[test]
+    fn test_svg_layout() {
+        let svg = render_football_field_svg(chart_input(None)).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800.0\" height=\"220.0\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">Valuation Summary</text>"));
+
+        // Background, blended band and one bar per methodology
+        assert_eq!(svg.matches("<rect").count(), 5);
+        assert_eq!(svg.matches(REFERENCE_FILL).count(), 1);
+
+        // Domain 70-140 padded to 66.5-143.5 over 540px: DCF bar starts at 294.7
+        assert!(svg.contains("<rect x=\"294.7\" y=\"70.0\" width=\"280.5\" height=\"20.0\" fill=\"#1f4e79\"/>"));
+
+        // Current price line at 100
+        assert!(svg.contains("<line x1=\"434.9\" y1=\"52.0\" x2=\"434.9\" y2=\"180.0\" stroke=\"#c00000\""));
+        assert!(svg.contains(">Current $100.00</text>"));
+
+        assert!(svg.contains(">Sum-of-the-Parts &amp; &lt;Other&gt;</text>"));
+    }
+
+    #[test]
+    fn test_rendering_is_deterministic() {
+        let first = render_football_field_svg(chart_input(None)).unwrap();
+        let second = render_football_field_svg(chart_input(None)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_html_wrapper() {
+        let input = chart_input(Some("Project \"Atlas\""));
+        let svg = render_football_field_svg(input.clone()).unwrap();
+        let html = render_football_field_html(input).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Project &quot;Atlas&quot;</title>"));
+        assert!(html.contains(&svg));
+        assert!(html.contains("<p>Valuation range: $80.00 - $140.00"));
+    }
+
+    #[test]
+    fn test_rejects_narrow_or_empty_chart() {
+        let mut input = chart_input(None);
+        input.width = Some(300);
+        assert!(render_football_field_svg(input).is_err());
+
+        let mut input = chart_input(None);
+        input.football_field.methodologies.clear();
+        assert!(render_football_field_svg(input).is_err());
+    }
+}
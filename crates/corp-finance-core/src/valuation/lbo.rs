@@ -0,0 +1,498 @@
+use std::cell::Cell;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use crate::core::{calculate_irr, moic_to_irr, solve_circular};
+use crate::error::{FinanceError, Result};
+use super::types::{DebtTranche, LboExit, LboInput, LboOutput, LboYear, TrancheYear};
+
+/// Build a multi-year LBO model
+///
+/// Entry: enterprise value = EBITDA × entry multiple; the sponsor funds EV, fees and
+/// minimum cash less the debt raised.
+///
+/// Each year projects revenue and EBITDA, deducts D&A, interest and tax, and adds
+/// back non-cash D&A and PIK interest less capex and the NWC build to get free cash
+/// flow. Mandatory amortisation is paid first, then excess cash above the minimum
+/// is swept into the eligible tranches in order of seniority.
+///
+/// Cash interest is charged on the average of opening and closing balances, which
+/// depend on the repayments that interest itself reduces; the circularity is solved
+/// with `core::solve_circular`. PIK accrues on the opening balance.
+///
+/// Sponsor returns are reported for an exit at the end of every projection year.
+///
+/// Simplifications: there is no revolver, so a year whose cash cannot cover the
+/// mandatory repayments and minimum cash is an error. Tax is charged on positive
+/// EBT only; losses are not carried forward. Financing fees are funded at entry
+/// but not amortised through the P&L or for tax.
+pub fn build_lbo_model(input: LboInput) -> Result<LboOutput> {
+    validate(&input)?;
+
+    let enterprise_value = input.entry_ebitda * input.entry_multiple;
+    let total_debt: Decimal = input.tranches.iter().map(|t| t.amount).sum();
+    let financing_fees = total_debt * input.financing_fee_percent.unwrap_or(Decimal::ZERO) / dec!(100);
+    let transaction_fees = input.transaction_fees.unwrap_or(Decimal::ZERO);
+    let minimum_cash = input.minimum_cash.unwrap_or(Decimal::ZERO);
+    let sponsor_equity = enterprise_value + financing_fees + transaction_fees + minimum_cash - total_debt;
+
+    if sponsor_equity <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(format!(
+            "Debt of {} funds the whole purchase; sponsor equity must be positive",
+            total_debt
+        )));
+    }
+
+    let mut balances: Vec<Decimal> = input.tranches.iter().map(|t| t.amount).collect();
+    let mut cash = minimum_cash;
+    let mut revenue = input.entry_revenue;
+    let mut nwc = input.entry_revenue * input.nwc_percent_revenue / dec!(100);
+
+    let mut years = Vec::new();
+    let mut exits = Vec::new();
+
+    for year in 0..input.revenue_growth.len() {
+        revenue *= Decimal::ONE + input.revenue_growth[year] / dec!(100);
+        let ebitda = revenue * input.ebitda_margin[year] / dec!(100);
+        let closing_nwc = revenue * input.nwc_percent_revenue / dec!(100);
+        let operations = Operations {
+            revenue,
+            ebitda,
+            depreciation_amortization: revenue * input.da_percent_revenue / dec!(100),
+            capex: revenue * input.capex_percent_revenue / dec!(100),
+            change_in_nwc: closing_nwc - nwc,
+        };
+
+        let lbo_year = project_year(&input, year as u32 + 1, &operations, &balances, cash)?;
+
+        balances = lbo_year.tranches.iter().map(|t| t.closing_balance).collect();
+        cash = lbo_year.ending_cash;
+        nwc = closing_nwc;
+
+        exits.push(exit_returns(&input, &lbo_year, sponsor_equity)?);
+        years.push(lbo_year);
+    }
+
+    Ok(LboOutput {
+        enterprise_value,
+        total_debt,
+        financing_fees,
+        transaction_fees,
+        minimum_cash,
+        sponsor_equity,
+        entry_leverage: total_debt / input.entry_ebitda,
+        years,
+        exits,
+    })
+}
+
+struct Operations {
+    revenue: Decimal,
+    ebitda: Decimal,
+    depreciation_amortization: Decimal,
+    capex: Decimal,
+    change_in_nwc: Decimal,
+}
+
+/// Project one year, solving for net cash interest (cash interest less interest income)
+fn project_year(
+    input: &LboInput,
+    year: u32,
+    operations: &Operations,
+    opening_balances: &[Decimal],
+    opening_cash: Decimal,
+) -> Result<LboYear> {
+    let opening_interest: Decimal = input
+        .tranches
+        .iter()
+        .zip(opening_balances)
+        .map(|(tranche, balance)| balance * tranche.cash_rate / dec!(100))
+        .sum();
+
+    let iterations = Cell::new(0u32);
+    let net_interest = solve_circular(
+        opening_interest,
+        |guess| {
+            iterations.set(iterations.get() + 1);
+            let projection = debt_schedule(input, year, operations, opening_balances, opening_cash, guess);
+            projection.cash_interest - projection.interest_income
+        },
+        dec!(0.000001),
+        100,
+    )?;
+
+    let mut lbo_year = debt_schedule(input, year, operations, opening_balances, opening_cash, net_interest);
+    lbo_year.interest_iterations = iterations.get();
+
+    // There is no revolver to fund a gap: ending cash must stay at the minimum every year,
+    // including bullet-only years with negative free cash flow
+    let minimum_cash = input.minimum_cash.unwrap_or(Decimal::ZERO);
+    if lbo_year.ending_cash < minimum_cash - dec!(0.000001) {
+        return Err(FinanceError::CalculationError(format!(
+            "Year {}: opening cash of {} and free cash flow of {} leave ending cash of {} after mandatory repayments of {}, below minimum cash of {}",
+            year, opening_cash, lbo_year.free_cash_flow, lbo_year.ending_cash, lbo_year.mandatory_repayment, minimum_cash
+        )));
+    }
+    Ok(lbo_year)
+}
+
+/// Debt schedule given an assumed net cash interest charge; the returned interest is
+/// recomputed from the resulting balances
+fn debt_schedule(
+    input: &LboInput,
+    year: u32,
+    operations: &Operations,
+    opening_balances: &[Decimal],
+    opening_cash: Decimal,
+    net_interest: Decimal,
+) -> LboYear {
+    let average = input.average_balance_interest.unwrap_or(true);
+    let minimum_cash = input.minimum_cash.unwrap_or(Decimal::ZERO);
+    let sweep_percent = input.cash_sweep_percent.unwrap_or(dec!(100)) / dec!(100);
+
+    let pik: Vec<Decimal> = input
+        .tranches
+        .iter()
+        .zip(opening_balances)
+        .map(|(tranche, balance)| balance * tranche.pik_rate.unwrap_or(Decimal::ZERO) / dec!(100))
+        .collect();
+    let pik_interest: Decimal = pik.iter().sum();
+
+    let ebit = operations.ebitda - operations.depreciation_amortization;
+    let ebt = ebit - net_interest - pik_interest;
+    let tax = ebt.max(Decimal::ZERO) * input.tax_rate / dec!(100);
+    let net_income = ebt - tax;
+    let free_cash_flow = net_income + operations.depreciation_amortization + pik_interest
+        - operations.capex
+        - operations.change_in_nwc;
+
+    // Mandatory amortisation on original principal, then the sweep in order of seniority
+    let mut balances: Vec<Decimal> = opening_balances.iter().zip(&pik).map(|(b, p)| b + p).collect();
+    let mut mandatory = vec![Decimal::ZERO; balances.len()];
+    for (i, tranche) in input.tranches.iter().enumerate() {
+        let scheduled = tranche.amount * tranche.amortization_percent.unwrap_or(Decimal::ZERO) / dec!(100);
+        mandatory[i] = scheduled.min(balances[i]);
+        balances[i] -= mandatory[i];
+    }
+    let mandatory_repayment: Decimal = mandatory.iter().sum();
+
+    let mut excess = ((opening_cash + free_cash_flow - mandatory_repayment - minimum_cash) * sweep_percent)
+        .max(Decimal::ZERO);
+    let mut sweep = vec![Decimal::ZERO; balances.len()];
+    for (i, tranche) in input.tranches.iter().enumerate() {
+        if tranche.cash_sweep.unwrap_or(true) {
+            sweep[i] = excess.min(balances[i]);
+            balances[i] -= sweep[i];
+            excess -= sweep[i];
+        }
+    }
+    let sweep_repayment: Decimal = sweep.iter().sum();
+    let ending_cash = opening_cash + free_cash_flow - mandatory_repayment - sweep_repayment;
+
+    let balance_for_interest = |opening: Decimal, closing: Decimal| {
+        if average { (opening + closing) / dec!(2) } else { opening }
+    };
+
+    let mut tranches = Vec::new();
+    let mut cash_interest = Decimal::ZERO;
+    for (i, tranche) in input.tranches.iter().enumerate() {
+        let interest = balance_for_interest(opening_balances[i], balances[i]) * tranche.cash_rate / dec!(100);
+        cash_interest += interest;
+        tranches.push(TrancheYear {
+            name: tranche.name.clone(),
+            opening_balance: opening_balances[i],
+            cash_interest: interest,
+            pik_interest: pik[i],
+            mandatory_repayment: mandatory[i],
+            sweep_repayment: sweep[i],
+            closing_balance: balances[i],
+        });
+    }
+    let interest_income = balance_for_interest(opening_cash, ending_cash).max(Decimal::ZERO)
+        * input.interest_income_rate.unwrap_or(Decimal::ZERO)
+        / dec!(100);
+
+    let total_debt: Decimal = balances.iter().sum();
+
+    LboYear {
+        year,
+        revenue: operations.revenue,
+        ebitda: operations.ebitda,
+        depreciation_amortization: operations.depreciation_amortization,
+        ebit,
+        cash_interest,
+        pik_interest,
+        interest_income,
+        ebt,
+        tax,
+        net_income,
+        capex: operations.capex,
+        change_in_nwc: operations.change_in_nwc,
+        free_cash_flow,
+        mandatory_repayment,
+        sweep_repayment,
+        ending_cash,
+        tranches,
+        total_debt,
+        net_debt: total_debt - ending_cash,
+        leverage: if operations.ebitda > Decimal::ZERO {
+            total_debt / operations.ebitda
+        } else {
+            Decimal::ZERO
+        },
+        interest_iterations: 0,
+    }
+}
+
+/// Exit returns for a sale at the end of `lbo_year`; the IRR is solved on the sponsor
+/// flows (equity in at entry, proceeds at exit) and is `None` only when equity is wiped out.
+/// Newton-Raphson is seeded with the MOIC's annualised rate so that low-multiple exits,
+/// whose root sits near -100%, do not overshoot it
+fn exit_returns(input: &LboInput, lbo_year: &LboYear, sponsor_equity: Decimal) -> Result<LboExit> {
+    let enterprise_value = lbo_year.ebitda * input.exit_multiple;
+    let equity_value = (enterprise_value - lbo_year.net_debt).max(Decimal::ZERO);
+    let moic = equity_value / sponsor_equity;
+
+    let irr = if equity_value > Decimal::ZERO {
+        let mut sponsor_flows = vec![Decimal::ZERO; lbo_year.year as usize + 1];
+        sponsor_flows[0] = -sponsor_equity;
+        sponsor_flows[lbo_year.year as usize] = equity_value;
+        let guess = moic_to_irr(moic, Decimal::from(lbo_year.year))?;
+        let irr = calculate_irr(&sponsor_flows, guess).map_err(|error| {
+            FinanceError::CalculationError(format!("Year {} exit IRR: {}", lbo_year.year, error))
+        })?;
+        Some(irr)
+    } else {
+        None
+    };
+
+    Ok(LboExit {
+        year: lbo_year.year,
+        exit_ebitda: lbo_year.ebitda,
+        enterprise_value,
+        net_debt: lbo_year.net_debt,
+        equity_value,
+        moic,
+        irr,
+    })
+}
+
+pub(crate) fn validate(input: &LboInput) -> Result<()> {
+    if input.entry_ebitda <= Decimal::ZERO || input.entry_revenue <= Decimal::ZERO {
+        return Err(FinanceError::InvalidInput(
+            "entry_revenue and entry_ebitda must be positive".to_string(),
+        ));
+    }
+    if input.revenue_growth.is_empty() {
+        return Err(FinanceError::InvalidInput(
+            "At least one projection year is required".to_string(),
+        ));
+    }
+    if input.ebitda_margin.len() != input.revenue_growth.len() {
+        return Err(FinanceError::InvalidInput(format!(
+            "ebitda_margin has {} values but revenue_growth has {}",
+            input.ebitda_margin.len(),
+            input.revenue_growth.len()
+        )));
+    }
+    if let Some(sweep) = input.cash_sweep_percent {
+        if sweep < Decimal::ZERO || sweep > dec!(100) {
+            return Err(FinanceError::OutOfRange(format!(
+                "cash_sweep_percent of {} must be between 0 and 100",
+                sweep
+            )));
+        }
+    }
+    for DebtTranche { name, amount, .. } in &input.tranches {
+        if *amount < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(format!("{} amount cannot be negative", name)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::MathematicalOps;
+    use rust_decimal_macros::dec;
+
+    fn tranche(name: &str, amount: Decimal, cash_rate: Decimal) -> DebtTranche {
+        DebtTranche {
+            name: name.to_string(),
+            amount,
+            cash_rate,
+            pik_rate: None,
+            amortization_percent: None,
+            cash_sweep: None,
+        }
+    }
+
+    fn input() -> LboInput {
+        LboInput {
+            entry_revenue: dec!(1000),
+            entry_ebitda: dec!(200),
+            entry_multiple: dec!(10),
+            tranches: vec![
+                DebtTranche {
+                    amortization_percent: Some(dec!(5)),
+                    ..tranche("Term Loan B", dec!(800), dec!(7))
+                },
+                DebtTranche {
+                    pik_rate: Some(dec!(4)),
+                    cash_sweep: Some(false),
+                    ..tranche("Senior Notes", dec!(400), dec!(9))
+                },
+            ],
+            transaction_fees: Some(dec!(40)),
+            financing_fee_percent: Some(dec!(2)),
+            minimum_cash: Some(dec!(25)),
+            revenue_growth: vec![dec!(8); 5],
+            ebitda_margin: vec![dec!(20); 5],
+            da_percent_revenue: dec!(3),
+            capex_percent_revenue: dec!(3),
+            nwc_percent_revenue: dec!(10),
+            tax_rate: dec!(25),
+            cash_sweep_percent: None,
+            interest_income_rate: None,
+            average_balance_interest: None,
+            exit_multiple: dec!(10),
+        }
+    }
+
+    #[test]
+    fn test_entry_funding() {
+        let result = build_lbo_model(input()).unwrap();
+
+        // EV 2000 + fees 40 + 24 + cash 25 - debt 1200
+        assert_eq!(result.enterprise_value, dec!(2000));
+        assert_eq!(result.financing_fees, dec!(24));
+        assert_eq!(result.sponsor_equity, dec!(889));
+        assert_eq!(result.entry_leverage, dec!(6));
+        assert_eq!(result.years.len(), 5);
+        assert_eq!(result.exits.len(), 5);
+    }
+
+    #[test]
+    fn test_average_balance_interest_converges() {
+        let result = build_lbo_model(input()).unwrap();
+        let year1 = &result.years[0];
+        let term_loan = &year1.tranches[0];
+        let notes = &year1.tranches[1];
+
+        // Interest matches the average balance after repayments
+        let expected = (term_loan.opening_balance + term_loan.closing_balance) / dec!(2) * dec!(0.07);
+        assert!((term_loan.cash_interest - expected).abs() < dec!(0.0001));
+        assert!(year1.interest_iterations > 1);
+
+        // Mandatory 5% of 800, then the sweep takes all excess cash above the minimum
+        assert_eq!(term_loan.mandatory_repayment, dec!(40));
+        assert!(term_loan.sweep_repayment > Decimal::ZERO);
+        assert!((year1.ending_cash - dec!(25)).abs() < dec!(0.0001));
+
+        // PIK accrues on the opening balance; the notes are not swept
+        assert_eq!(notes.pik_interest, dec!(16));
+        assert_eq!(notes.sweep_repayment, Decimal::ZERO);
+        assert_eq!(notes.closing_balance, dec!(416));
+
+        // FCF adds back non-cash PIK
+        let fcf = year1.net_income + year1.depreciation_amortization + year1.pik_interest
+            - year1.capex - year1.change_in_nwc;
+        assert_eq!(year1.free_cash_flow, fcf);
+    }
+
+    #[test]
+    fn test_opening_balance_interest_is_higher() {
+        let opening = build_lbo_model(LboInput {
+            average_balance_interest: Some(false),
+            ..input()
+        })
+        .unwrap();
+        let average = build_lbo_model(input()).unwrap();
+
+        assert_eq!(opening.years[0].tranches[0].cash_interest, dec!(56));
+        assert!(average.years[0].cash_interest < opening.years[0].cash_interest);
+    }
+
+    #[test]
+    fn test_returns_at_each_exit_year() {
+        let result = build_lbo_model(input()).unwrap();
+
+        for exit in &result.exits {
+            let year = &result.years[exit.year as usize - 1];
+            assert_eq!(exit.enterprise_value, year.ebitda * dec!(10));
+            assert_eq!(exit.equity_value, exit.enterprise_value - year.net_debt);
+            assert_eq!(exit.moic, exit.equity_value / result.sponsor_equity);
+        }
+
+        // IRR consistent with MOIC: (1 + IRR)^n = MOIC
+        let exit5 = &result.exits[4];
+        let irr = exit5.irr.unwrap() / dec!(100);
+        let compounded = (Decimal::ONE + irr).powi(5);
+        assert!((compounded - exit5.moic).abs() < dec!(0.001));
+
+        // Deleveraging and growth lift the multiple over time
+        assert!(result.exits[4].moic > result.exits[0].moic);
+    }
+
+    #[test]
+    fn test_mandatory_repayment_shortfall_rejected() {
+        // 30% amortisation on 800 is 240 a year, well above free cash flow
+        let result = build_lbo_model(LboInput {
+            tranches: vec![DebtTranche {
+                amortization_percent: Some(dec!(30)),
+                ..tranche("Term Loan A", dec!(800), dec!(7))
+            }],
+            ..input()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_negative_free_cash_flow_with_bullet_debt_rejected() {
+        // 15% on 1800 is 270 of interest against ~216 of EBITDA; nothing amortises,
+        // but cash would still fall below the minimum with no revolver to fund it
+        let result = build_lbo_model(LboInput {
+            tranches: vec![tranche("Unitranche", dec!(1800), dec!(15))],
+            ..input()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cash_sweep_above_100_percent_rejected() {
+        let result = build_lbo_model(LboInput {
+            cash_sweep_percent: Some(dec!(300)),
+            ..input()
+        });
+        assert!(matches!(result, Err(FinanceError::OutOfRange(_))));
+    }
+
+    #[test]
+    fn test_irr_solved_on_sponsor_flows() {
+        let result = build_lbo_model(input()).unwrap();
+
+        // A 6x exit loses more than half the equity in year 1, a root near -100%
+        let low_exit = build_lbo_model(LboInput {
+            exit_multiple: dec!(6),
+            ..input()
+        })
+        .unwrap();
+        assert!(low_exit.exits[0].moic < dec!(0.5));
+
+        for exit in result.exits.iter().chain(&low_exit.exits) {
+            let irr = exit.irr.unwrap() / dec!(100);
+            let discounted = exit.equity_value / (Decimal::ONE + irr).powi(exit.year as i64);
+            assert!((discounted - result.sponsor_equity).abs() < dec!(0.01));
+            assert!(exit.irr.unwrap() > dec!(-100));
+        }
+    }
+
+    #[test]
+    fn test_mismatched_projections_rejected() {
+        let result = build_lbo_model(LboInput {
+            ebitda_margin: vec![dec!(20); 3],
+            ..input()
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use super::lbo::{build_lbo_model, validate};
+use super::types::{
+    LboExit, LboInput, LboSensitivityInput, LboSensitivityOutput, LboSolveVariable, LboTargetInput,
+    LboTargetOutput, ReturnMetric, SensitivityGrid,
+};
+
+/// Solve the LBO for the entry multiple, exit multiple or growth that hits a target return
+///
+/// Returns fall as the entry multiple rises and rise with the exit multiple and
+/// growth, so the answer is found by bisection: the maximum entry multiple, or the
+/// minimum exit multiple or growth, at which the target is still met. With a
+/// single entry and exit cash flow an IRR target is equivalent to a MOIC target of
+/// (1 + IRR)^years, which is what is solved.
+pub fn solve_lbo_target(input: LboTargetInput) -> Result<LboTargetOutput> {
+    // The search bounds divide by entry EBITDA, so validate before computing them
+    validate(&input.lbo)?;
+    let exit_year = resolve_exit_year(&input.lbo, input.exit_year)?;
+
+    let target_moic = match input.target_metric {
+        ReturnMetric::Moic => input.target_value,
+        ReturnMetric::Irr => {
+            if input.target_value <= dec!(-100) {
+                return Err(FinanceError::OutOfRange(
+                    "Target IRR must be above -100%".to_string(),
+                ));
+            }
+            (Decimal::ONE + input.target_value / dec!(100))
+                .checked_powi(exit_year as i64)
+                .ok_or_else(|| {
+                    FinanceError::OutOfRange(format!(
+                        "Target IRR of {}% compounded over {} years is too large",
+                        input.target_value, exit_year
+                    ))
+                })?
+        }
+    };
+    if target_moic <= Decimal::ZERO {
+        return Err(FinanceError::OutOfRange(
+            "Target MOIC must be positive".to_string(),
+        ));
+    }
+
+    let (mut low, mut high) = search_bounds(&input.lbo, input.solve_for);
+    let increasing = input.solve_for != LboSolveVariable::MaxEntryMultiple;
+    // A value whose projection runs out of cash (there is no revolver) misses the target
+    let gap = |value: Decimal| -> Result<Decimal> {
+        let lbo = with_variable(&input.lbo, input.solve_for, value);
+        match exit_at(lbo, exit_year) {
+            Ok(exit) => Ok(exit.moic - target_moic),
+            Err(FinanceError::CalculationError(_)) => Ok(-target_moic),
+            Err(error) => Err(error),
+        }
+    };
+
+    let (gap_low, gap_high) = (gap(low)?, gap(high)?);
+    let bracketed = if increasing {
+        gap_low < Decimal::ZERO && gap_high >= Decimal::ZERO
+    } else {
+        gap_low >= Decimal::ZERO && gap_high < Decimal::ZERO
+    };
+    if !bracketed {
+        return Err(FinanceError::OutOfRange(format!(
+            "Target {:?} of {} is not achievable with {:?} between {:.2} and {:.2}",
+            input.target_metric, input.target_value, input.solve_for, low, high
+        )));
+    }
+
+    let mut iterations = 0;
+    while high - low > dec!(0.0001) && iterations < 100 {
+        iterations += 1;
+        let mid = (low + high) / dec!(2);
+        if (gap(mid)? >= Decimal::ZERO) == increasing {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    // Keep the side of the bracket that still meets the target
+    let solved_value = if increasing { high } else { low };
+    let result = build_lbo_model(with_variable(&input.lbo, input.solve_for, solved_value))?;
+    let exit = &result.exits[exit_year as usize - 1];
+
+    Ok(LboTargetOutput {
+        solve_for: input.solve_for,
+        solved_value,
+        exit_year,
+        irr: exit.irr,
+        moic: exit.moic,
+        sponsor_equity: result.sponsor_equity,
+        exit_equity_value: exit.equity_value,
+        iterations,
+    })
+}
+
+/// Two-way sensitivity tables of IRR and MOIC
+///
+/// Entry × exit multiple, and leverage × growth. Leverage is total debt over entry
+/// EBITDA, with each tranche scaled pro rata; growth replaces the revenue growth
+/// in every year.
+pub fn calculate_lbo_sensitivity(input: LboSensitivityInput) -> Result<LboSensitivityOutput> {
+    validate(&input.lbo)?;
+    let exit_year = resolve_exit_year(&input.lbo, input.exit_year)?;
+
+    let entry_exit = match (&input.entry_multiples, &input.exit_multiples) {
+        (Some(entry), Some(exit)) => Some(grid("Entry Multiple", "Exit Multiple", entry, exit, exit_year, |row, column| {
+            LboInput {
+                exit_multiple: column,
+                ..with_variable(&input.lbo, LboSolveVariable::MaxEntryMultiple, row)
+            }
+        })),
+        _ => None,
+    };
+
+    let leverage_growth = match (&input.leverage_multiples, &input.growth_rates) {
+        (Some(leverage), Some(growth)) => {
+            if input.lbo.tranches.iter().all(|t| t.amount.is_zero()) {
+                return Err(FinanceError::InvalidInput(
+                    "Leverage sensitivity needs at least one debt tranche with a non-zero amount".to_string(),
+                ));
+            }
+            Some(grid("Leverage", "Revenue Growth", leverage, growth, exit_year, |row, column| {
+                with_variable(&with_leverage(&input.lbo, row), LboSolveVariable::RequiredGrowth, column)
+            }))
+        }
+        _ => None,
+    };
+
+    Ok(LboSensitivityOutput {
+        exit_year,
+        entry_exit,
+        leverage_growth,
+    })
+}
+
+fn grid<F>(
+    row_label: &str,
+    column_label: &str,
+    rows: &[Decimal],
+    columns: &[Decimal],
+    exit_year: u32,
+    scenario: F,
+) -> SensitivityGrid
+where
+    F: Fn(Decimal, Decimal) -> LboInput,
+{
+    let mut irr = Vec::new();
+    let mut moic = Vec::new();
+    for &row in rows {
+        let cells: Vec<Option<LboExit>> = columns
+            .iter()
+            .map(|&column| exit_at(scenario(row, column), exit_year).ok())
+            .collect();
+        irr.push(cells.iter().map(|exit| exit.as_ref().and_then(|e| e.irr)).collect());
+        moic.push(cells.iter().map(|exit| exit.as_ref().map(|e| e.moic)).collect());
+    }
+
+    SensitivityGrid {
+        row_label: row_label.to_string(),
+        column_label: column_label.to_string(),
+        rows: rows.to_vec(),
+        columns: columns.to_vec(),
+        irr,
+        moic,
+    }
+}
+
+fn resolve_exit_year(lbo: &LboInput, exit_year: Option<u32>) -> Result<u32> {
+    let years = lbo.revenue_growth.len() as u32;
+    let exit_year = exit_year.unwrap_or(years);
+    if exit_year == 0 || exit_year > years {
+        return Err(FinanceError::OutOfRange(format!(
+            "exit_year must be between 1 and {}",
+            years
+        )));
+    }
+    Ok(exit_year)
+}
+
+fn exit_at(lbo: LboInput, exit_year: u32) -> Result<LboExit> {
+    let mut result = build_lbo_model(lbo)?;
+    Ok(result.exits.swap_remove(exit_year as usize - 1))
+}
+
+/// Search range for each variable; the entry multiple starts just above the point
+/// where debt funds the whole purchase
+fn search_bounds(lbo: &LboInput, variable: LboSolveVariable) -> (Decimal, Decimal) {
+    match variable {
+        LboSolveVariable::MaxEntryMultiple => {
+            let total_debt: Decimal = lbo.tranches.iter().map(|t| t.amount).sum();
+            let funded_by_debt = total_debt
+                - total_debt * lbo.financing_fee_percent.unwrap_or(Decimal::ZERO) / dec!(100)
+                - lbo.transaction_fees.unwrap_or(Decimal::ZERO)
+                - lbo.minimum_cash.unwrap_or(Decimal::ZERO);
+            ((funded_by_debt / lbo.entry_ebitda).max(Decimal::ZERO) + dec!(0.01), dec!(50))
+        }
+        LboSolveVariable::MinExitMultiple => (Decimal::ZERO, dec!(50)),
+        LboSolveVariable::RequiredGrowth => (dec!(-50), dec!(100)),
+    }
+}
+
+fn with_variable(lbo: &LboInput, variable: LboSolveVariable, value: Decimal) -> LboInput {
+    let mut lbo = lbo.clone();
+    match variable {
+        LboSolveVariable::MaxEntryMultiple => lbo.entry_multiple = value,
+        LboSolveVariable::MinExitMultiple => lbo.exit_multiple = value,
+        LboSolveVariable::RequiredGrowth => lbo.revenue_growth = vec![value; lbo.revenue_growth.len()],
+    }
+    lbo
+}
+
+fn with_leverage(lbo: &LboInput, leverage: Decimal) -> LboInput {
+    let mut lbo = lbo.clone();
+    let total_debt: Decimal = lbo.tranches.iter().map(|t| t.amount).sum();
+    let scale = leverage * lbo.entry_ebitda / total_debt;
+    for tranche in &mut lbo.tranches {
+        tranche.amount *= scale;
+    }
+    lbo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use super::super::types::DebtTranche;
+
+    fn lbo() -> LboInput {
+        LboInput {
+            entry_revenue: dec!(1000),
+            entry_ebitda: dec!(200),
+            entry_multiple: dec!(10),
+            tranches: vec![DebtTranche {
+                name: "Term Loan".to_string(),
+                amount: dec!(1000),
+                cash_rate: dec!(7),
+                pik_rate: None,
+                amortization_percent: None,
+                cash_sweep: None,
+            }],
+            transaction_fees: None,
+            financing_fee_percent: None,
+            minimum_cash: None,
+            revenue_growth: vec![dec!(5); 5],
+            ebitda_margin: vec![dec!(20); 5],
+            da_percent_revenue: dec!(3),
+            capex_percent_revenue: dec!(3),
+            nwc_percent_revenue: dec!(10),
+            tax_rate: dec!(25),
+            cash_sweep_percent: None,
+            interest_income_rate: None,
+            average_balance_interest: None,
+            exit_multiple: dec!(10),
+        }
+    }
+
+    fn target(solve_for: LboSolveVariable, metric: ReturnMetric, value: Decimal) -> LboTargetInput {
+        LboTargetInput {
+            lbo: lbo(),
+            solve_for,
+            target_metric: metric,
+            target_value: value,
+            exit_year: None,
+        }
+    }
+
+    #[test]
+    fn test_max_entry_multiple_for_target_irr() {
+        let result = solve_lbo_target(target(LboSolveVariable::MaxEntryMultiple, ReturnMetric::Irr, dec!(20))).unwrap();
+
+        // The solved multiple just meets the target; a turn more misses it
+        assert!(result.irr.unwrap() >= dec!(19.99));
+        assert!((result.irr.unwrap() - dec!(20)).abs() < dec!(0.05));
+
+        let higher = build_lbo_model(LboInput {
+            entry_multiple: result.solved_value + dec!(1),
+            ..lbo()
+        })
+        .unwrap();
+        assert!(higher.exits[4].irr.unwrap() < dec!(20));
+    }
+
+    #[test]
+    fn test_min_exit_multiple_and_growth_for_target_moic() {
+        let exit = solve_lbo_target(target(LboSolveVariable::MinExitMultiple, ReturnMetric::Moic, dec!(2.5))).unwrap();
+        assert!(exit.moic >= dec!(2.5));
+        assert!(exit.moic - dec!(2.5) < dec!(0.01));
+
+        let growth = solve_lbo_target(target(LboSolveVariable::RequiredGrowth, ReturnMetric::Moic, dec!(2.5))).unwrap();
+        assert!(growth.moic >= dec!(2.5));
+        assert!(growth.solved_value > dec!(5));
+    }
+
+    #[test]
+    fn test_unachievable_target_rejected() {
+        let result = solve_lbo_target(target(LboSolveVariable::RequiredGrowth, ReturnMetric::Irr, dec!(500)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_lbo_and_overflowing_target_rejected() {
+        let mut zero_ebitda = target(LboSolveVariable::MaxEntryMultiple, ReturnMetric::Moic, dec!(2));
+        zero_ebitda.lbo.entry_ebitda = Decimal::ZERO;
+        assert!(solve_lbo_target(zero_ebitda).is_err());
+
+        // (1 + 10^10)^5 is beyond Decimal's range
+        let result = solve_lbo_target(target(LboSolveVariable::MinExitMultiple, ReturnMetric::Irr, dec!(1000000000000)));
+        assert!(matches!(result, Err(FinanceError::OutOfRange(_))));
+    }
+
+    #[test]
+    fn test_sensitivity_grids() {
+        let result = calculate_lbo_sensitivity(LboSensitivityInput {
+            lbo: lbo(),
+            exit_year: Some(5),
+            entry_multiples: Some(vec![dec!(9), dec!(10), dec!(11)]),
+            exit_multiples: Some(vec![dec!(9), dec!(10), dec!(11)]),
+            leverage_multiples: Some(vec![dec!(4), dec!(5), dec!(6)]),
+            growth_rates: Some(vec![dec!(0), dec!(5), dec!(10)]),
+        })
+        .unwrap();
+
+        let entry_exit = result.entry_exit.unwrap();
+        assert_eq!(entry_exit.irr.len(), 3);
+        assert_eq!(entry_exit.irr[0].len(), 3);
+
+        // Centre cell is the base case
+        let base = build_lbo_model(lbo()).unwrap();
+        assert_eq!(entry_exit.moic[1][1], Some(base.exits[4].moic));
+
+        // Cheaper entry and richer exit both help
+        assert!(entry_exit.irr[0][1] > entry_exit.irr[2][1]);
+        assert!(entry_exit.irr[1][2] > entry_exit.irr[1][0]);
+
+        let leverage_growth = result.leverage_growth.unwrap();
+        assert_eq!(leverage_growth.moic[1][1], Some(base.exits[4].moic));
+        assert!(leverage_growth.irr[2][1] > leverage_growth.irr[0][1]);
+        assert!(leverage_growth.irr[1][2] > leverage_growth.irr[1][0]);
+    }
+}
@@ -1,7 +1,15 @@
 pub mod types;
 pub mod football_field;
+pub mod football_field_chart;
 pub mod paper_lbo;
+pub mod lbo;
+pub mod lbo_sensitivity;
+pub mod option_pricing;
 
 pub use types::*;
 pub use football_field::create_football_field;
+pub use football_field_chart::{render_football_field_html, render_football_field_svg};
 pub use paper_lbo::calculate_paper_lbo;
+pub use lbo::build_lbo_model;
+pub use lbo_sensitivity::{calculate_lbo_sensitivity, solve_lbo_target};
+pub use option_pricing::price_option;
@@ -0,0 +1,260 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use crate::error::{FinanceError, Result};
+use crate::types::ExerciseStyle;
+use super::types::{OptionPricingInput, OptionPricingOutput, OptionType};
+
+/// Price a call or put option
+///
+/// European options use Black-Scholes-Merton with a continuous dividend yield.
+/// American options (e.g. employee options exercised early) use a
+/// Cox-Ross-Rubinstein binomial tree, checking early exercise at every node.
+pub fn price_option(input: OptionPricingInput) -> Result<OptionPricingOutput> {
+    let params = Params::from_input(&input)?;
+    let european = black_scholes_merton(input.option_type, &params)?;
+
+    let style = input.exercise_style.unwrap_or(ExerciseStyle::European);
+    let (option_value, model) = match style {
+        ExerciseStyle::European => (european.value, "Black-Scholes-Merton"),
+        ExerciseStyle::American => {
+            let steps = input.binomial_steps.unwrap_or(100);
+            (binomial_american(input.option_type, &params, steps)?, "Binomial (Cox-Ross-Rubinstein)")
+        }
+    };
+
+    let intrinsic_value = intrinsic(input.option_type, params.spot, params.strike);
+
+    Ok(OptionPricingOutput {
+        option_value,
+        model: model.to_string(),
+        intrinsic_value,
+        time_value: option_value - intrinsic_value,
+        european_value: european.value,
+        early_exercise_premium: option_value - european.value,
+        d1: european.d1,
+        d2: european.d2,
+        delta: european.delta,
+    })
+}
+
+/// Inputs converted to decimals (rates and volatility as fractions)
+struct Params {
+    spot: Decimal,
+    strike: Decimal,
+    time: Decimal,
+    rate: Decimal,
+    volatility: Decimal,
+    dividend_yield: Decimal,
+}
+
+impl Params {
+    fn from_input(input: &OptionPricingInput) -> Result<Self> {
+        if input.spot_price <= Decimal::ZERO || input.strike_price <= Decimal::ZERO {
+            return Err(FinanceError::InvalidInput(
+                "Spot and strike prices must be positive".to_string(),
+            ));
+        }
+        if input.time_to_expiry <= Decimal::ZERO {
+            return Err(FinanceError::InvalidInput(
+                "time_to_expiry must be positive".to_string(),
+            ));
+        }
+        if input.volatility <= Decimal::ZERO {
+            return Err(FinanceError::InvalidInput(
+                "volatility must be positive".to_string(),
+            ));
+        }
+
+        Ok(Params {
+            spot: input.spot_price,
+            strike: input.strike_price,
+            time: input.time_to_expiry,
+            rate: input.risk_free_rate / dec!(100),
+            volatility: input.volatility / dec!(100),
+            dividend_yield: input.dividend_yield.unwrap_or(Decimal::ZERO) / dec!(100),
+        })
+    }
+}
+
+struct BlackScholes {
+    value: Decimal,
+    d1: Decimal,
+    d2: Decimal,
+    delta: Decimal,
+}
+
+fn black_scholes_merton(option_type: OptionType, p: &Params) -> Result<BlackScholes> {
+    let sqrt_time = p.time.sqrt().ok_or_else(|| {
+        FinanceError::CalculationError("Square root of time_to_expiry failed".to_string())
+    })?;
+    let vol_sqrt_time = p.volatility * sqrt_time;
+
+    let d1 = ((p.spot / p.strike).ln()
+        + (p.rate - p.dividend_yield + p.volatility * p.volatility / dec!(2)) * p.time)
+        / vol_sqrt_time;
+    let d2 = d1 - vol_sqrt_time;
+
+    let dividend_discount = discount(p.dividend_yield * p.time)?;
+    let rate_discount = discount(p.rate * p.time)?;
+
+    let (value, delta) = match option_type {
+        OptionType::Call => (
+            p.spot * dividend_discount * d1.norm_cdf() - p.strike * rate_discount * d2.norm_cdf(),
+            dividend_discount * d1.norm_cdf(),
+        ),
+        OptionType::Put => (
+            p.strike * rate_discount * (-d2).norm_cdf() - p.spot * dividend_discount * (-d1).norm_cdf(),
+            dividend_discount * (d1.norm_cdf() - Decimal::ONE),
+        ),
+    };
+
+    Ok(BlackScholes {
+        value: value.max(Decimal::ZERO),
+        d1,
+        d2,
+        delta,
+    })
+}
+
+fn binomial_american(option_type: OptionType, p: &Params, steps: u32) -> Result<Decimal> {
+    if steps == 0 || steps > 1000 {
+        return Err(FinanceError::OutOfRange(
+            "binomial_steps must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let dt = p.time / Decimal::from(steps);
+    let sqrt_dt = dt.sqrt().ok_or_else(|| {
+        FinanceError::CalculationError("Square root of time step failed".to_string())
+    })?;
+    let up = (p.volatility * sqrt_dt).checked_exp().ok_or_else(|| {
+        FinanceError::CalculationError("Up factor overflowed".to_string())
+    })?;
+    let down = Decimal::ONE / up;
+    let growth = Decimal::ONE / discount((p.rate - p.dividend_yield) * dt)?;
+    let step_discount = discount(p.rate * dt)?;
+
+    let up_probability = (growth - down) / (up - down);
+    if up_probability <= Decimal::ZERO || up_probability >= Decimal::ONE {
+        return Err(FinanceError::CalculationError(
+            "Binomial tree has no valid risk-neutral probability; increase binomial_steps".to_string(),
+        ));
+    }
+    let down_probability = Decimal::ONE - up_probability;
+
+    // High volatility over a long tenor can push the top nodes past Decimal's range
+    let node_price = |step: u32, ups: u32| -> Result<Decimal> {
+        up.checked_powi(2 * ups as i64 - step as i64)
+            .and_then(|factor| p.spot.checked_mul(factor))
+            .ok_or_else(|| {
+                FinanceError::CalculationError(
+                    "Binomial tree node price overflowed; reduce binomial_steps, volatility or time_to_expiry".to_string(),
+                )
+            })
+    };
+
+    let mut values: Vec<Decimal> = (0..=steps)
+        .map(|ups| Ok(intrinsic(option_type, node_price(steps, ups)?, p.strike)))
+        .collect::<Result<_>>()?;
+
+    for step in (0..steps).rev() {
+        for ups in 0..=step {
+            let i = ups as usize;
+            let continuation = step_discount * (up_probability * values[i + 1] + down_probability * values[i]);
+            let exercise = intrinsic(option_type, node_price(step, ups)?, p.strike);
+            values[i] = continuation.max(exercise);
+        }
+    }
+
+    Ok(values[0])
+}
+
+fn intrinsic(option_type: OptionType, spot: Decimal, strike: Decimal) -> Decimal {
+    match option_type {
+        OptionType::Call => (spot - strike).max(Decimal::ZERO),
+        OptionType::Put => (strike - spot).max(Decimal::ZERO),
+    }
+}
+
+/// e^(-x)
+fn discount(x: Decimal) -> Result<Decimal> {
+    (-x).checked_exp().ok_or_else(|| {
+        FinanceError::CalculationError("Discount factor overflowed".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn input(option_type: OptionType, style: ExerciseStyle) -> OptionPricingInput {
+        OptionPricingInput {
+            option_type,
+            spot_price: dec!(100),
+            strike_price: dec!(100),
+            time_to_expiry: dec!(1),
+            risk_free_rate: dec!(5),
+            volatility: dec!(20),
+            dividend_yield: None,
+            exercise_style: Some(style),
+            binomial_steps: Some(200),
+        }
+    }
+
+    #[test]
+    fn test_black_scholes_at_the_money() {
+        let call = price_option(input(OptionType::Call, ExerciseStyle::European)).unwrap();
+        let put = price_option(input(OptionType::Put, ExerciseStyle::European)).unwrap();
+
+        // Textbook values: call 10.4506, put 5.5735
+        assert!((call.option_value - dec!(10.4506)).abs() < dec!(0.001));
+        assert!((put.option_value - dec!(5.5735)).abs() < dec!(0.001));
+
+        // Put-call parity: C − P = S − K e^(−rT)
+        let parity = dec!(100) - dec!(100) * dec!(-0.05).exp();
+        assert!((call.option_value - put.option_value - parity).abs() < dec!(0.001));
+    }
+
+    #[test]
+    fn test_dividend_yield_lowers_call_value() {
+        let mut with_dividend = input(OptionType::Call, ExerciseStyle::European);
+        with_dividend.dividend_yield = Some(dec!(3));
+
+        let base = price_option(input(OptionType::Call, ExerciseStyle::European)).unwrap();
+        let result = price_option(with_dividend).unwrap();
+        assert!(result.option_value < base.option_value);
+    }
+
+    #[test]
+    fn test_binomial_american() {
+        // American call on a non-dividend stock is worth the same as the European
+        let call = price_option(input(OptionType::Call, ExerciseStyle::American)).unwrap();
+        assert!((call.option_value - call.european_value).abs() < dec!(0.05));
+
+        // American put carries an early exercise premium
+        let put = price_option(input(OptionType::Put, ExerciseStyle::American)).unwrap();
+        assert!(put.early_exercise_premium > dec!(0.2));
+        assert!((put.option_value - dec!(6.09)).abs() < dec!(0.05));
+    }
+
+    #[test]
+    fn test_invalid_inputs_rejected() {
+        let mut bad = input(OptionType::Call, ExerciseStyle::European);
+        bad.volatility = dec!(0);
+        assert!(price_option(bad).is_err());
+    }
+
+    #[test]
+    fn test_binomial_overflow_is_an_error() {
+        // e^(0.8 × √0.01 × 1000) = e^80 at the top node is beyond Decimal's range
+        let extreme = OptionPricingInput {
+            spot_price: dec!(1000),
+            time_to_expiry: dec!(10),
+            volatility: dec!(80),
+            binomial_steps: Some(1000),
+            ..input(OptionType::Put, ExerciseStyle::American)
+        };
+        assert!(matches!(price_option(extreme), Err(FinanceError::CalculationError(_))));
+    }
+}
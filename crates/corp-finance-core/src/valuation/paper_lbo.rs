@@ -1,7 +1,9 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use crate::error::Result;
-use super::types::{PaperLboInput, PaperLboOutput};
+use chrono::{Months, NaiveDate};
+use crate::core::{calculate_xirr, moic_to_irr, moic_to_irr_approx};
+use crate::error::{FinanceError, Result};
+use super::types::{DatedCashFlow, InterimCashFlow, InterimFlowType, PaperLboInput, PaperLboOutput};
 
 /// Calculate quick Paper LBO (mental math approach)
 /// Used in interviews and quick analysis without Excel
@@ -13,7 +15,16 @@ use super::types::{PaperLboInput, PaperLboOutput};
 /// 4. Exit Value: Exit EBITDA × Exit Multiple
 /// 5. Debt Paydown: Use simplified approach (% of EBITDA)
 /// 6. Exit Equity: Exit Value - Remaining Debt
-/// 7. IRR: Using approximation formulas
+/// 7. IRR: Exact MoM^(1/n) - 1, with the mental math estimate and its error
+///
+/// Interim cash flows (dividend recaps, add-on equity injections, monitoring fees)
+/// need an `entry_date`; the sponsor's dated cash flows are then returned and IRR
+/// is computed with `core::calculate_xirr`, seeded from the money multiple over the
+/// capital-weighted holding time. If XIRR finds no root the call fails rather than
+/// reporting a different metric as the IRR.
+///
+/// Exit equity is floored at zero: a recap larger than the exit value leaves the
+/// sponsor with nothing at exit, not a further payment.
 pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
     let mut mental_math_steps = Vec::new();
     let mut key_assumptions = Vec::new();
@@ -56,7 +67,7 @@ pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
     for _ in 0..input.hold_period_years {
         growth_multiplier *= Decimal::ONE + growth_rate_decimal;
     }
-    let exit_ebitda = entry_ebitda * growth_multiplier;
+    let organic_exit_ebitda = entry_ebitda * growth_multiplier;
 
     if input.ebitda_growth_rate != Decimal::ZERO {
         mental_math_steps.push(format!(
@@ -64,7 +75,7 @@ pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
             entry_ebitda,
             input.ebitda_growth_rate,
             input.hold_period_years,
-            exit_ebitda
+            organic_exit_ebitda
         ));
         key_assumptions.push(format!(
             "{}% annual EBITDA growth",
@@ -73,7 +84,39 @@ pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
     } else {
         mental_math_steps.push(format!(
             "EBITDA flat at ${} (no growth assumed)",
-            exit_ebitda
+            organic_exit_ebitda
+        ));
+    }
+
+    // Interim cash flows: recaps add debt, add-ons add equity and EBITDA, fees leak cash
+    let interim_flows = input.interim_cash_flows.clone().unwrap_or_default();
+    let exit_date = match input.entry_date {
+        Some(entry_date) => Some(
+            entry_date
+                .checked_add_months(Months::new(12 * input.hold_period_years))
+                .ok_or_else(|| FinanceError::OutOfRange("Exit date out of range".to_string()))?,
+        ),
+        None => None,
+    };
+    validate_interim_flows(&interim_flows, input.entry_date, exit_date)?;
+
+    let total_of = |flow_type: InterimFlowType| -> Decimal {
+        interim_flows
+            .iter()
+            .filter(|flow| flow.flow_type == flow_type)
+            .map(|flow| flow.amount)
+            .sum()
+    };
+    let interim_distributions = total_of(InterimFlowType::DividendRecap);
+    let additional_equity_invested = total_of(InterimFlowType::EquityInjection);
+    let monitoring_fees = total_of(InterimFlowType::MonitoringFee);
+    let ebitda_acquired: Decimal = interim_flows.iter().filter_map(|flow| flow.ebitda_acquired).sum();
+    let exit_ebitda = organic_exit_ebitda + ebitda_acquired;
+
+    if !ebitda_acquired.is_zero() {
+        mental_math_steps.push(format!(
+            "Add-ons: ${} equity injected for ${} of acquired EBITDA, exit EBITDA ${}",
+            additional_equity_invested, ebitda_acquired, exit_ebitda
         ));
     }
 
@@ -95,74 +138,144 @@ pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
     // Assume debt is paid down using free cash flow
     // Simplified: 50% of cumulative EBITDA goes to debt paydown
     let cumulative_ebitda = if input.ebitda_growth_rate == Decimal::ZERO {
-        exit_ebitda * Decimal::from(input.hold_period_years)
+        organic_exit_ebitda * Decimal::from(input.hold_period_years)
     } else {
         // Simplified: average EBITDA × years
-        let avg_ebitda = (entry_ebitda + exit_ebitda) / dec!(2);
+        let avg_ebitda = (entry_ebitda + organic_exit_ebitda) / dec!(2);
         avg_ebitda * Decimal::from(input.hold_period_years)
     };
 
     let debt_paydown = (cumulative_ebitda * dec!(0.5)).min(debt_amount);
-    let remaining_debt = debt_amount - debt_paydown;
+    let remaining_debt = debt_amount - debt_paydown + interim_distributions + monitoring_fees;
 
     mental_math_steps.push(format!(
         "Debt paydown: ~50% of cumulative EBITDA = ${} paid down",
         debt_paydown
     ));
 
+    if !interim_distributions.is_zero() {
+        mental_math_steps.push(format!(
+            "Dividend recaps: ${} distributed, funded by new debt",
+            interim_distributions
+        ));
+    }
+    if !monitoring_fees.is_zero() {
+        mental_math_steps.push(format!(
+            "Monitoring fees: ${} paid from cash that would have repaid debt",
+            monitoring_fees
+        ));
+    }
+
     key_assumptions.push(format!(
         "{}% interest rate on debt",
         input.interest_rate
     ));
 
-    // Exit equity value
-    let exit_equity_value = exit_valuation - remaining_debt;
+    // Exit equity value; limited liability floors it at zero
+    let exit_equity_value = (exit_valuation - remaining_debt).max(Decimal::ZERO);
 
     mental_math_steps.push(format!(
         "Exit equity: ${} valuation - ${} remaining debt = ${}",
         exit_valuation, remaining_debt, exit_equity_value
     ));
+    if remaining_debt > exit_valuation {
+        mental_math_steps.push(
+            "Remaining debt exceeds the exit value; equity is wiped out, not called for more cash".to_string(),
+        );
+    }
 
-    // Money multiple
-    let money_multiple = if equity_invested > Decimal::ZERO {
-        exit_equity_value / equity_invested
+    // Money multiple on all equity invested, counting cash returned before exit
+    let total_invested = equity_invested + additional_equity_invested;
+    let total_returned = exit_equity_value + interim_distributions;
+    let money_multiple = if total_invested > Decimal::ZERO {
+        total_returned / total_invested
     } else {
         Decimal::ZERO
     };
 
     mental_math_steps.push(format!(
         "Money multiple: ${} / ${} = {:.2}x",
-        exit_equity_value, equity_invested, money_multiple
+        total_returned, total_invested, money_multiple
     ));
 
-    // IRR calculation using approximation
-    // IRR ≈ (MoM^(1/years) - 1) × 100
-    // For mental math, use rule of 72 approximation
-    let irr_percent = if input.hold_period_years > 0 && money_multiple > Decimal::ZERO {
-        // More accurate: (MoM^(1/n) - 1) × 100
-        // Approximation for mental math:
+    // Sponsor cash flows by date; monitoring fees go to the manager, not the fund
+    let cash_flows = match (input.entry_date, exit_date) {
+        (Some(entry_date), Some(exit_date)) => {
+            let mut flows = vec![DatedCashFlow {
+                date: entry_date,
+                description: "Equity Investment".to_string(),
+                amount: -equity_invested,
+            }];
+            for flow in &interim_flows {
+                let (description, amount) = match flow.flow_type {
+                    InterimFlowType::DividendRecap => ("Dividend Recap", flow.amount),
+                    InterimFlowType::EquityInjection => ("Equity Injection", -flow.amount),
+                    InterimFlowType::MonitoringFee => continue,
+                };
+                flows.push(DatedCashFlow {
+                    date: flow.date,
+                    description: description.to_string(),
+                    amount,
+                });
+            }
+            flows.push(DatedCashFlow {
+                date: exit_date,
+                description: "Exit Equity".to_string(),
+                amount: exit_equity_value,
+            });
+            flows.sort_by_key(|flow| flow.date);
+            Some(flows)
+        }
+        _ => None,
+    };
+
+    // IRR: exact MoM^(1/n) - 1, or XIRR of the dated flows, alongside the mental math estimate
+    let (irr_percent, mental_math_irr_percent) = if input.hold_period_years > 0 && money_multiple > Decimal::ZERO {
         let years = Decimal::from(input.hold_period_years);
+        let exact = match &cash_flows {
+            Some(flows) if !interim_flows.is_empty() => {
+                let amounts: Vec<Decimal> = flows.iter().map(|flow| flow.amount).collect();
+                let dates: Vec<NaiveDate> = flows.iter().map(|flow| flow.date).collect();
+                let seed = xirr_seed(flows, money_multiple, years)?;
+                calculate_xirr(&amounts, &dates, seed).map_err(|_| {
+                    FinanceError::CalculationError(
+                        "XIRR did not converge on the sponsor's dated cash flows".to_string(),
+                    )
+                })?
+            }
+            _ => moic_to_irr(money_multiple, years)?,
+        };
 
-        // Simple approximation: (MoM - 1) / years × 100 for rough estimate
-        // Better approximation: Use (MoM^(1/n) - 1) which we can approximate
-        if money_multiple == dec!(2) {
+        let estimate = if money_multiple == dec!(2) {
             // Rule of 72: 72/years ≈ IRR for 2x
             dec!(72) / years
         } else if money_multiple == dec!(3) {
             // Rule of 114 for 3x
             dec!(114) / years
         } else {
-            // General approximation: (MoM - 1) / years × 100
-            // This is simplified and less accurate but easier for mental math
-            ((money_multiple - Decimal::ONE) / years) * dec!(100)
-        }
+            // (MoM - 1) / years ignores compounding and overstates longer holds
+            moic_to_irr_approx(money_multiple, years)?
+        };
+        (exact, estimate)
     } else {
-        Decimal::ZERO
+        (Decimal::ZERO, Decimal::ZERO)
     };
+    let mental_math_irr_error = mental_math_irr_percent - irr_percent;
 
+    if interim_flows.is_empty() {
+        mental_math_steps.push(format!(
+            "IRR: {:.2}x^(1/{}) - 1 = {:.1}% per year",
+            money_multiple, input.hold_period_years, irr_percent
+        ));
+    } else {
+        mental_math_steps.push(format!(
+            "IRR: XIRR of dated cash flows = {:.1}% per year",
+            irr_percent
+        ));
+    }
     mental_math_steps.push(format!(
-        "IRR approximation: {:.1}% per year over {} years",
-        irr_percent, input.hold_period_years
+        "Mental math estimate: {:.1}% ({:+.1}pp vs exact)",
+        mental_math_irr_percent, mental_math_irr_error
     ));
 
     Ok(PaperLboOutput {
@@ -178,13 +291,68 @@ pub fn calculate_paper_lbo(input: PaperLboInput) -> Result<PaperLboOutput> {
         debt_paydown,
         remaining_debt,
         exit_equity_value,
+        additional_equity_invested,
+        interim_distributions,
+        monitoring_fees,
+        cash_flows,
         money_multiple,
         irr_percent,
+        mental_math_irr_percent,
+        mental_math_irr_error,
         key_assumptions,
         mental_math_steps,
     })
 }
 
+/// Starting rate for XIRR: the money multiple compounded over the gap between the
+/// capital-weighted dates of cash returned and cash invested, or the full hold
+/// when that gap is not positive
+fn xirr_seed(flows: &[DatedCashFlow], money_multiple: Decimal, years: Decimal) -> Result<Decimal> {
+    let entry_date = flows[0].date;
+    let weighted_time = |inflows: bool| -> Decimal {
+        let (weighted, total) = flows
+            .iter()
+            .filter(|flow| (flow.amount > Decimal::ZERO) == inflows && !flow.amount.is_zero())
+            .fold((Decimal::ZERO, Decimal::ZERO), |(weighted, total), flow| {
+                let time = Decimal::from((flow.date - entry_date).num_days()) / dec!(365.25);
+                (weighted + flow.amount.abs() * time, total + flow.amount.abs())
+            });
+        if total.is_zero() { Decimal::ZERO } else { weighted / total }
+    };
+    let duration = weighted_time(true) - weighted_time(false);
+    let duration = if duration > Decimal::ZERO { duration } else { years };
+    moic_to_irr(money_multiple, duration)
+}
+
+fn validate_interim_flows(
+    flows: &[InterimCashFlow],
+    entry_date: Option<NaiveDate>,
+    exit_date: Option<NaiveDate>,
+) -> Result<()> {
+    if flows.is_empty() {
+        return Ok(());
+    }
+    let (Some(entry_date), Some(exit_date)) = (entry_date, exit_date) else {
+        return Err(FinanceError::MissingField(
+            "entry_date is required with interim cash flows".to_string(),
+        ));
+    };
+    for flow in flows {
+        if flow.date <= entry_date || flow.date > exit_date {
+            return Err(FinanceError::OutOfRange(format!(
+                "Interim cash flow on {} falls outside the hold period {} to {}",
+                flow.date, entry_date, exit_date
+            )));
+        }
+        if flow.amount < Decimal::ZERO {
+            return Err(FinanceError::NegativeValue(
+                "Interim cash flow amounts must not be negative; the type sets the direction".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +369,8 @@ mod tests {
             hold_period_years: 5,
             exit_multiple: dec!(10),
             interest_rate: dec!(5),
+            entry_date: None,
+            interim_cash_flows: None,
         };
 
         let result = calculate_paper_lbo(input).unwrap();
@@ -233,6 +403,8 @@ mod tests {
             hold_period_years: 5,
             exit_multiple: dec!(10),
             interest_rate: dec!(5),
+            entry_date: None,
+            interim_cash_flows: None,
         };
 
         let result = calculate_paper_lbo(input).unwrap();
@@ -260,6 +432,8 @@ mod tests {
             hold_period_years: 5,
             exit_multiple: dec!(12),  // Multiple expansion
             interest_rate: dec!(5),
+            entry_date: None,
+            interim_cash_flows: None,
         };
 
         let result = calculate_paper_lbo(input).unwrap();
@@ -282,6 +456,8 @@ mod tests {
             hold_period_years: 5,
             exit_multiple: dec!(10),
             interest_rate: dec!(6),
+            entry_date: None,
+            interim_cash_flows: None,
         };
 
         let result = calculate_paper_lbo(input).unwrap();
@@ -294,4 +470,140 @@ mod tests {
         // Higher leverage should lead to higher returns (if exit works out)
         assert!(result.money_multiple > dec!(1.5));
     }
+
+    #[test]
+    fn test_exact_irr_vs_mental_math() {
+        let input = PaperLboInput {
+            purchase_price: dec!(1000),
+            ebitda: dec!(100),
+            entry_multiple: dec!(10),
+            debt_multiple: dec!(6),
+            ebitda_growth_rate: dec!(0),
+            hold_period_years: 5,
+            exit_multiple: dec!(13.5),
+            interest_rate: dec!(6),
+            entry_date: None,
+            interim_cash_flows: None,
+        };
+
+        let result = calculate_paper_lbo(input).unwrap();
+
+        // Exit equity 1350 - 350 remaining debt = 1000 on 400 invested: 2.5x over 5 years
+        assert_eq!(result.money_multiple, dec!(2.5));
+        assert!((result.irr_percent - dec!(20.1124)).abs() < dec!(0.0001));
+        assert_eq!(result.mental_math_irr_percent, dec!(30));
+        assert!((result.mental_math_irr_error - dec!(9.8876)).abs() < dec!(0.0001));
+    }
+
+    #[test]
+    fn test_interim_cash_flows_use_xirr() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let input = PaperLboInput {
+            purchase_price: dec!(1000),
+            ebitda: dec!(100),
+            entry_multiple: dec!(10),
+            debt_multiple: dec!(5),
+            ebitda_growth_rate: dec!(0),
+            hold_period_years: 5,
+            exit_multiple: dec!(10),
+            interest_rate: dec!(5),
+            entry_date: Some(date(2024, 1, 1)),
+            interim_cash_flows: Some(vec![
+                InterimCashFlow {
+                    date: date(2026, 1, 1),
+                    flow_type: InterimFlowType::DividendRecap,
+                    amount: dec!(200),
+                    ebitda_acquired: None,
+                },
+                InterimCashFlow {
+                    date: date(2027, 1, 1),
+                    flow_type: InterimFlowType::EquityInjection,
+                    amount: dec!(80),
+                    ebitda_acquired: Some(dec!(10)),
+                },
+                InterimCashFlow {
+                    date: date(2028, 1, 1),
+                    flow_type: InterimFlowType::MonitoringFee,
+                    amount: dec!(10),
+                    ebitda_acquired: None,
+                },
+            ]),
+        };
+
+        let result = calculate_paper_lbo(input).unwrap();
+
+        // Paydown 250 of 500; recap adds 200 and fees 10 back onto the debt
+        assert_eq!(result.remaining_debt, dec!(460));
+        assert_eq!(result.exit_ebitda, dec!(110));
+        assert_eq!(result.exit_equity_value, dec!(640));
+
+        // (640 + 200) / (500 + 80)
+        assert_eq!(result.money_multiple, dec!(840) / dec!(580));
+
+        // Entry, recap, injection and exit; the monitoring fee is not a sponsor flow
+        let flows = result.cash_flows.unwrap();
+        assert_eq!(flows.len(), 4);
+        assert_eq!(flows[3].date, date(2029, 1, 1));
+        assert_eq!(flows[2].amount, dec!(-80));
+
+        // Early cash back lifts the IRR above the MoM-implied rate
+        let implied = moic_to_irr(result.money_multiple, dec!(5)).unwrap();
+        assert!(result.irr_percent > implied);
+    }
+
+    #[test]
+    fn test_large_early_recap_floors_exit_equity_and_solves_xirr() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let input = PaperLboInput {
+            purchase_price: dec!(1000),
+            ebitda: dec!(100),
+            entry_multiple: dec!(10),
+            debt_multiple: dec!(5),
+            ebitda_growth_rate: dec!(10),
+            hold_period_years: 5,
+            exit_multiple: dec!(10),
+            interest_rate: dec!(5),
+            entry_date: Some(date(2024, 1, 1)),
+            interim_cash_flows: Some(vec![InterimCashFlow {
+                date: date(2024, 7, 1),
+                flow_type: InterimFlowType::DividendRecap,
+                amount: dec!(2000),
+                ebitda_acquired: None,
+            }]),
+        };
+
+        // The recap debt exceeds the exit value, so the sponsor gets nothing at exit
+        let result = calculate_paper_lbo(input).unwrap();
+        assert!(result.remaining_debt > result.exit_valuation);
+        assert_eq!(result.exit_equity_value, Decimal::ZERO);
+        assert_eq!(result.cash_flows.unwrap()[2].amount, Decimal::ZERO);
+        assert_eq!(result.money_multiple, dec!(4));
+
+        // 500 in, 2000 back after 182 days: (1 + r)^(182 / 365.25) = 4
+        let expected = moic_to_irr(dec!(4), dec!(182) / dec!(365.25)).unwrap();
+        assert!((result.irr_percent - expected).abs() < dec!(0.01));
+        assert!(result.irr_percent > dec!(1500));
+    }
+
+    #[test]
+    fn test_interim_flows_need_entry_date() {
+        let input = PaperLboInput {
+            purchase_price: dec!(1000),
+            ebitda: dec!(100),
+            entry_multiple: dec!(10),
+            debt_multiple: dec!(5),
+            ebitda_growth_rate: dec!(0),
+            hold_period_years: 5,
+            exit_multiple: dec!(10),
+            interest_rate: dec!(5),
+            entry_date: None,
+            interim_cash_flows: Some(vec![InterimCashFlow {
+                date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                flow_type: InterimFlowType::DividendRecap,
+                amount: dec!(200),
+                ebitda_acquired: None,
+            }]),
+        };
+        assert!(calculate_paper_lbo(input).is_err());
+    }
 }
@@ -1,27 +1,51 @@
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::types::ExerciseStyle;
 
 /// Football Field Valuation Summary Input
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FootballFieldInput {
-    pub dcf_low: Decimal,
-    pub dcf_high: Decimal,
-    pub comps_low: Decimal,
-    pub comps_high: Decimal,
-    pub precedents_low: Decimal,
-    pub precedents_high: Decimal,
+    // Legacy fixed methodologies; each pair is optional and shown before `methodologies`
+    pub dcf_low: Option<Decimal>,
+    pub dcf_high: Option<Decimal>,
+    pub comps_low: Option<Decimal>,
+    pub comps_high: Option<Decimal>,
+    pub precedents_low: Option<Decimal>,
+    pub precedents_high: Option<Decimal>,
     pub current_price: Option<Decimal>,
+
+    pub methodologies: Option<Vec<MethodologyRange>>,
+    pub fifty_two_week_low: Option<Decimal>,  // reference bar, per share
+    pub fifty_two_week_high: Option<Decimal>,
+    pub analyst_targets: Option<Vec<Decimal>>,  // reference bar, per share
+    pub diluted_shares: Option<Decimal>,  // converts `methodologies` equity values to per share; legacy pairs are already per share
+}
+
+/// Valuation range for one methodology
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodologyRange {
+    pub name: String,
+    pub low: Decimal,
+    pub high: Decimal,
+    pub base: Option<Decimal>,  // point estimate, defaults to the midpoint
+    pub weight: Option<Decimal>,  // relative weight in the blended range; give for every methodology or none (equal weights)
 }
 
 /// Football Field Valuation Summary Output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FootballFieldOutput {
     pub methodologies: Vec<ValuationMethodology>,
-    pub overall_low: Decimal,
+    pub overall_low: Decimal,  // across valuation methodologies, excluding reference bars
     pub overall_high: Decimal,
     pub overall_midpoint: Decimal,
+    pub blended_low: Decimal,
+    pub blended_high: Decimal,
+    pub blended_base: Decimal,
+    pub per_share: bool,  // every range is per share: legacy pairs always, `methodologies` once converted with diluted_shares
     pub current_price: Option<Decimal>,
     pub implied_upside_downside: Option<Decimal>,
+    pub blended_upside_downside: Option<Decimal>,
     pub summary: String,
 }
 
@@ -32,6 +56,17 @@ pub struct ValuationMethodology {
     pub high: Decimal,
     pub midpoint: Decimal,
     pub range_width: Decimal,
+    pub base: Decimal,
+    pub weight: Option<Decimal>,  // normalised %, None for reference bars
+    pub reference: bool,  // 52-week range or analyst targets, not part of the valuation
+}
+
+/// Football field chart rendering options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootballFieldChartInput {
+    pub football_field: FootballFieldOutput,
+    pub title: Option<String>,  // defaults to "Valuation Summary"
+    pub width: Option<u32>,  // pixels, defaults to 800
 }
 
 /// Paper LBO (Mental Math LBO) Input
@@ -45,6 +80,32 @@ pub struct PaperLboInput {
     pub hold_period_years: u32,
     pub exit_multiple: Decimal,
     pub interest_rate: Decimal,  // % per annum
+    pub entry_date: Option<NaiveDate>,  // required with interim cash flows; exit is hold_period_years later
+    pub interim_cash_flows: Option<Vec<InterimCashFlow>>,
+}
+
+/// Cash flow between entry and exit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterimCashFlow {
+    pub date: NaiveDate,
+    pub flow_type: InterimFlowType,
+    pub amount: Decimal,
+    pub ebitda_acquired: Option<Decimal>,  // add-on acquisitions: EBITDA added at exit
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterimFlowType {
+    DividendRecap,  // distribution to the sponsor funded by new debt
+    EquityInjection,  // sponsor funds an add-on acquisition
+    MonitoringFee,  // paid from company cash, reducing debt paydown
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatedCashFlow {
+    pub date: NaiveDate,
+    pub description: String,
+    pub amount: Decimal,  // to the sponsor: negative invested, positive returned
 }
 
 /// Paper LBO Output
@@ -65,9 +126,217 @@ pub struct PaperLboOutput {
     pub remaining_debt: Decimal,
     pub exit_equity_value: Decimal,
 
-    pub money_multiple: Decimal,  // MoM (exit equity / entry equity)
-    pub irr_percent: Decimal,
+    pub additional_equity_invested: Decimal,
+    pub interim_distributions: Decimal,
+    pub monitoring_fees: Decimal,
+    pub cash_flows: Option<Vec<DatedCashFlow>>,  // when entry_date is given
+
+    pub money_multiple: Decimal,  // MoM (exit equity + distributions) / (equity invested)
+    pub irr_percent: Decimal,  // exact: MoM^(1/years) - 1, or XIRR of the dated cash flows
+    pub mental_math_irr_percent: Decimal,  // rule-of-thumb estimate (rule of 72 / 114, else (MoM - 1) / years)
+    pub mental_math_irr_error: Decimal,  // estimate less exact, in percentage points
 
     pub key_assumptions: Vec<String>,
     pub mental_math_steps: Vec<String>,
 }
+
+/// Option Pricing Input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionPricingInput {
+    pub option_type: OptionType,
+    pub spot_price: Decimal,
+    pub strike_price: Decimal,
+    pub time_to_expiry: Decimal,  // years
+    pub risk_free_rate: Decimal,  // % continuously compounded
+    pub volatility: Decimal,  // % annualised
+    pub dividend_yield: Option<Decimal>,  // % continuous
+    pub exercise_style: Option<ExerciseStyle>,  // defaults to European
+    pub binomial_steps: Option<u32>,  // American only, defaults to 100
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+/// Option Pricing Output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionPricingOutput {
+    pub option_value: Decimal,
+    pub model: String,
+    pub intrinsic_value: Decimal,
+    pub time_value: Decimal,
+    pub european_value: Decimal,  // Black-Scholes-Merton reference value
+    pub early_exercise_premium: Decimal,
+    pub d1: Decimal,
+    pub d2: Decimal,
+    pub delta: Decimal,
+}
+
+/// Multi-year LBO Input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboInput {
+    // Entry
+    pub entry_revenue: Decimal,
+    pub entry_ebitda: Decimal,
+    pub entry_multiple: Decimal,
+    pub tranches: Vec<DebtTranche>,  // in order of seniority; the cash sweep follows this order
+    pub transaction_fees: Option<Decimal>,
+    pub financing_fee_percent: Option<Decimal>,  // % of debt raised
+    pub minimum_cash: Option<Decimal>,  // funded at close and held throughout
+
+    // Operating projections (one value per year)
+    pub revenue_growth: Vec<Decimal>,  // % per year
+    pub ebitda_margin: Vec<Decimal>,  // % of revenue
+    pub da_percent_revenue: Decimal,
+    pub capex_percent_revenue: Decimal,
+    pub nwc_percent_revenue: Decimal,
+    pub tax_rate: Decimal,  // %
+
+    pub cash_sweep_percent: Option<Decimal>,  // % of excess cash, defaults to 100
+    pub interest_income_rate: Option<Decimal>,  // % on average cash
+    pub average_balance_interest: Option<bool>,  // defaults to true; false uses opening balances
+    pub exit_multiple: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtTranche {
+    pub name: String,
+    pub amount: Decimal,
+    pub cash_rate: Decimal,  // % per annum, paid in cash
+    pub pik_rate: Option<Decimal>,  // % per annum on the opening balance, accrued to principal
+    pub amortization_percent: Option<Decimal>,  // mandatory repayment, % of original principal per year
+    pub cash_sweep: Option<bool>,  // eligible for the excess cash sweep, defaults to true
+}
+
+/// Multi-year LBO Output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboOutput {
+    pub enterprise_value: Decimal,
+    pub total_debt: Decimal,
+    pub financing_fees: Decimal,
+    pub transaction_fees: Decimal,
+    pub minimum_cash: Decimal,
+    pub sponsor_equity: Decimal,
+    pub entry_leverage: Decimal,
+    pub years: Vec<LboYear>,
+    pub exits: Vec<LboExit>,  // returns for an exit at the end of each projection year
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboYear {
+    pub year: u32,
+    pub revenue: Decimal,
+    pub ebitda: Decimal,
+    pub depreciation_amortization: Decimal,
+    pub ebit: Decimal,
+    pub cash_interest: Decimal,
+    pub pik_interest: Decimal,
+    pub interest_income: Decimal,
+    pub ebt: Decimal,
+    pub tax: Decimal,
+    pub net_income: Decimal,
+    pub capex: Decimal,
+    pub change_in_nwc: Decimal,
+    pub free_cash_flow: Decimal,  // available for debt repayment
+    pub mandatory_repayment: Decimal,
+    pub sweep_repayment: Decimal,
+    pub ending_cash: Decimal,
+    pub tranches: Vec<TrancheYear>,
+    pub total_debt: Decimal,
+    pub net_debt: Decimal,
+    pub leverage: Decimal,  // total debt / EBITDA
+    pub interest_iterations: u32,  // iterations to solve the average-balance circularity
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrancheYear {
+    pub name: String,
+    pub opening_balance: Decimal,
+    pub cash_interest: Decimal,
+    pub pik_interest: Decimal,
+    pub mandatory_repayment: Decimal,
+    pub sweep_repayment: Decimal,
+    pub closing_balance: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboExit {
+    pub year: u32,
+    pub exit_ebitda: Decimal,
+    pub enterprise_value: Decimal,
+    pub net_debt: Decimal,
+    pub equity_value: Decimal,
+    pub moic: Decimal,
+    pub irr: Option<Decimal>,  // %, None when the equity is wiped out
+}
+
+/// LBO target-return solver input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboTargetInput {
+    pub lbo: LboInput,
+    pub solve_for: LboSolveVariable,
+    pub target_metric: ReturnMetric,
+    pub target_value: Decimal,  // IRR in %, or MOIC as a multiple
+    pub exit_year: Option<u32>,  // defaults to the last projection year
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LboSolveVariable {
+    MaxEntryMultiple,
+    MinExitMultiple,
+    RequiredGrowth,  // constant annual revenue (and, at fixed margins, EBITDA) growth in %
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnMetric {
+    Irr,
+    Moic,
+}
+
+/// LBO target-return solver output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboTargetOutput {
+    pub solve_for: LboSolveVariable,
+    pub solved_value: Decimal,
+    pub exit_year: u32,
+    pub irr: Option<Decimal>,
+    pub moic: Decimal,
+    pub sponsor_equity: Decimal,
+    pub exit_equity_value: Decimal,
+    pub iterations: u32,
+}
+
+/// LBO sensitivity input; a grid is produced for each pair of axes supplied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboSensitivityInput {
+    pub lbo: LboInput,
+    pub exit_year: Option<u32>,  // defaults to the last projection year
+    pub entry_multiples: Option<Vec<Decimal>>,
+    pub exit_multiples: Option<Vec<Decimal>>,
+    pub leverage_multiples: Option<Vec<Decimal>>,  // total debt / entry EBITDA, tranches scaled pro rata
+    pub growth_rates: Option<Vec<Decimal>>,  // constant annual revenue growth in %
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LboSensitivityOutput {
+    pub exit_year: u32,
+    pub entry_exit: Option<SensitivityGrid>,
+    pub leverage_growth: Option<SensitivityGrid>,
+}
+
+/// Two-way grid: `irr[row][column]` and `moic[row][column]`, None where the
+/// structure is infeasible (e.g. debt exceeds the purchase price) or equity is wiped out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityGrid {
+    pub row_label: String,
+    pub column_label: String,
+    pub rows: Vec<Decimal>,
+    pub columns: Vec<Decimal>,
+    pub irr: Vec<Vec<Option<Decimal>>>,
+    pub moic: Vec<Vec<Option<Decimal>>>,
+}
@@ -10,6 +10,7 @@ use corp_finance_core::{
     build_three_statement_model, equity_enterprise_bridge,
     calculate_diluted_shares, analyze_accounting_flow,
     create_football_field, calculate_paper_lbo,
+    render_football_field_svg, render_football_field_html,
 
     // Phase 2 types
     fundamentals::{
@@ -17,7 +18,7 @@ use corp_finance_core::{
         DilutedSharesInput, AccountingFlowInput,
     },
     valuation::{
-        FootballFieldInput, PaperLboInput,
+        FootballFieldInput, FootballFieldChartInput, PaperLboInput,
     },
 };
 
@@ -143,6 +144,24 @@ pub fn football_field_valuation(input: String) -> Result<String> {
         .map_err(|e| Error::from_reason(format!("Serialization error: {}", e)))
 }
 
+#[napi]
+pub fn football_field_svg(input: String) -> Result<String> {
+    let input: FootballFieldChartInput = serde_json::from_str(&input)
+        .map_err(|e| Error::from_reason(format!("Invalid input: {}", e)))?;
+
+    render_football_field_svg(input)
+        .map_err(|e| Error::from_reason(format!("Rendering error: {}", e)))
+}
+
+#[napi]
+pub fn football_field_html(input: String) -> Result<String> {
+    let input: FootballFieldChartInput = serde_json::from_str(&input)
+        .map_err(|e| Error::from_reason(format!("Invalid input: {}", e)))?;
+
+    render_football_field_html(input)
+        .map_err(|e| Error::from_reason(format!("Rendering error: {}", e)))
+}
+
 #[napi]
 pub fn paper_lbo_calc(input: String) -> Result<String> {
     let input: PaperLboInput = serde_json::from_str(&input)
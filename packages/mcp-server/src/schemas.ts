@@ -46,6 +46,29 @@ export const CovenantInputSchema = z.object({
 
 // ========== Phase 2 Schemas ==========
 
+export const TaxDepreciationInputSchema = z.object({
+  method: z.enum(['macrs', 'schedule']),
+  recovery_period: z.number().optional(),
+  bonus_percent: z.string().or(z.number()).optional(),
+  existing_assets: z.array(z.string().or(z.number())).optional(),
+  schedule: z.array(z.string().or(z.number())).optional(),
+});
+
+export const IntangibleAdditionSchema = z.object({
+  year: z.number(),
+  amount: z.string().or(z.number()),
+  useful_life: z.number(),
+});
+
+export const FixedAssetInputSchema = z.object({
+  method: z.enum(['straight_line', 'declining_balance']),
+  useful_life: z.number(),
+  declining_balance_rate: z.string().or(z.number()).optional(),
+  existing_ppe_remaining_life: z.number().optional(),
+  intangibles_remaining_life: z.number().optional(),
+  intangible_additions: z.array(IntangibleAdditionSchema).optional(),
+});
+
 export const ThreeStatementInputSchema = z.object({
   starting_cash: z.string().or(z.number()),
   starting_debt: z.string().or(z.number()),
@@ -59,9 +82,56 @@ export const ThreeStatementInputSchema = z.object({
   opex_percent: z.string().or(z.number()),
   tax_rate: z.string().or(z.number()),
   capex: z.array(z.string().or(z.number())),
-  depreciation: z.array(z.string().or(z.number())),
+  depreciation: z.array(z.string().or(z.number())).optional(),
   nwc_percent_revenue: z.string().or(z.number()),
   interest_rate: z.string().or(z.number()),
+  cogs_percent_by_year: z.array(z.string().or(z.number())).optional(),
+  opex_percent_by_year: z.array(z.string().or(z.number())).optional(),
+  sbc_percent_by_year: z.array(z.string().or(z.number())).optional(),
+  sbc_percent: z.string().or(z.number()).optional(),
+  amortization: z.array(z.string().or(z.number())).optional(),
+  starting_intangibles: z.string().or(z.number()).optional(),
+  other_income: z.array(z.string().or(z.number())).optional(),
+  interest_income_rate: z.string().or(z.number()).optional(),
+  equity_method_income: z.array(z.string().or(z.number())).optional(),
+  starting_equity_investments: z.string().or(z.number()).optional(),
+  nci_percent: z.string().or(z.number()).optional(),
+  starting_nci: z.string().or(z.number()).optional(),
+  preferred_dividends: z.array(z.string().or(z.number())).optional(),
+  starting_nol: z.string().or(z.number()).optional(),
+  nol_annual_limit: z.string().or(z.number()).optional(),
+  nol_income_limit_percent: z.string().or(z.number()).optional(),
+  starting_basic_shares: z.string().or(z.number()).optional(),
+  dilutive_shares: z.string().or(z.number()).optional(),
+  dividend_payout_ratio: z.string().or(z.number()).optional(),
+  dividends_per_share: z.array(z.string().or(z.number())).optional(),
+  buyback_amount: z.array(z.string().or(z.number())).optional(),
+  buyback_percent_fcf: z.string().or(z.number()).optional(),
+  buyback_share_price: z.array(z.string().or(z.number())).optional(),
+  tax_depreciation: TaxDepreciationInputSchema.optional(),
+  starting_deferred_tax_liability: z.string().or(z.number()).optional(),
+  fixed_assets: FixedAssetInputSchema.optional(),
+});
+
+export const BridgeAdjustmentSchema = z.object({
+  name: z.string(),
+  adjustment_type: z.enum([
+    'operating_lease',
+    'pension_deficit',
+    'convertible_debt',
+    'contingent_consideration',
+    'other_debt_like',
+    'trapped_cash',
+    'non_operating_assets',
+    'other_cash_like',
+    'tax_assets',
+  ]),
+  book_value: z.string().or(z.number()),
+  market_value: z.string().or(z.number()).optional(),
+  valuation_basis: z.enum(['book', 'market']).optional(),
+  tax_rate: z.string().or(z.number()).optional(),
+  lease_standard: z.enum(['ifrs16', 'asc842']).optional(),
+  convertible_treatment: z.enum(['debt', 'equity']).optional(),
 });
 
 export const EquityEnterpriseBridgeInputSchema = z.object({
@@ -72,24 +142,129 @@ export const EquityEnterpriseBridgeInputSchema = z.object({
   minority_interest: z.string().or(z.number()),
   associates: z.string().or(z.number()),
   preferred_stock: z.string().or(z.number()),
+  adjustments: z.array(BridgeAdjustmentSchema).optional(),
+  verify_roundtrip: z.boolean().optional(),
 });
 
 export const OptionGrantSchema = z.object({
   quantity: z.string().or(z.number()),
   strike_price: z.string().or(z.number()),
+  vested_quantity: z.string().or(z.number()).optional(),
+  accelerates_on_change_of_control: z.boolean().optional(),
+  time_to_expiry: z.string().or(z.number()).optional(),
+});
+
+export const WarrantSchema = z.object({
+  quantity: z.string().or(z.number()),
+  exercise_price: z.string().or(z.number()),
+  shares_per_warrant: z.string().or(z.number()).optional(),
+});
+
+export const PerformanceShareUnitSchema = z.object({
+  target_units: z.string().or(z.number()),
+  achievement_percent: z.string().or(z.number()),
+  probability_percent: z.string().or(z.number()).optional(),
+});
+
+export const MakeWholeTableSchema = z.object({
+  stock_prices: z.array(z.string().or(z.number())),
+  dates: z.array(z.string()),
+  additional_shares: z.array(z.array(z.string().or(z.number()))),
+});
+
+export const CallSpreadSchema = z.object({
+  lower_strike: z.string().or(z.number()).optional(),
+  cap_price: z.string().or(z.number()),
 });
 
 export const ConvertibleSchema = z.object({
   principal: z.string().or(z.number()),
   conversion_price: z.string().or(z.number()),
+  settlement: z.enum(['if_converted', 'net_share']).optional(),
+  make_whole: MakeWholeTableSchema.optional(),
+  make_whole_date: z.string().optional(),
+  call_spread: CallSpreadSchema.optional(),
+});
+
+export const OptionValuationAssumptionsSchema = z.object({
+  risk_free_rate: z.string().or(z.number()),
+  volatility: z.string().or(z.number()),
+  dividend_yield: z.string().or(z.number()).optional(),
+  time_to_expiry: z.string().or(z.number()),
+  exercise_style: z.enum(['european', 'american']).optional(),
+  binomial_steps: z.number().optional(),
 });
 
 export const DilutedSharesInputSchema = z.object({
   basic_shares: z.string().or(z.number()),
-  stock_price: z.string().or(z.number()),
+  stock_price: z.string().or(z.number()).optional(),
   options: z.array(OptionGrantSchema),
   rsus: z.string().or(z.number()),
   convertibles: z.array(ConvertibleSchema),
+  equity_purchase_price: z.string().or(z.number()).optional(),
+  warrants: z.array(WarrantSchema).optional(),
+  psus: z.array(PerformanceShareUnitSchema).optional(),
+  exercisable_only: z.boolean().optional(),
+  change_of_control: z.boolean().optional(),
+  option_valuation: OptionValuationAssumptionsSchema.optional(),
+});
+
+export const JournalLineSchema = z.object({
+  account: z.enum([
+    'cash',
+    'accounts_receivable',
+    'inventory',
+    'prepaid_expenses',
+    'ppe',
+    'intangibles',
+    'right_of_use_asset',
+    'deferred_tax_asset',
+    'accounts_payable',
+    'accrued_liabilities',
+    'deferred_revenue',
+    'tax_payable',
+    'debt',
+    'lease_liability',
+    'deferred_tax_liability',
+    'paid_in_capital',
+    'retained_earnings',
+    'revenue',
+    'cogs',
+    'operating_expenses',
+    'stock_based_compensation',
+    'depreciation_expense',
+    'amortization_expense',
+    'impairment_loss',
+    'interest_expense',
+    'gain_loss_on_sale',
+    'tax_expense',
+  ]),
+  debit: z.string().or(z.number()).optional(),
+  credit: z.string().or(z.number()).optional(),
+  cash_flow_line: z.enum([
+    'net_income',
+    'depreciation',
+    'amortization',
+    'impairment',
+    'stock_based_compensation',
+    'inventory_write_down',
+    'deferred_tax',
+    'change_in_receivables',
+    'change_in_inventory',
+    'change_in_prepaid_expenses',
+    'change_in_payables',
+    'change_in_accrued_liabilities',
+    'change_in_deferred_revenue',
+    'change_in_tax_payable',
+    'capital_expenditures',
+    'asset_sale_proceeds',
+    'intangibles_purchased',
+    'debt',
+    'lease_principal',
+    'equity_issuance',
+    'dividends',
+    'non_cash',
+  ]).optional(),
 });
 
 export const AccountingFlowInputSchema = z.object({
@@ -103,17 +278,56 @@ export const AccountingFlowInputSchema = z.object({
     'debt_repayment',
     'inventory_purchase',
     'revenue_recognition',
+    'accrued_expense',
+    'prepaid_expense',
+    'prepaid_expense_recognition',
+    'impairment',
+    'stock_based_compensation',
+    'inventory_write_down',
+    'deferred_revenue',
+    'deferred_revenue_recognition',
+    'asset_sale',
+    'lease_commencement',
+    'lease_payment',
+    'right_of_use_depreciation',
+    'custom',
   ]),
+  carrying_value: z.string().or(z.number()).optional(),
+  interest_portion: z.string().or(z.number()).optional(),
+  journal_entries: z.array(JournalLineSchema).optional(),
+  tax_rate: z.string().or(z.number()).optional(),
+  taxable_amount: z.string().or(z.number()).optional(),
+  permanent_difference: z.boolean().optional(),
+});
+
+export const MethodologyRangeSchema = z.object({
+  name: z.string(),
+  low: z.string().or(z.number()),
+  high: z.string().or(z.number()),
+  base: z.string().or(z.number()).optional(),
+  weight: z.string().or(z.number()).optional(),
 });
 
 export const FootballFieldInputSchema = z.object({
-  dcf_low: z.string().or(z.number()),
-  dcf_high: z.string().or(z.number()),
-  comps_low: z.string().or(z.number()),
-  comps_high: z.string().or(z.number()),
-  precedents_low: z.string().or(z.number()),
-  precedents_high: z.string().or(z.number()),
+  dcf_low: z.string().or(z.number()).optional(),
+  dcf_high: z.string().or(z.number()).optional(),
+  comps_low: z.string().or(z.number()).optional(),
+  comps_high: z.string().or(z.number()).optional(),
+  precedents_low: z.string().or(z.number()).optional(),
+  precedents_high: z.string().or(z.number()).optional(),
   current_price: z.string().or(z.number()).optional(),
+  methodologies: z.array(MethodologyRangeSchema).optional(),
+  fifty_two_week_low: z.string().or(z.number()).optional(),
+  fifty_two_week_high: z.string().or(z.number()).optional(),
+  analyst_targets: z.array(z.string().or(z.number())).optional(),
+  diluted_shares: z.string().or(z.number()).optional(),
+});
+
+export const InterimCashFlowSchema = z.object({
+  date: z.string(),
+  flow_type: z.enum(['dividend_recap', 'equity_injection', 'monitoring_fee']),
+  amount: z.string().or(z.number()),
+  ebitda_acquired: z.string().or(z.number()).optional(),
 });
 
 export const PaperLboInputSchema = z.object({
@@ -125,6 +339,8 @@ export const PaperLboInputSchema = z.object({
   hold_period_years: z.number(),
   exit_multiple: z.string().or(z.number()),
   interest_rate: z.string().or(z.number()),
+  entry_date: z.string().optional(),
+  interim_cash_flows: z.array(InterimCashFlowSchema).optional(),
 });
 
 // Type exports for TypeScript
@@ -19,11 +19,71 @@ export const PHASE2_TOOLS = [
         opex_percent: { type: ['number', 'string'], description: 'OpEx as % of revenue' },
         tax_rate: { type: ['number', 'string'], description: 'Tax rate %' },
         capex: { type: 'array', items: { type: ['number', 'string'] }, description: 'Annual CapEx' },
-        depreciation: { type: 'array', items: { type: ['number', 'string'] }, description: 'Annual depreciation' },
+        depreciation: { type: 'array', items: { type: ['number', 'string'] }, description: 'Annual depreciation; may be omitted when fixed_assets is given' },
         nwc_percent_revenue: { type: ['number', 'string'], description: 'NWC as % of revenue' },
         interest_rate: { type: ['number', 'string'], description: 'Interest rate % on debt' },
+        cogs_percent_by_year: { type: 'array', items: { type: ['number', 'string'] }, description: 'COGS % of revenue per year; replaces cogs_percent (optional)' },
+        opex_percent_by_year: { type: 'array', items: { type: ['number', 'string'] }, description: 'OpEx % of revenue per year; replaces opex_percent (optional)' },
+        sbc_percent_by_year: { type: 'array', items: { type: ['number', 'string'] }, description: 'Stock-based comp % of revenue per year; replaces sbc_percent (optional)' },
+        sbc_percent: { type: ['number', 'string'], description: 'Stock-based comp as % of revenue (optional)' },
+        amortization: { type: 'array', items: { type: ['number', 'string'] }, description: 'Amortisation of acquired intangibles (optional)' },
+        starting_intangibles: { type: ['number', 'string'], description: 'Starting intangibles (optional)' },
+        other_income: { type: 'array', items: { type: ['number', 'string'] }, description: 'Other income / (expense), pre-tax (optional)' },
+        interest_income_rate: { type: ['number', 'string'], description: 'Interest rate % earned on beginning cash (optional)' },
+        equity_method_income: { type: 'array', items: { type: ['number', 'string'] }, description: "Share of associates' profit, after tax (optional)" },
+        starting_equity_investments: { type: ['number', 'string'], description: 'Starting equity-method investments (optional)' },
+        nci_percent: { type: ['number', 'string'], description: '% of net income attributable to non-controlling interests (optional)' },
+        starting_nci: { type: ['number', 'string'], description: 'Starting non-controlling interests (optional)' },
+        preferred_dividends: { type: 'array', items: { type: ['number', 'string'] }, description: 'Preferred dividends (optional)' },
+        starting_nol: { type: ['number', 'string'], description: 'Starting net operating loss carryforward (optional)' },
+        nol_annual_limit: { type: ['number', 'string'], description: 'Absolute cap on NOL used per year (optional)' },
+        nol_income_limit_percent: { type: ['number', 'string'], description: 'Cap on NOL used as % of pre-NOL taxable income (optional)' },
+        starting_basic_shares: { type: ['number', 'string'], description: 'Starting basic shares; required for share counts, DPS and buybacks (optional)' },
+        dilutive_shares: { type: ['number', 'string'], description: 'Incremental diluted shares, e.g. from TSM (optional)' },
+        dividend_payout_ratio: { type: ['number', 'string'], description: '% of net income to common (optional)' },
+        dividends_per_share: { type: 'array', items: { type: ['number', 'string'] }, description: 'Dividends per share; alternative to the payout ratio (optional)' },
+        buyback_amount: { type: 'array', items: { type: ['number', 'string'] }, description: 'Fixed share repurchase programme (optional)' },
+        buyback_percent_fcf: { type: ['number', 'string'], description: 'Buybacks as % of FCF (CFO - capex); alternative to buyback_amount (optional)' },
+        buyback_share_price: { type: 'array', items: { type: ['number', 'string'] }, description: 'Assumed average repurchase price (optional)' },
+        tax_depreciation: {
+          type: 'object',
+          properties: {
+            method: { type: 'string', enum: ['macrs', 'schedule'], description: 'MACRS tables with bonus, or an explicit schedule' },
+            recovery_period: { type: 'number', description: 'macrs: class life of 3, 5, 7, 10, 15 or 20 years' },
+            bonus_percent: { type: ['number', 'string'], description: 'macrs: bonus depreciation % on new capex' },
+            existing_assets: { type: 'array', items: { type: ['number', 'string'] }, description: 'macrs: tax depreciation on the opening asset base, one value per year' },
+            schedule: { type: 'array', items: { type: ['number', 'string'] }, description: 'schedule: tax depreciation per year' },
+          },
+          required: ['method'],
+          description: 'Book vs tax depreciation for deferred tax; book depreciation stays as depreciation (optional)',
+        },
+        starting_deferred_tax_liability: { type: ['number', 'string'], description: 'Starting net DTL, negative for a net DTA (optional)' },
+        fixed_assets: {
+          type: 'object',
+          properties: {
+            method: { type: 'string', enum: ['straight_line', 'declining_balance'], description: 'Depreciation method for each capex vintage' },
+            useful_life: { type: 'number', description: 'Years, for each new capex vintage' },
+            declining_balance_rate: { type: ['number', 'string'], description: 'Declining balance rate %, default 200 (double-declining)' },
+            existing_ppe_remaining_life: { type: 'number', description: 'Years; required when starting PP&E is not zero' },
+            intangibles_remaining_life: { type: 'number', description: 'Years; omit for indefinite-lived intangibles' },
+            intangible_additions: {
+              type: 'array',
+              items: {
+                type: 'object',
+                properties: {
+                  year: { type: 'number', description: '1-based projection year' },
+                  amount: { type: ['number', 'string'] },
+                  useful_life: { type: 'number', description: 'Years, straight-line' },
+                },
+                required: ['year', 'amount', 'useful_life'],
+              },
+            },
+          },
+          required: ['method', 'useful_life'],
+          description: 'PP&E and intangibles waterfall; replaces depreciation and amortization (optional)',
+        },
       },
-      required: ['starting_cash', 'starting_debt', 'starting_equity', 'starting_inventory', 'starting_ar', 'starting_ap', 'starting_ppe', 'revenue', 'cogs_percent', 'opex_percent', 'tax_rate', 'capex', 'depreciation', 'nwc_percent_revenue', 'interest_rate'],
+      required: ['starting_cash', 'starting_debt', 'starting_equity', 'starting_inventory', 'starting_ar', 'starting_ap', 'starting_ppe', 'revenue', 'cogs_percent', 'opex_percent', 'tax_rate', 'capex', 'nwc_percent_revenue', 'interest_rate'],
     },
   },
   {
@@ -39,18 +99,40 @@ export const PHASE2_TOOLS = [
         minority_interest: { type: ['number', 'string'], description: 'Minority interest' },
         associates: { type: ['number', 'string'], description: 'Associates/investments' },
         preferred_stock: { type: ['number', 'string'], description: 'Preferred stock' },
+        adjustments: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              name: { type: 'string' },
+              adjustment_type: {
+                type: 'string',
+                enum: ['operating_lease', 'pension_deficit', 'convertible_debt', 'contingent_consideration', 'other_debt_like', 'trapped_cash', 'non_operating_assets', 'other_cash_like', 'tax_assets'],
+              },
+              book_value: { type: ['number', 'string'] },
+              market_value: { type: ['number', 'string'], description: 'Used when valuation_basis is market' },
+              valuation_basis: { type: 'string', enum: ['book', 'market'], description: 'Default book' },
+              tax_rate: { type: ['number', 'string'], description: 'Tax rate % for pensions, trapped cash and tax assets' },
+              lease_standard: { type: 'string', enum: ['ifrs16', 'asc842'], description: 'Default IFRS 16; ASC 842 leases are excluded' },
+              convertible_treatment: { type: 'string', enum: ['debt', 'equity'], description: 'Default debt; equity excludes it from the bridge' },
+            },
+            required: ['name', 'adjustment_type', 'book_value'],
+          },
+          description: 'Typed debt-like and cash-like bridge items: leases, pensions, convertibles and others (optional)',
+        },
+        verify_roundtrip: { type: 'boolean', description: 'Run the bridge back the other way and fail if it does not tie (optional)' },
       },
       required: ['direction', 'value', 'cash', 'debt', 'minority_interest', 'associates', 'preferred_stock'],
     },
   },
   {
     name: 'diluted_shares',
-    description: 'Calculate fully diluted shares using treasury stock method for options, RSUs, and convertibles',
+    description: 'Calculate fully diluted shares for options (with vesting and acceleration), RSUs, PSUs, warrants and convertibles, optionally solving the share price from a total equity purchase price',
     inputSchema: {
       type: 'object',
       properties: {
         basic_shares: { type: ['number', 'string'], description: 'Basic shares outstanding' },
-        stock_price: { type: ['number', 'string'], description: 'Current stock price' },
+        stock_price: { type: ['number', 'string'], description: 'Current stock price; required unless equity_purchase_price is given, and not allowed with it' },
         options: {
           type: 'array',
           items: {
@@ -58,6 +140,9 @@ export const PHASE2_TOOLS = [
             properties: {
               quantity: { type: ['number', 'string'] },
               strike_price: { type: ['number', 'string'] },
+              vested_quantity: { type: ['number', 'string'], description: 'Defaults to fully vested' },
+              accelerates_on_change_of_control: { type: 'boolean' },
+              time_to_expiry: { type: ['number', 'string'], description: 'Remaining term in years, for fair-value dilution' },
             },
             required: ['quantity', 'strike_price'],
           },
@@ -71,13 +156,78 @@ export const PHASE2_TOOLS = [
             properties: {
               principal: { type: ['number', 'string'] },
               conversion_price: { type: ['number', 'string'] },
+              settlement: { type: 'string', enum: ['if_converted', 'net_share'], description: 'Default if_converted' },
+              make_whole: {
+                type: 'object',
+                properties: {
+                  stock_prices: { type: 'array', items: { type: ['number', 'string'] }, description: 'Ascending' },
+                  dates: { type: 'array', items: { type: 'string' }, description: 'Ascending (YYYY-MM-DD)' },
+                  additional_shares: {
+                    type: 'array',
+                    items: { type: 'array', items: { type: ['number', 'string'] } },
+                    description: 'Additional shares per 1,000 of principal: one row per date, one column per stock price',
+                  },
+                },
+                required: ['stock_prices', 'dates', 'additional_shares'],
+              },
+              make_whole_date: { type: 'string', description: 'Effective date of the make-whole event (YYYY-MM-DD)' },
+              call_spread: {
+                type: 'object',
+                properties: {
+                  lower_strike: { type: ['number', 'string'], description: 'Defaults to the conversion price' },
+                  cap_price: { type: ['number', 'string'] },
+                },
+                required: ['cap_price'],
+              },
             },
             required: ['principal', 'conversion_price'],
           },
           description: 'Convertible securities',
         },
+        equity_purchase_price: { type: ['number', 'string'], description: 'Total offer value; solves for the per-share price instead of using stock_price (optional)' },
+        warrants: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              quantity: { type: ['number', 'string'] },
+              exercise_price: { type: ['number', 'string'], description: 'Paid per warrant, not per share delivered' },
+              shares_per_warrant: { type: ['number', 'string'], description: 'Defaults to 1' },
+            },
+            required: ['quantity', 'exercise_price'],
+          },
+          description: 'Warrants (optional)',
+        },
+        psus: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              target_units: { type: ['number', 'string'] },
+              achievement_percent: { type: ['number', 'string'], description: '% of target earned, e.g. 150 for maximum payout' },
+              probability_percent: { type: ['number', 'string'], description: 'Likelihood of vesting, defaults to 100' },
+            },
+            required: ['target_units', 'achievement_percent'],
+          },
+          description: 'Performance share units (optional)',
+        },
+        exercisable_only: { type: 'boolean', description: 'Count vested (or accelerated) options only (optional)' },
+        change_of_control: { type: 'boolean', description: 'Trigger acceleration on grants that carry it (optional)' },
+        option_valuation: {
+          type: 'object',
+          properties: {
+            risk_free_rate: { type: ['number', 'string'], description: 'Risk-free rate %' },
+            volatility: { type: ['number', 'string'], description: 'Volatility %' },
+            dividend_yield: { type: ['number', 'string'], description: 'Dividend yield %' },
+            time_to_expiry: { type: ['number', 'string'], description: 'Years, where a grant has no term of its own' },
+            exercise_style: { type: 'string', enum: ['european', 'american'], description: 'American uses a binomial tree' },
+            binomial_steps: { type: 'number' },
+          },
+          required: ['risk_free_rate', 'volatility', 'time_to_expiry'],
+          description: 'Also report fair-value dilution of unvested options (optional)',
+        },
       },
-      required: ['basic_shares', 'stock_price', 'options', 'rsus', 'convertibles'],
+      required: ['basic_shares', 'options', 'rsus', 'convertibles'],
     },
   },
   {
@@ -90,28 +240,77 @@ export const PHASE2_TOOLS = [
         amount: { type: ['number', 'string'], description: 'Transaction amount' },
         transaction_type: {
           type: 'string',
-          enum: ['depreciation', 'amortization', 'capex', 'debt_issuance', 'debt_repayment', 'inventory_purchase', 'revenue_recognition'],
-          description: 'Type of transaction',
+          enum: ['depreciation', 'amortization', 'capex', 'debt_issuance', 'debt_repayment', 'inventory_purchase', 'revenue_recognition', 'accrued_expense', 'prepaid_expense', 'prepaid_expense_recognition', 'impairment', 'stock_based_compensation', 'inventory_write_down', 'deferred_revenue', 'deferred_revenue_recognition', 'asset_sale', 'lease_commencement', 'lease_payment', 'right_of_use_depreciation', 'custom'],
+          description: 'Standard transaction from the library, or custom to post journal_entries',
         },
+        carrying_value: { type: ['number', 'string'], description: 'asset_sale: book value of the asset sold' },
+        interest_portion: { type: ['number', 'string'], description: 'lease_payment: part of the payment that is interest' },
+        journal_entries: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              account: {
+                type: 'string',
+                enum: ['cash', 'accounts_receivable', 'inventory', 'prepaid_expenses', 'ppe', 'intangibles', 'right_of_use_asset', 'deferred_tax_asset', 'accounts_payable', 'accrued_liabilities', 'deferred_revenue', 'tax_payable', 'debt', 'lease_liability', 'deferred_tax_liability', 'paid_in_capital', 'retained_earnings', 'revenue', 'cogs', 'operating_expenses', 'stock_based_compensation', 'depreciation_expense', 'amortization_expense', 'impairment_loss', 'interest_expense', 'gain_loss_on_sale', 'tax_expense'],
+              },
+              debit: { type: ['number', 'string'] },
+              credit: { type: ['number', 'string'] },
+              cash_flow_line: {
+                type: 'string',
+                enum: ['net_income', 'depreciation', 'amortization', 'impairment', 'stock_based_compensation', 'inventory_write_down', 'deferred_tax', 'change_in_receivables', 'change_in_inventory', 'change_in_prepaid_expenses', 'change_in_payables', 'change_in_accrued_liabilities', 'change_in_deferred_revenue', 'change_in_tax_payable', 'capital_expenditures', 'asset_sale_proceeds', 'intangibles_purchased', 'debt', 'lease_principal', 'equity_issuance', 'dividends', 'non_cash'],
+                description: "Overrides the account's default cash flow line",
+              },
+            },
+            required: ['account'],
+          },
+          description: 'custom: balanced debits and credits to post',
+        },
+        tax_rate: { type: ['number', 'string'], description: 'Tax rate %, defaults to 0 (pre-tax view)' },
+        taxable_amount: { type: ['number', 'string'], description: 'Effect on taxable income this period, if different from book' },
+        permanent_difference: { type: 'boolean', description: 'Book/tax difference never reverses, so no deferred tax' },
       },
       required: ['transaction', 'amount', 'transaction_type'],
     },
   },
   {
     name: 'football_field',
-    description: 'Create football field valuation summary across multiple methodologies (DCF, Comps, Precedents)',
+    description: 'Create football field valuation summary across any number of named methodologies, with 52-week range and analyst target reference bars',
     inputSchema: {
       type: 'object',
       properties: {
-        dcf_low: { type: ['number', 'string'], description: 'DCF valuation low end' },
-        dcf_high: { type: ['number', 'string'], description: 'DCF valuation high end' },
-        comps_low: { type: ['number', 'string'], description: 'Comps valuation low end' },
-        comps_high: { type: ['number', 'string'], description: 'Comps valuation high end' },
-        precedents_low: { type: ['number', 'string'], description: 'Precedents valuation low end' },
-        precedents_high: { type: ['number', 'string'], description: 'Precedents valuation high end' },
+        dcf_low: { type: ['number', 'string'], description: 'DCF valuation low end per share (optional, legacy)' },
+        dcf_high: { type: ['number', 'string'], description: 'DCF valuation high end per share (optional, legacy)' },
+        comps_low: { type: ['number', 'string'], description: 'Comps valuation low end per share (optional, legacy)' },
+        comps_high: { type: ['number', 'string'], description: 'Comps valuation high end per share (optional, legacy)' },
+        precedents_low: { type: ['number', 'string'], description: 'Precedents valuation low end per share (optional, legacy)' },
+        precedents_high: { type: ['number', 'string'], description: 'Precedents valuation high end per share (optional, legacy)' },
         current_price: { type: ['number', 'string'], description: 'Current price (optional)', required: false },
+        methodologies: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              name: { type: 'string' },
+              low: { type: ['number', 'string'] },
+              high: { type: ['number', 'string'] },
+              base: { type: ['number', 'string'], description: 'Point estimate, defaults to the midpoint' },
+              weight: { type: ['number', 'string'], description: 'Relative weight in the blended range; give for every methodology or none (equal weights), and not alongside the legacy pairs' },
+            },
+            required: ['name', 'low', 'high'],
+          },
+          description: 'Named valuation methodologies (optional)',
+        },
+        fifty_two_week_low: { type: ['number', 'string'], description: '52-week low share price (optional)' },
+        fifty_two_week_high: { type: ['number', 'string'], description: '52-week high share price (optional)' },
+        analyst_targets: {
+          type: 'array',
+          items: { type: ['number', 'string'] },
+          description: 'Analyst price targets per share (optional)',
+        },
+        diluted_shares: { type: ['number', 'string'], description: 'Converts methodologies given as equity values to per share; required to combine them with the legacy pairs, reference bars or current_price (optional)' },
       },
-      required: ['dcf_low', 'dcf_high', 'comps_low', 'comps_high', 'precedents_low', 'precedents_high'],
+      required: [],
     },
   },
   {
@@ -128,6 +327,21 @@ export const PHASE2_TOOLS = [
         hold_period_years: { type: 'number', description: 'Hold period in years' },
         exit_multiple: { type: ['number', 'string'], description: 'Exit EBITDA multiple' },
         interest_rate: { type: ['number', 'string'], description: 'Interest rate % on debt' },
+        entry_date: { type: 'string', description: 'Entry date (YYYY-MM-DD), required with interim cash flows' },
+        interim_cash_flows: {
+          type: 'array',
+          items: {
+            type: 'object',
+            properties: {
+              date: { type: 'string', description: 'Date (YYYY-MM-DD) between entry and exit' },
+              flow_type: { type: 'string', enum: ['dividend_recap', 'equity_injection', 'monitoring_fee'] },
+              amount: { type: ['number', 'string'] },
+              ebitda_acquired: { type: ['number', 'string'], description: 'EBITDA added by an add-on acquisition' },
+            },
+            required: ['date', 'flow_type', 'amount'],
+          },
+          description: 'Dividend recaps, add-on equity injections and monitoring fees; returns use XIRR (optional)',
+        },
       },
       required: ['purchase_price', 'ebitda', 'entry_multiple', 'debt_multiple', 'ebitda_growth_rate', 'hold_period_years', 'exit_multiple', 'interest_rate'],
     },